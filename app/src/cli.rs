@@ -0,0 +1,301 @@
+//! Command line interface for maintenance tasks
+//! Without arguments the display is started.
+//! The database can only be opened by one process, so stop the display before running a command.
+//! While it runs, the same tasks are available through the dashboard.
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+
+use anyhow::{Context, Error};
+use common::models::ImportConflict;
+
+use crate::shared::bundle::ConfigBundle;
+
+pub const USAGE: &str = "Usage:
+  app [OPTIONS]                                     Start the display
+  app [OPTIONS] export [--embed-components] [FILE]  Export the configuration, to stdout if no file is given
+  app [OPTIONS] import [--grant-permissions] FILE   Import a configuration exported by another display,
+                                                    granting widgets the permissions granted on it
+
+Options:
+  --data-dir DIR  Directory of the database and the secrets key.
+                  Defaults to $WG_DISPLAY_DATA_DIR, $XDG_DATA_HOME/wg_display or ~/.local/share/wg_display
+  --config FILE   TOML or YAML file to configure the display with, applied whenever it changes.
+                  Defaults to $WG_DISPLAY_CONFIG or config.toml, config.yaml or config.yml in the data directory";
+
+/// The parsed command line
+#[derive(Debug, PartialEq)]
+pub struct Arguments {
+    /// The data directory given with `--data-dir`
+    pub data_dir: Option<PathBuf>,
+    /// The configuration file given with `--config`
+    pub config_file: Option<PathBuf>,
+    pub command: Command,
+}
+
+/// A command given on the command line
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    /// Start the display
+    Run,
+    Export {
+        embed_components: bool,
+        path: Option<PathBuf>,
+    },
+    Import {
+        /// Grant the widgets the permissions they were granted on the exporting display
+        grant_permissions: bool,
+        path: PathBuf,
+    },
+}
+
+/// Parse the command line arguments
+/// # Arguments
+/// * `args` - The arguments without the name of the executable
+/// # Returns
+/// The arguments or an error describing the invalid ones
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Arguments, String> {
+    let mut args = args.into_iter().peekable();
+    let mut data_dir = None;
+    let mut config_file = None;
+    // Options of all commands precede the command
+    while let Some(arg) = args.next_if(|arg| arg.starts_with("--")) {
+        let (option, path) = match arg.split_once('=') {
+            Some((option, path)) => (option.to_string(), path.to_string()),
+            None => {
+                let path = args
+                    .next()
+                    .ok_or_else(|| format!("Missing path of {}", arg))?;
+                (arg, path)
+            }
+        };
+        match option.as_str() {
+            "--data-dir" => data_dir = Some(PathBuf::from(path)),
+            "--config" => config_file = Some(PathBuf::from(path)),
+            _ => return Err(format!("Unknown option {}", option)),
+        }
+    }
+    let command = parse_command(args)?;
+    Ok(Arguments {
+        data_dir,
+        config_file,
+        command,
+    })
+}
+
+fn parse_command(mut args: impl Iterator<Item = String>) -> Result<Command, String> {
+    let Some(command) = args.next() else {
+        return Ok(Command::Run);
+    };
+    match command.as_str() {
+        "export" => {
+            let mut embed_components = false;
+            let mut path = None;
+            for arg in args {
+                match arg.as_str() {
+                    "--embed-components" => embed_components = true,
+                    _ if arg.starts_with('-') => return Err(format!("Unknown option {}", arg)),
+                    _ if path.is_none() => path = Some(PathBuf::from(arg)),
+                    _ => return Err(format!("Unexpected argument {}", arg)),
+                }
+            }
+            Ok(Command::Export {
+                embed_components,
+                path,
+            })
+        }
+        "import" => {
+            let mut grant_permissions = false;
+            let mut path = None;
+            for arg in args {
+                match arg.as_str() {
+                    "--grant-permissions" => grant_permissions = true,
+                    _ if arg.starts_with('-') => return Err(format!("Unknown option {}", arg)),
+                    _ if path.is_none() => path = Some(PathBuf::from(arg)),
+                    _ => return Err(format!("Unexpected argument {}", arg)),
+                }
+            }
+            Ok(Command::Import {
+                grant_permissions,
+                path: path.ok_or("Missing bundle file")?,
+            })
+        }
+        _ => Err(format!("Unknown command {}", command)),
+    }
+}
+
+/// Execute a maintenance command
+/// # Arguments
+/// * `command` - The command, [Command::Run] is handled by the caller
+pub async fn execute(command: Command) -> Result<(), Error> {
+    match command {
+        Command::Run => Ok(()),
+        Command::Export {
+            embed_components,
+            path,
+        } => {
+            let bundle = ConfigBundle::export(embed_components)?;
+            let bundle = serde_json::to_vec_pretty(&bundle)?;
+            match path {
+                Some(path) => fs::write(&path, bundle)
+                    .with_context(|| format!("Could not write {}", path.display())),
+                None => Ok(std::io::stdout().write_all(&bundle)?),
+            }
+        }
+        Command::Import {
+            grant_permissions,
+            path,
+        } => {
+            let bytes =
+                fs::read(&path).with_context(|| format!("Could not read {}", path.display()))?;
+            let bundle = ConfigBundle::parse(&bytes)?;
+            let approved: Vec<String> = if grant_permissions {
+                bundle
+                    .widgets
+                    .iter()
+                    .map(|widget| widget.name.clone())
+                    .collect()
+            } else {
+                Vec::new()
+            };
+            let report = bundle.import(&approved).await?;
+            for widget_name in &report.installed {
+                println!("Installed {}", widget_name);
+            }
+            for conflict in &report.conflicts {
+                println!("{}", describe_conflict(conflict));
+            }
+            for rejected in &report.rejected_configs {
+                println!("Configuration not restored: {}", rejected);
+            }
+            Ok(())
+        }
+    }
+}
+
+fn describe_conflict(conflict: &ImportConflict) -> String {
+    match conflict {
+        ImportConflict::AlreadyInstalled {
+            widget_name,
+            installed_version,
+        } => format!(
+            "Skipped {}: version {} is installed already",
+            widget_name, installed_version
+        ),
+        ImportConflict::ApprovalRequired {
+            widget_name,
+            permissions,
+        } => {
+            let hosts = permissions
+                .http
+                .as_ref()
+                .map(|http| http.hosts.join(", "))
+                .unwrap_or_default();
+            format!(
+                "Skipped {}: it may send HTTP requests to {}, import with --grant-permissions to grant it",
+                widget_name, hosts
+            )
+        }
+        ImportConflict::PermissionsChanged { widget_name, .. } => format!(
+            "Skipped {}: it requests other permissions, install it on the dashboard to approve them",
+            widget_name
+        ),
+        ImportConflict::NoSource { widget_name } => {
+            format!("Skipped {}: the bundle does not contain it", widget_name)
+        }
+        ImportConflict::Failed { widget_name, error } => {
+            format!("Could not install {}: {}", widget_name, error)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    fn command(args: &[&str]) -> Result<Command, String> {
+        parse(self::args(args)).map(|arguments| arguments.command)
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(command(&[]), Ok(Command::Run));
+        assert_eq!(
+            command(&["export"]),
+            Ok(Command::Export {
+                embed_components: false,
+                path: None
+            })
+        );
+        assert_eq!(
+            command(&["export", "--embed-components", "display.json"]),
+            Ok(Command::Export {
+                embed_components: true,
+                path: Some(PathBuf::from("display.json"))
+            })
+        );
+        assert_eq!(
+            command(&["import", "display.json"]),
+            Ok(Command::Import {
+                grant_permissions: false,
+                path: PathBuf::from("display.json")
+            })
+        );
+        assert_eq!(
+            command(&["import", "--grant-permissions", "display.json"]),
+            Ok(Command::Import {
+                grant_permissions: true,
+                path: PathBuf::from("display.json")
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_options() {
+        assert_eq!(
+            parse(args(&[
+                "--data-dir",
+                "/srv/display",
+                "import",
+                "display.json"
+            ])),
+            Ok(Arguments {
+                data_dir: Some(PathBuf::from("/srv/display")),
+                config_file: None,
+                command: Command::Import {
+                    grant_permissions: false,
+                    path: PathBuf::from("display.json")
+                }
+            })
+        );
+        assert_eq!(
+            parse(args(&[
+                "--config",
+                "display.toml",
+                "--data-dir=/srv/display"
+            ])),
+            Ok(Arguments {
+                data_dir: Some(PathBuf::from("/srv/display")),
+                config_file: Some(PathBuf::from("display.toml")),
+                command: Command::Run
+            })
+        );
+        assert_eq!(parse(args(&[])).unwrap().data_dir, None);
+        assert!(parse(args(&["--data-dir"])).is_err());
+        assert!(parse(args(&["--data-directory", "/srv"])).is_err());
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(command(&["import"]).is_err());
+        assert!(command(&["import", "a.json", "b.json"]).is_err());
+        assert!(command(&["import", "--grant", "a.json"]).is_err());
+        assert!(command(&["export", "--all"]).is_err());
+        assert!(command(&["export", "a.json", "b.json"]).is_err());
+        assert!(command(&["serve"]).is_err());
+    }
+}
@@ -1,6 +1,8 @@
 //! The WG Display main crate holding everything together.
 //! This crate is the entry point for the application.
 //! It starts the server to serve the frontend and an API to fetch and modify the configuration.
+use std::io::IsTerminal;
+
 use tokio::signal;
 
 #[macro_use]
@@ -8,6 +10,7 @@ extern crate rocket;
 #[macro_use]
 extern crate lazy_static;
 
+mod cli;
 mod renderer;
 mod server;
 pub mod shared;
@@ -16,6 +19,52 @@ mod widgets;
 #[forbid(unsafe_code)]
 #[tokio::main]
 async fn main() {
+    let arguments = match cli::parse(std::env::args().skip(1)) {
+        Ok(arguments) => arguments,
+        Err(err) => {
+            eprintln!("{}\n\n{}", err, cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    // The renderer draws on the terminal, logging to it would garble the display.
+    // Widget logs can be viewed on the dashboard in any case.
+    if arguments.command == cli::Command::Run
+        && (!std::io::stdout().is_terminal() || std::env::var_os("RUST_LOG").is_some())
+    {
+        simple_logger::SimpleLogger::new()
+            .with_level(log::LevelFilter::Info)
+            .env()
+            .init()
+            .expect("Could not initialize logger");
+    }
+
+    let data_dir = match shared::data_dir::init(arguments.data_dir) {
+        Ok(data_dir) => data_dir,
+        Err(err) => {
+            eprintln!("Could not create data directory: {}", err);
+            std::process::exit(1);
+        }
+    };
+    if let Err(err) = shared::persistence::Persistence::init(data_dir) {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+
+    if arguments.command != cli::Command::Run {
+        if let Err(err) = cli::execute(arguments.command).await {
+            eprintln!("{:#}", err);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    shared::config_file::watch(arguments.config_file, data_dir);
+    // Warns about a seed for the random values right away instead of on the first widget run
+    lazy_static::initialize(&widgets::running::runtime::RANDOM_SEED);
+    // Widgets compiled by a previous version of WG Display can not be loaded until they are recompiled
+    shared::widget_manager::WidgetManager::recompile_outdated_widgets().await;
+
     // Create a channel for shutdown signaling
     let (shutdown_tx, mut shutdown_rx) = tokio::sync::broadcast::channel(1);
     let shutdown_rx_server = shutdown_tx.subscribe();
@@ -1,9 +1,10 @@
 //! Widgets to display rendering implementation using [Cursive](https://crates.io/crates/cursive)
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::sync::broadcast;
 
-use common::models::SystemConfiguration;
+use common::models::{LogLevel, LogSource, SystemConfiguration, WidgetInstallationData};
 use cursive::theme::BaseColor;
 use cursive::theme::Color;
 use cursive::theme::Color::Dark;
@@ -18,59 +19,140 @@ use cursive::{CursiveRunnable, CursiveRunner};
 use crate::shared::persistence::Persistence;
 
 use crate::shared::widget_manager::WidgetManager;
+use crate::widgets::running::http_client::shared_http_client;
 use crate::widgets::running::runtime::Runtime;
-use crate::widgets::running::runtime::Widget;
+use crate::widgets::running::runtime::WidgetInstance;
+use crate::widgets::running::runtime::{PushedUpdate, RunMode};
+use crate::widgets::running::streaming::StreamingWidget;
+use crate::widgets::utils::logging::LOGGING_PREFIX;
+use crate::widgets::utils::widget_logs::WidgetLogs;
+
+/// Longest time between two rendering steps
+const MAX_STEP_INTERVAL: Duration = Duration::from_millis(1000);
 
 struct WasmWidget {
     name: String,
-    widget: Widget,
+    widget: WidgetInstance,
+    /// Every widget has its own runtime, so its store can be dropped when it is instantiated again
+    runtime: Runtime,
+    /// Streaming widgets are run on their own thread and push their updates
+    streaming: bool,
+    /// Content of the last successful run, shown as stale while the widget fails
+    last_good: Option<String>,
+}
+
+impl WasmWidget {
+    /// Compute the content to display after a run
+    /// # Args
+    /// * `result` - The data of the widget or the error if the run failed
+    /// # Returns
+    /// The data, the last good data marked as stale or the error if the widget never succeeded
+    fn content(&mut self, result: Result<String, String>) -> String {
+        match result {
+            Ok(data) => {
+                self.last_good = Some(data.clone());
+                data
+            }
+            Err(err) => match &self.last_good {
+                Some(data) => format!(
+                    "{}\n(stale: {})",
+                    data,
+                    err.lines().next().unwrap_or_default()
+                ),
+                None => err,
+            },
+        }
+    }
 }
 
 pub struct Renderer {
     widgets: Vec<WasmWidget>,
-    runtime: Runtime,
+    /// Dropping the handles stops the streaming widgets
+    streaming_widgets: Vec<StreamingWidget>,
+    updates: Receiver<PushedUpdate>,
 }
 
 // Renders the widget on the display using the [Cursive](https://crates.io/crates/cursive) crate
 impl Renderer {
     pub fn new() -> Self {
-        let mut runtime = Runtime::new();
         Self {
-            widgets: Renderer::initialize_widgets(&mut runtime),
-            runtime,
+            widgets: vec![],
+            streaming_widgets: vec![],
+            updates: mpsc::channel().1,
         }
     }
 
-    fn initialize_widgets(runtime: &mut Runtime) -> Vec<WasmWidget> {
-        let mut widgets = vec![];
+    /// Instantiates all installed widgets and starts the ones run in streaming mode
+    /// Streaming widgets of a previous configuration are stopped
+    /// # Args
+    /// * `config` - The system configuration
+    fn initialize_widgets(&mut self, config: &SystemConfiguration) {
+        let (updates_tx, updates_rx) = mpsc::channel();
+        self.updates = updates_rx;
+        self.streaming_widgets.clear();
+        self.widgets.clear();
         for widget in WidgetManager::get_widgets() {
-            let widget = runtime.instantiate_widget(&widget);
-            if let Ok(widget) = widget {
-                let name = runtime
-                    .get_widget_name(&widget)
-                    .expect("Could not get widget name");
-
-                widgets.push(WasmWidget { name, widget });
+            let mut runtime = Runtime::new();
+            runtime.apply_system_config(config);
+            let widget = match runtime.instantiate_widget(&widget) {
+                Ok(widget) => widget,
+                Err(err) => {
+                    log::error!(
+                        "{}: Could not instantiate widget: {:#}",
+                        LOGGING_PREFIX,
+                        err
+                    );
+                    continue;
+                }
+            };
+            let name = widget.name().to_owned();
+            let streaming = matches!(runtime.get_run_mode(&widget), Ok(RunMode::Streaming));
+            if streaming {
+                self.streaming_widgets.push(StreamingWidget::spawn(
+                    name.clone(),
+                    config.clone(),
+                    updates_tx.clone(),
+                ));
             }
+
+            self.widgets.push(WasmWidget {
+                name,
+                widget,
+                runtime,
+                streaming,
+                last_good: None,
+            });
         }
-        widgets
     }
 
     /// Runs the renderer (blocking)
     pub fn run(&mut self, mut shutdown_rx: broadcast::Receiver<()>) {
         let mut siv = cursive::default().into_runner();
-        let mut config = Persistence::get_system_config().expect("Could not load config");
+        let mut config = Persistence::global().get_system_config().unwrap_or_else(|err| {
+            log::error!("{}: Could not load config, using defaults: {}", LOGGING_PREFIX, err);
+            SystemConfiguration::default()
+        });
+        shared_http_client().set_cache_enabled(!config.disable_http_cache);
+        self.initialize_widgets(&config);
         self.initialize_layout(&config, &mut siv);
 
         loop {
             if let Ok(_) = shutdown_rx.try_recv() {
                 println!("Renderer received shutdown signal");
+                self.streaming_widgets.clear();
                 break;
             }
 
-            if let Some(new_config) = Persistence::get_system_config_change() {
+            let config_change = Persistence::global()
+                .get_system_config_change()
+                .unwrap_or_else(|err| {
+                    log::error!("{}: Could not load changed config: {}", LOGGING_PREFIX, err);
+                    None
+                });
+            if let Some(new_config) = config_change {
                 config = new_config;
-                self.widgets = Renderer::initialize_widgets(&mut self.runtime);
+                shared_http_client().set_cache_enabled(!config.disable_http_cache);
+                self.initialize_widgets(&config);
                 self.initialize_layout(&config, &mut siv)
             }
 
@@ -78,7 +160,52 @@ impl Renderer {
             siv.step();
             siv.refresh();
 
-            thread::sleep(Duration::from_millis(1000));
+            self.wait_for_updates(&mut siv);
+        }
+    }
+
+    /// Waits until the next polled widget is due, showing updates pushed by streaming widgets in the meantime
+    /// # Args
+    /// * `siv` - The cursive instance
+    fn wait_for_updates(&mut self, siv: &mut CursiveRunner<CursiveRunnable>) {
+        let deadline = Instant::now()
+            + self
+                .widgets
+                .iter()
+                .filter_map(|widget| widget.runtime.next_due())
+                .min()
+                .map_or(MAX_STEP_INTERVAL, |due| {
+                    due.saturating_duration_since(Instant::now())
+                })
+                .min(MAX_STEP_INTERVAL);
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match self.updates.recv_timeout(remaining) {
+                Ok(update) => {
+                    let Some(widget) = self
+                        .widgets
+                        .iter_mut()
+                        .find(|widget| widget.name == update.widget_name)
+                    else {
+                        continue;
+                    };
+                    let content = widget.content(if update.failed {
+                        Err(update.data)
+                    } else {
+                        Ok(update.data)
+                    });
+                    siv.call_on_name(update.widget_name.as_str(), |view: &mut TextView| {
+                        view.set_content(content);
+                    });
+                    siv.refresh();
+                }
+                Err(RecvTimeoutError::Timeout) => break,
+                // No streaming widgets are running
+                Err(RecvTimeoutError::Disconnected) => {
+                    thread::sleep(remaining);
+                    break;
+                }
+            }
         }
     }
 
@@ -127,19 +254,48 @@ impl Renderer {
     /// * `siv` - The cursive instance
     /// * `config` - The widget configuration
     fn update_widgets(&mut self, siv: &mut CursiveRunner<CursiveRunnable>) {
-        self.widgets.iter_mut().for_each(|widget| {
-            let widget_config = Persistence::get_widget_config(widget.name.as_str());
-            let widget_config = widget_config.unwrap_or("{}".to_string());
+        self.widgets.iter_mut().filter(|widget| !widget.streaming).for_each(|widget| {
+            let installation_data = Persistence::global()
+                .get_installation_data(widget.name.as_str())
+                .ok()
+                .flatten()
+                .unwrap_or_else(|| WidgetInstallationData {
+                    name: widget.name.clone(),
+                    json_config: "{}".to_string(),
+                    ..Default::default()
+                });
 
-            let res = self.runtime.run_widget(&widget.widget, &widget_config);
+            let res = widget.runtime.run_widget(&widget.widget, &installation_data);
             let res = match res {
-                Ok(res) => res.map(|res| res.data),
-                Err(err) => Some(err.to_string()),
+                Ok(res) => res.map(|res| Ok(res.data)),
+                Err(err) => {
+                    WidgetLogs::record(
+                        &widget.name,
+                        LogLevel::Error,
+                        LogSource::Host,
+                        &format!("Run failed: {:#}", err),
+                    );
+                    // A widget that trapped can not be called again, the store of its instance is dropped
+                    widget.runtime.reset_store();
+                    let instance = WidgetManager::get_widget(&widget.name)
+                        .and_then(|compiled| widget.runtime.instantiate_widget(&compiled));
+                    match instance {
+                        Ok(instance) => widget.widget = instance,
+                        Err(err) => log::error!(
+                            "{}: Could not instantiate widget {} again: {:#}",
+                            LOGGING_PREFIX,
+                            widget.name,
+                            err
+                        ),
+                    }
+                    Some(Err(err.to_string()))
+                }
             };
 
-            if let Some(data) = res {
+            if let Some(res) = res {
+                let content = widget.content(res);
                 siv.call_on_name(widget.name.as_str(), |view: &mut TextView| {
-                    view.set_content(data);
+                    view.set_content(content);
                 });
             }
         });
@@ -1,6 +1,8 @@
 //! Serves the frontend files and provides an API to fetch and modify the configuration.
+use jiff::tz::TimeZone;
 use rocket::config::Config;
-use rocket::http::ContentType;
+use rocket::data::{Data, ToByteUnit};
+use rocket::http::{ContentType, Header};
 use rocket::response::content::RawHtml;
 use rocket::response::status::Custom;
 use rocket::serde::json;
@@ -8,15 +10,28 @@ use rust_embed::RustEmbed;
 use tokio::sync::broadcast;
 
 use std::borrow::Cow;
+use std::collections::HashMap;
 use std::ffi::OsStr;
 use std::path::PathBuf;
 
-use common::models::{InstallAction, SystemConfiguration, WidgetStoreItem};
+use common::config_schema;
+use common::models::{
+    ConfigChange, ConfigFieldError, ConfigFileStatus, ConfigRevision, ImportReport, InstallAction,
+    InstallRequest, InstallResponse, LogLevel, QuarantinedEntry, RateLimit, SystemConfiguration,
+    TestRunReport, WidgetHealth, WidgetLogEntry, WidgetPermissions,
+};
 
-use crate::shared::persistence::Persistence;
+use crate::shared::bundle::ConfigBundle;
+use crate::shared::config_file;
+use crate::shared::persistence::{Persistence, PersistenceError};
+use crate::shared::revisions;
+use crate::shared::secrets::Secrets;
 use crate::shared::widget_manager::WidgetManager;
-use crate::widgets::running::runtime::Runtime;
+use crate::widgets::running::health::HealthRegistry;
+use crate::widgets::running::http_client::shared_http_client;
+use crate::widgets::running::runtime;
 use crate::widgets::store::widget_store::WidgetStore;
+use crate::widgets::utils::widget_logs::WidgetLogs;
 
 /// Contains the frontend files
 /// They are embedded using the [RustEmbed](https://crates.io/crates/rust-embed) crate
@@ -24,6 +39,24 @@ use crate::widgets::store::widget_store::WidgetStore;
 #[folder = "../frontend/dist"]
 struct Asset;
 
+impl From<PersistenceError> for Custom<String> {
+    fn from(err: PersistenceError) -> Self {
+        log::error!("{}", err);
+        Custom(rocket::http::Status::InternalServerError, err.to_string())
+    }
+}
+
+/// Fails with 404 if the widget is not installed
+fn require_installed(widget_name: &str) -> Result<(), Custom<String>> {
+    if Persistence::global().get_installation_data(widget_name)?.is_none() {
+        return Err(Custom(
+            rocket::http::Status::NotFound,
+            format!("Widget {} is not installed", widget_name),
+        ));
+    }
+    Ok(())
+}
+
 /// Returns the list of widgets available in the store
 #[get("/get_store_items")]
 async fn get_store_items() -> Result<json::Value, Custom<String>> {
@@ -39,12 +72,19 @@ async fn get_store_items() -> Result<json::Value, Custom<String>> {
 }
 
 /// Install a new widget
-#[post("/install_widget", format = "json", data = "<action>")]
-async fn install_widget(action: json::Json<InstallAction>) -> Result<(), Custom<String>> {
+/// Answers with the permissions the widget requests if they have not been approved yet
+#[post("/install_widget", format = "json", data = "<request>")]
+async fn install_widget(
+    request: json::Json<InstallRequest>,
+) -> Result<json::Json<InstallResponse>, Custom<String>> {
+    let InstallRequest {
+        action,
+        granted_permissions,
+    } = request.into_inner();
     let mut description = "No description".to_string();
-    let download_url = match action {
-        json::Json(InstallAction::FromUrl(url)) => url,
-        json::Json(InstallAction::FromStoreItemName(name)) => {
+    let download_url = match action.clone() {
+        InstallAction::FromUrl(url) => url,
+        InstallAction::FromStoreItemName(name) => {
             let mut store = WidgetStore::new();
             let res = store.fetch_from_store().await;
             if let Err(err) = res {
@@ -53,19 +93,36 @@ async fn install_widget(action: json::Json<InstallAction>) -> Result<(), Custom<
                     format!("Could not update store: {}", err),
                 ));
             }
-            let item: &WidgetStoreItem = store
-                .get_items()
-                .iter()
-                .find(|item: &&WidgetStoreItem| item.name == name)
-                .unwrap();
+            let Some(item) = store.find_item(&name) else {
+                return Err(Custom(
+                    rocket::http::Status::NotFound,
+                    format!("Widget {} does not exist in the store", name),
+                ));
+            };
+            if !item.supports_host_version(env!("CARGO_PKG_VERSION")) {
+                return Err(Custom(
+                    rocket::http::Status::UnprocessableEntity,
+                    format!(
+                        "Widget {} requires WG Display {} or newer",
+                        name,
+                        item.min_host_version.clone().unwrap_or_default()
+                    ),
+                ));
+            }
             description = item.description.clone();
             item.get_download_url()
         }
     };
     log::info!("Installing widget from URL {}", download_url);
-    let result = WidgetManager::install_widget(download_url.as_str(), description.as_str()).await;
+    let result = WidgetManager::install_widget(
+        download_url.as_str(),
+        description.as_str(),
+        action,
+        granted_permissions.as_ref(),
+    )
+    .await;
     match result {
-        Ok(_) => Ok(()),
+        Ok(response) => Ok(json::Json(response)),
         Err(err) => {
             let err = format!(
                 "Could not install widget from URL {}: {}",
@@ -93,49 +150,434 @@ async fn deinstall_widget(widget_name: &str) -> Result<(), Custom<String>> {
 /// Returns the configuration schema of a widget
 #[get("/config_schema/<widget_name>")]
 fn get_config_schema(widget_name: &str) -> Option<String> {
-    let mut runtime = Runtime::new();
-    let compiled_widget = WidgetManager::get_widget(widget_name);
-    let Ok(compiled_widget) = compiled_widget else {
-        println!("Could not load compiled widget");
-        return None;
-    };
+    match WidgetManager::get_config_schema(widget_name) {
+        Ok(schema) => Some(schema),
+        Err(err) => {
+            log::error!("Could not get config schema of widget {}: {}", widget_name, err);
+            None
+        }
+    }
+}
 
-    let widget = runtime.instantiate_widget(&compiled_widget);
-    let Ok(widget) = widget else {
-        println!("Could not instantiate widget");
-        return None;
-    };
+/// Returns the permissions a widget requests
+/// Used to approve the permissions of widgets installed before widgets had to request them
+#[get("/widgets/<widget_name>/requested_permissions")]
+fn get_requested_permissions(
+    widget_name: &str,
+) -> Result<json::Json<WidgetPermissions>, Custom<String>> {
+    require_installed(widget_name)?;
+    WidgetManager::get_requested_permissions(widget_name)
+        .map(json::Json)
+        .map_err(|err| {
+            Custom(
+                rocket::http::Status::InternalServerError,
+                format!("Could not get requested permissions: {}", err),
+            )
+        })
+}
 
-    let schema = runtime.get_config_schema(&widget);
-    let Ok(schema) = schema else {
-        println!("Could not get config schema");
-        return None;
-    };
-    Some(schema)
+/// Grants a widget the permissions it requests
+/// Fails with 409 if the approved permissions are not the ones the widget requests
+#[post("/widgets/<widget_name>/approve_permissions", format = "json", data = "<permissions>")]
+fn approve_permissions(
+    widget_name: &str,
+    permissions: json::Json<WidgetPermissions>,
+) -> Result<(), Custom<String>> {
+    require_installed(widget_name)?;
+    let approved = WidgetManager::approve_permissions(widget_name, &permissions).map_err(|err| {
+        Custom(
+            rocket::http::Status::InternalServerError,
+            format!("Could not approve permissions: {}", err),
+        )
+    })?;
+    if !approved {
+        return Err(Custom(
+            rocket::http::Status::Conflict,
+            "The widget requests other permissions, reload the page to review them".to_string(),
+        ));
+    }
+    Ok(())
 }
 
-/// Saves the system configuration
+/// Saves the system settings of the configuration
+/// The widgets are kept as stored, they are (de)installed and configured through their own endpoints.
 #[post("/system_config", format = "json", data = "<config>")]
-async fn save_system_config(config: json::Json<SystemConfiguration>) {
-    Persistence::save_system_config(config.into_inner());
+async fn save_system_config(config: json::Json<SystemConfiguration>) -> Result<(), Custom<String>> {
+    let config = config.into_inner();
+    if let Some(location) = &config.location {
+        if !location.is_valid() {
+            return Err(Custom(
+                rocket::http::Status::UnprocessableEntity,
+                "Latitude must be within ±90° and longitude within ±180°".to_string(),
+            ));
+        }
+    }
+    if let Some(timezone) = &config.timezone {
+        if TimeZone::get(timezone).is_err() {
+            return Err(Custom(
+                rocket::http::Status::UnprocessableEntity,
+                format!("Unknown timezone {}", timezone),
+            ));
+        }
+    }
+    Persistence::global().update_system_config(|current| current.set_system_settings(config))?;
+    Ok(())
+}
+
+/// Grants or revokes the scratch directory of a widget
+#[post("/widgets/<widget_name>/scratch_directory", format = "json", data = "<enabled>")]
+fn save_scratch_directory(
+    widget_name: &str,
+    enabled: json::Json<bool>,
+) -> Result<(), Custom<String>> {
+    require_installed(widget_name)?;
+    Persistence::global().update_system_config(|config| {
+        for widget in config.widgets.iter_mut() {
+            if widget.name == widget_name {
+                widget.wasi_grants.scratch_directory = *enabled;
+            }
+        }
+    })?;
+    Ok(())
+}
+
+/// Sets or removes the limit of the HTTP requests of a widget
+#[post("/widgets/<widget_name>/http_rate_limit", format = "json", data = "<rate_limit>")]
+fn save_http_rate_limit(
+    widget_name: &str,
+    rate_limit: json::Json<Option<RateLimit>>,
+) -> Result<(), Custom<String>> {
+    require_installed(widget_name)?;
+    if let Some(limit) = rate_limit.as_ref() {
+        if limit.requests == 0 || limit.per_seconds == 0 {
+            return Err(Custom(
+                rocket::http::Status::UnprocessableEntity,
+                "Requests and seconds of the rate limit must be positive".to_string(),
+            ));
+        }
+    }
+    Persistence::global().update_system_config(|config| {
+        for widget in config.widgets.iter_mut() {
+            if widget.name == widget_name {
+                widget.http_rate_limit = rate_limit.clone();
+            }
+        }
+    })?;
+    shared_http_client().forget_widget(widget_name);
+    Ok(())
+}
+
+/// Why a widget config was rejected
+#[derive(Responder)]
+enum WidgetConfigError {
+    /// The config does not match the configuration schema of the widget
+    #[response(status = 422, content_type = "json")]
+    Invalid(json::Json<Vec<ConfigFieldError>>),
+    Failed(Custom<String>),
+}
+
+impl From<Custom<String>> for WidgetConfigError {
+    fn from(err: Custom<String>) -> Self {
+        WidgetConfigError::Failed(err)
+    }
+}
+
+impl From<PersistenceError> for WidgetConfigError {
+    fn from(err: PersistenceError) -> Self {
+        WidgetConfigError::Failed(err.into())
+    }
+}
+
+/// Validates a widget config against the configuration schema of the widget
+/// # Returns
+/// The config, normalized to compact JSON
+fn validate_widget_config(widget_name: &str, config: &str) -> Result<String, WidgetConfigError> {
+    require_installed(widget_name)?;
+    let schema = WidgetManager::get_config_schema(widget_name).map_err(|err| {
+        Custom(
+            rocket::http::Status::InternalServerError,
+            format!("Could not get config schema: {}", err),
+        )
+    })?;
+    config_schema::validate_str(&schema, config)
+        .map(|config| config.to_string())
+        .map_err(|errors| WidgetConfigError::Invalid(json::Json(errors)))
 }
 
 /// Saves a widget config
+/// Answers with the invalid fields if the config does not match the configuration schema of the widget
 #[post("/widget_config/<widget_name>", data = "<config>")]
-async fn save_widget_config(widget_name: &str, config: String) {
-    let mut system_config = Persistence::get_system_config().unwrap();
-    system_config.widgets.iter_mut().for_each(|widget| {
-        if widget.name == widget_name {
-            widget.json_config = config.clone();
-        }
-    });
-    Persistence::save_system_config(system_config);
+async fn save_widget_config(
+    widget_name: &str,
+    config: String,
+) -> Result<(), WidgetConfigError> {
+    let config = validate_widget_config(widget_name, &config)?;
+    Persistence::global().update_system_config(|system_config| {
+        system_config.widgets.iter_mut().for_each(|widget| {
+            if widget.name == widget_name {
+                widget.json_config = config.clone();
+            }
+        });
+    })?;
+    Ok(())
+}
+
+/// Runs a widget once with a config without saving it
+/// Answers with the invalid fields if the config does not match the configuration schema of the widget
+#[post("/widgets/<widget_name>/test_run", data = "<config>")]
+async fn test_run_widget(
+    widget_name: &str,
+    config: String,
+) -> Result<json::Json<TestRunReport>, WidgetConfigError> {
+    let config = validate_widget_config(widget_name, &config)?;
+    let widget_name = widget_name.to_owned();
+    // Widgets block while they run, e.g. on HTTP requests
+    let report = tokio::task::spawn_blocking(move || WidgetManager::test_run(&widget_name, config))
+        .await
+        .map_err(|err| err.to_string())
+        .and_then(|report| report.map_err(|err| format!("{:#}", err)))
+        .map_err(|err| {
+            Custom(
+                rocket::http::Status::InternalServerError,
+                format!("Could not run widget: {}", err),
+            )
+        })?;
+    Ok(json::Json(report))
+}
+
+/// Returns the names of the secrets of a widget
+/// The values can not be read back through the API
+#[get("/secrets/<widget_name>")]
+fn get_secret_names(widget_name: &str) -> Result<json::Json<Vec<String>>, Custom<String>> {
+    require_installed(widget_name)?;
+    Secrets::global().get_secret_names(widget_name)
+        .map(json::Json)
+        .map_err(|err| {
+            Custom(
+                rocket::http::Status::InternalServerError,
+                format!("Could not load secrets: {}", err),
+            )
+        })
+}
+
+/// Saves a secret of a widget
+#[post("/secrets/<widget_name>/<secret_name>", data = "<value>")]
+fn save_secret(
+    widget_name: &str,
+    secret_name: &str,
+    value: String,
+) -> Result<(), Custom<String>> {
+    require_installed(widget_name)?;
+    Secrets::global().set_secret(widget_name, secret_name, &value).map_err(|err| {
+        Custom(
+            rocket::http::Status::InternalServerError,
+            format!("Could not save secret: {}", err),
+        )
+    })
+}
+
+/// Removes a secret of a widget
+#[delete("/secrets/<widget_name>/<secret_name>")]
+fn remove_secret(widget_name: &str, secret_name: &str) -> Result<(), Custom<String>> {
+    require_installed(widget_name)?;
+    Secrets::global().remove_secret(widget_name, secret_name).map_err(|err| {
+        Custom(
+            rocket::http::Status::InternalServerError,
+            format!("Could not remove secret: {}", err),
+        )
+    })
+}
+
+/// Returns the health of all widgets that ran since the display was started
+#[get("/widgets/status")]
+fn get_widgets_status() -> json::Json<HashMap<String, WidgetHealth>> {
+    json::Json(HealthRegistry::get_all())
+}
+
+/// Returns the recent log lines of a widget, oldest first
+/// Covers the widget's own logs, its stdout/stderr and errors reported by the host
+#[get("/widgets/<widget_name>/logs?<min_level>")]
+fn get_widget_logs(
+    widget_name: &str,
+    min_level: Option<&str>,
+) -> Result<json::Json<Vec<WidgetLogEntry>>, Custom<String>> {
+    let min_level = match min_level {
+        Some(level) => level
+            .parse()
+            .map_err(|err| Custom(rocket::http::Status::BadRequest, err))?,
+        None => LogLevel::Debug,
+    };
+    Ok(json::Json(WidgetLogs::get(widget_name, min_level)))
 }
 
 /// Returns the system configuration
 #[get("/system_config")]
-fn get_system_config() -> Option<json::Value> {
-    Some(json::json!(Persistence::get_system_config()))
+fn get_system_config() -> Result<json::Value, Custom<String>> {
+    Ok(json::json!(Persistence::global().get_system_config()?))
+}
+
+/// Returns the stored values that could not be decoded and were quarantined
+/// The display replaced them with defaults, e.g. widgets have to be reinstalled
+#[get("/persistence/problems")]
+fn get_persistence_problems() -> Result<json::Json<Vec<QuarantinedEntry>>, Custom<String>> {
+    Ok(json::Json(Persistence::global().get_quarantined_entries()?))
+}
+
+/// Dismisses the reported persistence problems, deleting the quarantined values
+#[delete("/persistence/problems")]
+fn clear_persistence_problems() -> Result<(), Custom<String>> {
+    Ok(Persistence::global().clear_quarantine()?)
+}
+
+/// Returns the seed of the random values handed to widgets, `null` if they are unpredictable
+/// The dashboard warns about a seed, as widgets can't keep secrets with it
+#[get("/random_seed")]
+fn get_random_seed() -> json::Json<Option<u64>> {
+    json::Json(*runtime::RANDOM_SEED)
+}
+
+/// Returns the state of the configuration file, `null` if the display is not configured by a file
+#[get("/config/file")]
+fn get_config_file_status() -> json::Json<Option<ConfigFileStatus>> {
+    json::Json(config_file::status())
+}
+
+/// Returns the kept revisions of the system configuration, newest first
+#[get("/config/revisions")]
+fn get_config_revisions() -> Result<json::Json<Vec<ConfigRevision>>, Custom<String>> {
+    Ok(json::Json(Persistence::global().get_config_revisions()?))
+}
+
+fn load_config_revision(id: u64) -> Result<SystemConfiguration, Custom<String>> {
+    Persistence::global().get_config_revision(id)?.ok_or_else(|| {
+        Custom(
+            rocket::http::Status::NotFound,
+            format!("Revision {} does not exist", id),
+        )
+    })
+}
+
+/// Returns the changes from a revision to another revision or, by default, the current configuration
+#[get("/config/revisions/<id>/diff?<to>")]
+fn diff_config_revision(
+    id: u64,
+    to: Option<u64>,
+) -> Result<json::Json<Vec<ConfigChange>>, Custom<String>> {
+    let old = load_config_revision(id)?;
+    let new = match to {
+        Some(to) => load_config_revision(to)?,
+        None => Persistence::global().get_system_config()?,
+    };
+    Ok(json::Json(revisions::diff(&old, &new)))
+}
+
+/// Validates the widget configs that differ from the current ones against the configuration schemas of the widgets
+/// # Arguments
+/// * `current` - The current configuration
+/// * `changed` - The configuration about to be saved
+/// # Returns
+/// 422 naming the fields that do not match, e.g. as the widget was upgraded since
+fn validate_changed_widget_configs(
+    current: &SystemConfiguration,
+    changed: &SystemConfiguration,
+) -> Result<(), Custom<String>> {
+    let errors: Vec<String> = changed
+        .widgets
+        .iter()
+        .filter(|widget| {
+            !current.widgets.iter().any(|current| {
+                current.name == widget.name && current.json_config == widget.json_config
+            })
+        })
+        .filter_map(|widget| {
+            WidgetManager::validate_config(&widget.name, &widget.json_config)
+                .err()
+                .map(|err| format!("{:#}", err))
+        })
+        .collect();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(Custom(
+            rocket::http::Status::UnprocessableEntity,
+            errors.join("\n"),
+        ))
+    }
+}
+
+/// Restores the settings of a revision
+/// Widgets are not installed or deinstalled, see [revisions::restore].
+/// Nothing is restored if a widget config of the revision does not match the installed version of the widget.
+#[post("/config/revisions/<id>/restore")]
+fn restore_config_revision(id: u64) -> Result<(), Custom<String>> {
+    let revision = load_config_revision(id)?;
+    Persistence::global().try_update_system_config(|current| {
+        let restored = revisions::restore(current, revision);
+        validate_changed_widget_configs(current, &restored)?;
+        *current = restored;
+        Ok::<(), Custom<String>>(())
+    })
+}
+
+/// Largest bundle that can be imported, bundles can embed the components of the widgets
+const MAX_BUNDLE_MIB: usize = 64;
+
+/// Bundle offered as file download
+#[derive(Responder)]
+#[response(content_type = "json")]
+struct BundleDownload {
+    bundle: String,
+    disposition: Header<'static>,
+}
+
+/// Exports the configuration of the display as bundle, see [ConfigBundle]
+/// Secrets are not exported
+#[get("/config/export?<embed_components>")]
+fn export_config(embed_components: Option<bool>) -> Result<BundleDownload, Custom<String>> {
+    let bundle = ConfigBundle::export(embed_components.unwrap_or(false))?;
+    let bundle = json::serde_json::to_string(&bundle).map_err(|err| {
+        Custom(
+            rocket::http::Status::InternalServerError,
+            format!("Could not serialize bundle: {}", err),
+        )
+    })?;
+    Ok(BundleDownload {
+        bundle,
+        disposition: Header::new(
+            "Content-Disposition",
+            "attachment; filename=\"wg_display_config.json\"",
+        ),
+    })
+}
+
+/// Imports a bundle exported by another display
+/// Installs its widgets and restores their configuration and the system settings.
+/// Widgets granted permissions on the exporting display are only installed if listed in `approve`,
+/// the others are reported, so the user can review their permissions and import the bundle again.
+#[post("/config/import?<approve>", data = "<bundle>")]
+async fn import_config(
+    bundle: Data<'_>,
+    approve: Vec<String>,
+) -> Result<json::Json<ImportReport>, Custom<String>> {
+    let bytes = bundle
+        .open(MAX_BUNDLE_MIB.mebibytes())
+        .into_bytes()
+        .await
+        .map_err(|err| Custom(rocket::http::Status::BadRequest, err.to_string()))?;
+    if !bytes.is_complete() {
+        return Err(Custom(
+            rocket::http::Status::PayloadTooLarge,
+            format!("Bundles can be at most {} MiB", MAX_BUNDLE_MIB),
+        ));
+    }
+    let bundle = ConfigBundle::parse(&bytes)
+        .map_err(|err| Custom(rocket::http::Status::UnprocessableEntity, format!("{:#}", err)))?;
+    bundle.import(&approve).await.map(json::Json).map_err(|err| {
+        Custom(
+            rocket::http::Status::InternalServerError,
+            format!("Could not import bundle: {:#}", err),
+        )
+    })
 }
 
 /// Serves index.html
@@ -145,14 +587,11 @@ async fn index() -> Option<RawHtml<Cow<'static, [u8]>>> {
     Some(RawHtml(asset.data))
 }
 
-/// Serves a configuration page for a widget
-#[get("/widget_configuration/<widget_name>")]
-async fn widget_configuration(widget_name: &str) -> Option<RawHtml<Cow<'static, [u8]>>> {
-    let asset = Asset::get("assets/html/widget_config.html")?;
-    let html = String::from_utf8(asset.data.to_vec()).unwrap();
-    let html = html.replace("{{WIDGET_NAME}}", widget_name);
-    let data = Cow::from(html.into_bytes());
-    Some(RawHtml(data))
+/// Serves index.html for the configuration page of a widget
+/// The page is rendered by the frontend, this allows opening it directly
+#[get("/widget_configuration/<_>")]
+async fn widget_configuration() -> Option<RawHtml<Cow<'static, [u8]>>> {
+    index().await
 }
 
 //// Serves the frontend files (WASM, JS, HTML, CSS, etc.)
@@ -184,12 +623,31 @@ pub async fn serve_dashboard(mut shutdown_rx: broadcast::Receiver<()>) -> Result
                 widget_configuration,
                 dist,
                 save_system_config,
+                save_scratch_directory,
+                save_http_rate_limit,
                 save_widget_config,
+                test_run_widget,
                 get_system_config,
                 get_config_schema,
+                get_requested_permissions,
+                approve_permissions,
                 install_widget,
                 deinstall_widget,
-                get_store_items
+                get_store_items,
+                get_secret_names,
+                save_secret,
+                remove_secret,
+                get_widget_logs,
+                get_widgets_status,
+                get_persistence_problems,
+                clear_persistence_problems,
+                get_config_file_status,
+                get_random_seed,
+                export_config,
+                import_config,
+                get_config_revisions,
+                diff_config_revision,
+                restore_config_revision
             ],
         )
         .ignite()
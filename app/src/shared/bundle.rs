@@ -0,0 +1,306 @@
+//! Export and import of the configuration of a display as a single file
+//!
+//! A bundle holds the system configuration and where every widget was installed from,
+//! optionally with the widget components embedded, so a display can be cloned without network access.
+//! Secrets and the storage of widgets are not exported.
+use std::time::SystemTime;
+
+use anyhow::{Context, Error, anyhow, bail};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use common::models::{
+    ImportConflict, ImportReport, InstallAction, InstallResponse, SystemConfiguration,
+    WidgetInstallationData,
+};
+use rocket::serde::json::serde_json::{self, Value};
+use serde::{Deserialize, Serialize};
+
+use super::migrations;
+use super::persistence::{Persistence, PersistenceError};
+use super::widget_manager::WidgetManager;
+use crate::widgets::store::widget_store::WidgetStore;
+
+/// Version of the bundle format written by this version of WG Display
+const BUNDLE_FORMAT_VERSION: u32 = 1;
+
+/// Configuration of a display, see the [module](self) documentation
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+pub struct ConfigBundle {
+    pub format_version: u32,
+    /// Version of WG Display that created the bundle
+    pub host_version: String,
+    /// Milliseconds since the UNIX epoch
+    pub exported_at_ms: u64,
+    /// The system configuration as stored, tagged with its schema version so it can be migrated on import
+    pub config: Value,
+    pub widgets: Vec<BundledWidget>,
+}
+
+/// A widget of a bundle
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+pub struct BundledWidget {
+    pub name: String,
+    /// Where the widget was installed from, if known
+    pub source: Option<InstallAction>,
+    /// The base64 encoded WebAssembly component, if embedded
+    pub component: Option<String>,
+}
+
+impl ConfigBundle {
+    /// Export the configuration of this display
+    /// # Arguments
+    /// * `embed_components` - Embed the components of the widgets, so they don't have to be downloaded on import
+    /// # Returns
+    /// The bundle
+    pub fn export(embed_components: bool) -> Result<ConfigBundle, PersistenceError> {
+        let config = Persistence::global().get_system_config()?;
+        let mut widgets = Vec::new();
+        for widget in &config.widgets {
+            let component = if embed_components {
+                Persistence::global()
+                    .get_widget_component(&widget.name)?
+                    .map(|bytes| BASE64.encode(bytes))
+            } else {
+                None
+            };
+            widgets.push(BundledWidget {
+                name: widget.name.clone(),
+                source: widget.source.clone(),
+                component,
+            });
+        }
+        let config = migrations::serialize(&config)
+            .and_then(|bytes| serde_json::from_slice(&bytes))
+            .map_err(|err| PersistenceError::Serialization(err.to_string()))?;
+        Ok(ConfigBundle {
+            format_version: BUNDLE_FORMAT_VERSION,
+            host_version: env!("CARGO_PKG_VERSION").to_string(),
+            exported_at_ms: SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map_or(0, |now| now.as_millis() as u64),
+            config,
+            widgets,
+        })
+    }
+
+    /// Parse a bundle
+    /// # Arguments
+    /// * `bytes` - The content of a bundle file
+    /// # Returns
+    /// The bundle or an error if it is invalid or was written by a newer version of WG Display
+    pub fn parse(bytes: &[u8]) -> Result<ConfigBundle, Error> {
+        let bundle: ConfigBundle = serde_json::from_slice(bytes).context("Invalid bundle")?;
+        if bundle.format_version > BUNDLE_FORMAT_VERSION {
+            bail!(
+                "Bundle was exported by WG Display {}, which is newer than this version",
+                bundle.host_version
+            );
+        }
+        Ok(bundle)
+    }
+
+    /// The system configuration of the bundle, migrated to the current schema version
+    fn system_config(&self) -> Result<SystemConfiguration, Error> {
+        let bytes = serde_json::to_vec(&self.config)?;
+        let migrated = migrations::migrate(&bytes).map_err(|err| match err {
+            migrations::MigrationError::Invalid(reason) => {
+                anyhow!("Invalid configuration in bundle: {}", reason)
+            }
+            migrations::MigrationError::UnsupportedVersion(version) => anyhow!(
+                "Configuration in bundle has the unsupported schema version {}",
+                version
+            ),
+        })?;
+        Ok(serde_json::from_value(migrated.config)?)
+    }
+
+    /// Install the widgets of the bundle and restore their configuration and the system settings
+    /// Widgets that are installed already are kept as they are and reported as conflict.
+    /// Configurations that do not match the schema of the installed widget are not restored.
+    /// The permissions granted on the exporting display are only granted again if the user approved them,
+    /// other widgets that were granted permissions are reported as conflict and not installed.
+    /// A widget that requests different permissions is not installed either.
+    /// # Arguments
+    /// * `approved` - Names of the widgets whose permissions the user approved on this display
+    /// # Returns
+    /// The installed widgets and the ones that could not be installed
+    pub async fn import(&self, approved: &[String]) -> Result<ImportReport, Error> {
+        let bundled_config = self.system_config()?;
+        let mut report = ImportReport::default();
+        let mut store: Option<WidgetStore> = None;
+
+        for widget in &bundled_config.widgets {
+            if let Some(installed) = Persistence::global().get_installation_data(&widget.name)? {
+                report.conflicts.push(ImportConflict::AlreadyInstalled {
+                    widget_name: widget.name.clone(),
+                    installed_version: installed.version,
+                });
+                continue;
+            }
+            if !widget.permissions.is_empty() && !approved.contains(&widget.name) {
+                report.conflicts.push(ImportConflict::ApprovalRequired {
+                    widget_name: widget.name.clone(),
+                    permissions: widget.permissions.clone(),
+                });
+                continue;
+            }
+
+            let bytes = match self.component(widget, &mut store).await {
+                Ok(Some(bytes)) => bytes,
+                Ok(None) => {
+                    report.conflicts.push(ImportConflict::NoSource {
+                        widget_name: widget.name.clone(),
+                    });
+                    continue;
+                }
+                Err(err) => {
+                    report.conflicts.push(ImportConflict::Failed {
+                        widget_name: widget.name.clone(),
+                        error: format!("{:#}", err),
+                    });
+                    continue;
+                }
+            };
+
+            let installed = WidgetManager::install_widget_bytes(
+                &bytes,
+                &widget.description,
+                widget.source.clone(),
+                Some(&widget.permissions),
+            );
+            match installed {
+                Ok(InstallResponse::Installed) => {
+                    // The exported configuration may not match the version installed here
+                    let json_config =
+                        match WidgetManager::validate_config(&widget.name, &widget.json_config) {
+                            Ok(json_config) => Some(json_config),
+                            Err(err) => {
+                                report.rejected_configs.push(format!("{:#}", err));
+                                None
+                            }
+                        };
+                    restore_widget_settings(widget, json_config)?;
+                    report.installed.push(widget.name.clone());
+                }
+                Ok(InstallResponse::ApprovalRequired { permissions, .. }) => {
+                    report.conflicts.push(ImportConflict::PermissionsChanged {
+                        widget_name: widget.name.clone(),
+                        permissions,
+                    })
+                }
+                Err(err) => report.conflicts.push(ImportConflict::Failed {
+                    widget_name: widget.name.clone(),
+                    error: format!("{:#}", err),
+                }),
+            }
+        }
+
+        Persistence::global()
+            .update_system_config(|config| config.set_system_settings(bundled_config))?;
+        Ok(report)
+    }
+
+    /// Get the component of a widget, from the bundle or from where it was installed from
+    /// # Arguments
+    /// * `widget` - The widget as configured on the exporting display
+    /// * `store` - The widget store, fetched on first use
+    /// # Returns
+    /// The component or `None` if the bundle does not know where to get it from
+    async fn component(
+        &self,
+        widget: &WidgetInstallationData,
+        store: &mut Option<WidgetStore>,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        let bundled = self
+            .widgets
+            .iter()
+            .find(|bundled| bundled.name == widget.name);
+        if let Some(component) = bundled.and_then(|bundled| bundled.component.as_ref()) {
+            return Ok(Some(
+                BASE64
+                    .decode(component)
+                    .context("Invalid embedded component")?,
+            ));
+        }
+
+        let source = widget
+            .source
+            .as_ref()
+            .or_else(|| bundled.and_then(|bundled| bundled.source.as_ref()));
+        let Some(source) = source else {
+            return Ok(None);
+        };
+        WidgetManager::download_from_source(source, store)
+            .await
+            .map(Some)
+    }
+}
+
+/// Restore the settings of an imported widget, as configured on the exporting display
+/// # Arguments
+/// * `imported` - The widget as configured on the exporting display
+/// * `json_config` - Its validated configuration, `None` keeps the configuration it was installed with
+fn restore_widget_settings(
+    imported: &WidgetInstallationData,
+    json_config: Option<String>,
+) -> Result<(), PersistenceError> {
+    Persistence::global().update_system_config(|config| {
+        for widget in config.widgets.iter_mut() {
+            if widget.name == imported.name {
+                if let Some(json_config) = &json_config {
+                    widget.json_config = json_config.clone();
+                }
+                widget.wasi_grants = imported.wasi_grants.clone();
+                widget.http_rate_limit = imported.http_rate_limit.clone();
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rocket::serde::json::serde_json::json;
+
+    fn bundle(format_version: u32, config: Value) -> ConfigBundle {
+        ConfigBundle {
+            format_version,
+            host_version: "9.9.9".to_string(),
+            exported_at_ms: 0,
+            config,
+            widgets: vec![BundledWidget {
+                name: "public_transport".to_string(),
+                source: Some(InstallAction::FromStoreItemName(
+                    "public_transport".to_string(),
+                )),
+                component: Some(BASE64.encode(b"\0asm")),
+            }],
+        }
+    }
+
+    #[test]
+    fn test_parse_roundtrip() {
+        let exported = bundle(BUNDLE_FORMAT_VERSION, json!({ "schema_version": 1 }));
+        let parsed = ConfigBundle::parse(&serde_json::to_vec(&exported).unwrap()).unwrap();
+        assert_eq!(parsed, exported);
+    }
+
+    #[test]
+    fn test_parse_rejects_newer_format() {
+        let exported = bundle(BUNDLE_FORMAT_VERSION + 1, json!({}));
+        let err = ConfigBundle::parse(&serde_json::to_vec(&exported).unwrap()).unwrap_err();
+        assert!(err.to_string().contains("9.9.9"));
+        assert!(ConfigBundle::parse(b"not json").is_err());
+    }
+
+    #[test]
+    fn test_config_of_older_schema_is_migrated() {
+        let exported = bundle(
+            BUNDLE_FORMAT_VERSION,
+            serde_json::from_str(include_str!("../../fixtures/system_config/v0.json")).unwrap(),
+        );
+        let config = exported.system_config().unwrap();
+        assert_eq!(config.background_color, "#1E1E2E");
+        assert_eq!(config.widgets.len(), 2);
+    }
+}
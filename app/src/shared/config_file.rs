@@ -0,0 +1,611 @@
+//! Optional configuration file to provision a display with, e.g. by Ansible
+//!
+//! The file is written in TOML or YAML and mirrors the [SystemConfiguration]:
+//! ```toml
+//! background_color = "#1E1E2E"
+//! timezone = "Europe/Zurich"
+//!
+//! [location]
+//! latitude = 46.948
+//! longitude = 7.447
+//! locality = "Bern"
+//!
+//! [widgets.public_transport]
+//! config = { from = "Bern", to = "Thun" }
+//! scratch_directory = false
+//! http_rate_limit = { requests = 10, per_seconds = 60 }
+//! ```
+//! Values set in the file take precedence over the ones edited on the dashboard:
+//! they are applied at startup, whenever the file changes and after every change of the configuration.
+//! Values not set in the file can be edited on the dashboard as usual.
+//! Widgets are not installed through the file, install them on the dashboard or with `app import`.
+//! Their settings in the file are applied as soon as they are installed.
+//! A widget configuration that does not match the widget's configuration schema is not applied.
+//!
+//! A file that can not be read or is invalid is not applied, the last valid version stays in effect.
+//! The problems are logged and reported on the dashboard.
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use common::models::{ConfigFileStatus, Location, RateLimit, SystemConfiguration};
+use jiff::tz::TimeZone;
+use rocket::serde::json::serde_json::Value;
+use serde::Deserialize;
+
+use super::persistence::Persistence;
+use super::widget_manager::WidgetManager;
+use crate::widgets::utils::logging::LOGGING_PREFIX;
+
+/// Environment variable with the path of the configuration file
+pub const CONFIG_FILE_ENV: &str = "WG_DISPLAY_CONFIG";
+
+/// Names the file is looked for in the data directory if no path is given
+const DEFAULT_FILE_NAMES: [&str; 3] = ["config.toml", "config.yaml", "config.yml"];
+
+/// How often the file is checked for changes
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// The configuration file, see the [module](self) documentation
+#[derive(Deserialize, PartialEq, Default, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigFile {
+    /// Hex color such as `#1E1E2E`
+    pub background_color: Option<String>,
+    pub disable_http_cache: Option<bool>,
+    pub location: Option<Location>,
+    pub timezone: Option<String>,
+    /// Settings of the widgets by name
+    #[serde(default)]
+    pub widgets: BTreeMap<String, WidgetSettings>,
+}
+
+/// Settings of a widget in the configuration file
+#[derive(Deserialize, PartialEq, Default, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct WidgetSettings {
+    /// Configuration of the widget, replaces the one edited on the dashboard
+    pub config: Option<Value>,
+    pub scratch_directory: Option<bool>,
+    pub http_rate_limit: Option<RateLimit>,
+}
+
+impl ConfigFile {
+    /// Parse and validate a configuration file
+    /// # Arguments
+    /// * `path` - Path of the file, its extension selects the format
+    /// * `content` - Content of the file
+    /// # Returns
+    /// The configuration or the problems found in the file
+    pub fn parse(path: &Path, content: &str) -> Result<ConfigFile, Vec<String>> {
+        let file = match path.extension().and_then(|extension| extension.to_str()) {
+            Some("toml") => toml::from_str(content).map_err(|err| vec![err.to_string()])?,
+            // An empty YAML document is no mapping, but is meant as a file setting nothing
+            Some("yaml" | "yml") if content.trim().is_empty() => ConfigFile::default(),
+            Some("yaml" | "yml") => {
+                serde_yaml::from_str(content).map_err(|err| vec![err.to_string()])?
+            }
+            _ => {
+                return Err(vec![format!(
+                    "Unknown format of {}, use a .toml, .yaml or .yml file",
+                    path.display()
+                )]);
+            }
+        };
+        let errors = ConfigFile::validate(&file);
+        if errors.is_empty() {
+            Ok(file)
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Check the values the dashboard would reject
+    /// # Returns
+    /// The invalid values
+    fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if let Some(color) = self
+            .background_color
+            .as_ref()
+            .filter(|color| !is_hex_color(color))
+        {
+            errors.push(format!(
+                "background_color: {} is no color like #1E1E2E",
+                color
+            ));
+        }
+        if self
+            .location
+            .as_ref()
+            .is_some_and(|location| !location.is_valid())
+        {
+            errors.push(
+                "location: latitude must be within ±90° and longitude within ±180°".to_string(),
+            );
+        }
+        if let Some(timezone) = self
+            .timezone
+            .as_ref()
+            .filter(|timezone| TimeZone::get(timezone).is_err())
+        {
+            errors.push(format!("timezone: unknown timezone {}", timezone));
+        }
+        for (name, widget) in &self.widgets {
+            if widget
+                .config
+                .as_ref()
+                .is_some_and(|config| !config.is_object())
+            {
+                errors.push(format!("widgets.{}.config: must be a table", name));
+            }
+            if widget
+                .http_rate_limit
+                .as_ref()
+                .is_some_and(|limit| limit.requests == 0 || limit.per_seconds == 0)
+            {
+                errors.push(format!(
+                    "widgets.{}.http_rate_limit: requests and per_seconds must be positive",
+                    name
+                ));
+            }
+        }
+        errors
+    }
+
+    /// Set the values of the file in a configuration
+    /// Widgets that are not installed are skipped.
+    /// # Arguments
+    /// * `config` - The configuration to change
+    /// * `validate` - Checks the configuration of a widget, see [WidgetManager::validate_config]
+    /// # Returns
+    /// The widget configurations that were not applied, as they are invalid
+    pub fn apply(
+        &self,
+        config: &mut SystemConfiguration,
+        validate: impl Fn(&str, &str) -> Result<String, String>,
+    ) -> Vec<String> {
+        let mut rejected = Vec::new();
+        if let Some(color) = &self.background_color {
+            config.background_color = color.clone();
+        }
+        if let Some(disable_http_cache) = self.disable_http_cache {
+            config.disable_http_cache = disable_http_cache;
+        }
+        if let Some(location) = &self.location {
+            config.location = Some(location.clone());
+        }
+        if let Some(timezone) = &self.timezone {
+            config.timezone = Some(timezone.clone());
+        }
+        for widget in config.widgets.iter_mut() {
+            let Some(settings) = self.widgets.get(&widget.name) else {
+                continue;
+            };
+            // A configuration applied before was validated already
+            if let Some(json_config) = settings
+                .config
+                .as_ref()
+                .map(Value::to_string)
+                .filter(|json_config| *json_config != widget.json_config)
+            {
+                match validate(&widget.name, &json_config) {
+                    Ok(json_config) => widget.json_config = json_config,
+                    Err(err) => rejected.push(format!("widgets.{}.config: {}", widget.name, err)),
+                }
+            }
+            if let Some(scratch_directory) = settings.scratch_directory {
+                widget.wasi_grants.scratch_directory = scratch_directory;
+            }
+            if let Some(limit) = &settings.http_rate_limit {
+                widget.http_rate_limit = Some(limit.clone());
+            }
+        }
+        rejected
+    }
+
+    /// The values set by the file
+    /// # Returns
+    /// Dotted paths of the values, as used by [ConfigChange](common::models::ConfigChange)
+    pub fn managed(&self) -> Vec<String> {
+        let mut managed = Vec::new();
+        let system = [
+            ("background_color", self.background_color.is_some()),
+            ("disable_http_cache", self.disable_http_cache.is_some()),
+            ("location", self.location.is_some()),
+            ("timezone", self.timezone.is_some()),
+        ];
+        for (path, is_set) in system {
+            if is_set {
+                managed.push(path.to_string());
+            }
+        }
+        for (name, settings) in &self.widgets {
+            let widget = [
+                ("json_config", settings.config.is_some()),
+                (
+                    "wasi_grants.scratch_directory",
+                    settings.scratch_directory.is_some(),
+                ),
+                ("http_rate_limit", settings.http_rate_limit.is_some()),
+            ];
+            for (path, is_set) in widget {
+                if is_set {
+                    managed.push(format!("widgets.{}.{}", name, path));
+                }
+            }
+        }
+        managed
+    }
+
+    /// Widgets configured in the file that are not installed
+    /// # Arguments
+    /// * `config` - The current configuration
+    pub fn not_installed(&self, config: &SystemConfiguration) -> Vec<String> {
+        self.widgets
+            .keys()
+            .filter(|name| !config.widgets.iter().any(|widget| &widget.name == *name))
+            .cloned()
+            .collect()
+    }
+}
+
+/// The configuration file in effect
+struct Loaded {
+    path: PathBuf,
+    /// Modification time of the file when it was read
+    modified: Option<SystemTime>,
+    /// The last valid version of the file
+    file: Option<ConfigFile>,
+    status: ConfigFileStatus,
+}
+
+static LOADED: Mutex<Option<Loaded>> = Mutex::new(None);
+
+/// Apply the configuration file and reapply it whenever it changes
+/// The file is also applied after every change of the configuration, see [overlay].
+/// # Arguments
+/// * `cli` - The file given on the command line. Otherwise it is taken from the environment
+///   or looked for in the data directory.
+/// * `data_dir` - The data directory
+pub fn watch(cli: Option<PathBuf>, data_dir: &Path) {
+    let explicit = cli
+        .or_else(|| {
+            std::env::var_os(CONFIG_FILE_ENV)
+                .filter(|value| !value.is_empty())
+                .map(PathBuf::from)
+        })
+        .map(|path| std::path::absolute(&path).unwrap_or(path));
+    let data_dir = data_dir.to_path_buf();
+    Persistence::global().set_overlay(overlay);
+    reload(explicit.as_deref(), &data_dir);
+    thread::spawn(move || {
+        loop {
+            thread::sleep(POLL_INTERVAL);
+            reload(explicit.as_deref(), &data_dir);
+        }
+    });
+}
+
+/// State of the configuration file
+/// # Returns
+/// The state or `None` if the display is not configured by a file
+pub fn status() -> Option<ConfigFileStatus> {
+    lock().as_ref().map(|loaded| loaded.status.clone())
+}
+
+/// Set the values of the configuration file in effect, so they take precedence
+/// Widget configurations that are not applied are reported in the [status].
+/// # Arguments
+/// * `config` - The configuration to change
+pub fn overlay(config: &mut SystemConfiguration) {
+    if let Some(Loaded {
+        file: Some(file),
+        status,
+        ..
+    }) = lock().as_mut()
+    {
+        status.rejected_configs = file.apply(config, |widget_name, json_config| {
+            WidgetManager::validate_config(widget_name, json_config)
+                .map_err(|err| format!("{:#}", err))
+        });
+    }
+}
+
+/// Find the configuration file
+/// # Arguments
+/// * `explicit` - The path given on the command line or in the environment
+/// * `data_dir` - The data directory the file is looked for otherwise
+/// # Returns
+/// The path of the file, `None` if no file is configured
+fn find(explicit: Option<&Path>, data_dir: &Path) -> Option<PathBuf> {
+    match explicit {
+        Some(path) => Some(path.to_path_buf()),
+        None => DEFAULT_FILE_NAMES
+            .iter()
+            .map(|name| data_dir.join(name))
+            .find(|path| path.is_file()),
+    }
+}
+
+/// Read the configuration file and apply it, if it changed since it was last read
+/// # Arguments
+/// * `explicit` - The path given on the command line or in the environment
+/// * `data_dir` - The data directory the file is looked for otherwise
+fn reload(explicit: Option<&Path>, data_dir: &Path) {
+    let Some(path) = find(explicit, data_dir) else {
+        // Values set by a removed file are kept, but can be edited on the dashboard again
+        if let Some(removed) = lock().take() {
+            log::info!(
+                "{}: Configuration file {} was removed",
+                LOGGING_PREFIX,
+                removed.path.display()
+            );
+        }
+        return;
+    };
+    let modified = fs::metadata(&path)
+        .and_then(|metadata| metadata.modified())
+        .ok();
+    let previous = {
+        let mut loaded = lock();
+        if loaded
+            .as_ref()
+            .is_some_and(|loaded| loaded.path == path && loaded.modified == modified)
+        {
+            return;
+        }
+        loaded.take().filter(|loaded| loaded.path == path)
+    };
+
+    let mut status = ConfigFileStatus {
+        path: path.display().to_string(),
+        loaded_at_ms: SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0, |now| now.as_millis() as u64),
+        ..Default::default()
+    };
+    let parsed = fs::read_to_string(&path)
+        .map_err(|err| vec![format!("Could not read the file: {}", err)])
+        .and_then(|content| ConfigFile::parse(&path, &content));
+    let file = match parsed {
+        Ok(file) => {
+            log::info!(
+                "{}: Applying configuration file {}",
+                LOGGING_PREFIX,
+                path.display()
+            );
+            Some(file)
+        }
+        Err(errors) => {
+            for error in &errors {
+                log::error!(
+                    "{}: Invalid configuration file {}: {}",
+                    LOGGING_PREFIX,
+                    path.display(),
+                    error
+                );
+            }
+            status.errors = errors;
+            previous.and_then(|previous| previous.file)
+        }
+    };
+    status.managed = file.as_ref().map(ConfigFile::managed).unwrap_or_default();
+    *lock() = Some(Loaded {
+        path,
+        modified,
+        file,
+        status,
+    });
+
+    // Applied through the overlay, so concurrent changes of the configuration see the new file as well
+    let applied = Persistence::global()
+        .update_system_config(|_| ())
+        .and_then(|()| Persistence::global().get_system_config());
+    match applied {
+        Ok(config) => {
+            if let Some(loaded) = lock().as_mut() {
+                loaded.status.not_installed = loaded
+                    .file
+                    .as_ref()
+                    .map(|file| file.not_installed(&config))
+                    .unwrap_or_default();
+            }
+        }
+        Err(err) => {
+            log::error!(
+                "{}: Could not apply configuration file: {}",
+                LOGGING_PREFIX,
+                err
+            );
+            if let Some(loaded) = lock().as_mut() {
+                loaded
+                    .status
+                    .errors
+                    .push(format!("Could not apply the file: {}", err));
+            }
+        }
+    }
+}
+
+fn lock() -> std::sync::MutexGuard<'static, Option<Loaded>> {
+    LOADED.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Whether a color is given as `#RGB` or `#RRGGBB`
+fn is_hex_color(color: &str) -> bool {
+    color
+        .strip_prefix('#')
+        .is_some_and(|hex| matches!(hex.len(), 3 | 6) && hex.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::models::WidgetInstallationData;
+
+    const TOML: &str = r##"
+background_color = "#1E1E2E"
+timezone = "Europe/Zurich"
+
+[location]
+latitude = 46.948
+longitude = 7.447
+locality = "Bern"
+
+[widgets.public_transport]
+config = { from = "Bern", to = "Thun" }
+http_rate_limit = { requests = 10, per_seconds = 60 }
+
+[widgets.weather]
+scratch_directory = true
+"##;
+
+    const YAML: &str = r##"
+background_color: "#1E1E2E"
+timezone: Europe/Zurich
+location:
+  latitude: 46.948
+  longitude: 7.447
+  locality: Bern
+widgets:
+  public_transport:
+    config:
+      from: Bern
+      to: Thun
+    http_rate_limit:
+      requests: 10
+      per_seconds: 60
+  weather:
+    scratch_directory: true
+"##;
+
+    fn widget(name: &str) -> WidgetInstallationData {
+        WidgetInstallationData {
+            name: name.to_string(),
+            json_config: "{\"from\":\"Zürich\"}".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_toml_and_yaml_are_equivalent() {
+        let toml = ConfigFile::parse(Path::new("config.toml"), TOML).unwrap();
+        let yaml = ConfigFile::parse(Path::new("config.yml"), YAML).unwrap();
+        assert_eq!(toml, yaml);
+        assert_eq!(
+            ConfigFile::parse(Path::new("config.yaml"), "").unwrap(),
+            ConfigFile::default()
+        );
+    }
+
+    #[test]
+    fn test_apply() {
+        let file = ConfigFile::parse(Path::new("config.toml"), TOML).unwrap();
+        let mut config = SystemConfiguration {
+            background_color: "#FFFFFF".to_string(),
+            disable_http_cache: true,
+            widgets: vec![widget("public_transport"), widget("clock")],
+            ..Default::default()
+        };
+        let rejected = file.apply(&mut config, |_, json_config| Ok(json_config.to_string()));
+
+        assert!(rejected.is_empty());
+        assert_eq!(config.background_color, "#1E1E2E");
+        // Values not set in the file are kept
+        assert!(config.disable_http_cache);
+        assert_eq!(config.timezone.as_deref(), Some("Europe/Zurich"));
+        assert_eq!(
+            config.widgets[0].json_config,
+            "{\"from\":\"Bern\",\"to\":\"Thun\"}"
+        );
+        assert_eq!(
+            config.widgets[0].http_rate_limit.as_ref().unwrap().requests,
+            10
+        );
+        assert_eq!(config.widgets[1], widget("clock"));
+        assert_eq!(file.not_installed(&config), vec!["weather"]);
+        assert_eq!(
+            file.managed(),
+            vec![
+                "background_color",
+                "location",
+                "timezone",
+                "widgets.public_transport.json_config",
+                "widgets.public_transport.http_rate_limit",
+                "widgets.weather.wasi_grants.scratch_directory",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_widget_configs_are_not_applied() {
+        let file = ConfigFile::parse(Path::new("config.toml"), TOML).unwrap();
+        let mut config = SystemConfiguration {
+            widgets: vec![widget("public_transport")],
+            ..Default::default()
+        };
+        let rejected = file.apply(&mut config, |_, _| Err("to: unknown field".to_string()));
+
+        assert_eq!(
+            rejected,
+            vec!["widgets.public_transport.config: to: unknown field"]
+        );
+        assert_eq!(config.widgets[0].json_config, "{\"from\":\"Zürich\"}");
+        // The other settings of the widget are applied
+        assert!(config.widgets[0].http_rate_limit.is_some());
+
+        // A configuration that is applied already is not validated again
+        config.widgets[0].json_config = "{\"from\":\"Bern\",\"to\":\"Thun\"}".to_string();
+        assert!(
+            file.apply(&mut config, |_, _| Err(String::new()))
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_invalid_files_are_reported() {
+        let errors = ConfigFile::parse(
+            Path::new("config.toml"),
+            r##"
+background_color = "dark"
+timezone = "Mars/Olympus_Mons"
+
+[widgets.weather]
+config = "Bern"
+"##,
+        )
+        .unwrap_err();
+        assert_eq!(errors.len(), 3);
+        assert!(errors[0].starts_with("background_color"));
+        assert!(errors[1].starts_with("timezone"));
+        assert!(errors[2].starts_with("widgets.weather.config"));
+
+        // Unknown keys are rejected, so typos do not go unnoticed
+        let errors =
+            ConfigFile::parse(Path::new("config.toml"), "backgroud_color = \"#000\"").unwrap_err();
+        assert!(errors[0].contains("backgroud_color"));
+        assert!(ConfigFile::parse(Path::new("config.yml"), "widgets: [").is_err());
+        assert!(ConfigFile::parse(Path::new("config.json"), "{}").is_err());
+    }
+
+    #[test]
+    fn test_find() {
+        let data_dir =
+            std::env::temp_dir().join(format!("wg_display_test_find_{}", std::process::id()));
+        fs::create_dir_all(&data_dir).unwrap();
+        assert_eq!(find(None, &data_dir), None);
+
+        fs::write(data_dir.join("config.yml"), "").unwrap();
+        assert_eq!(find(None, &data_dir), Some(data_dir.join("config.yml")));
+        let explicit = Path::new("/etc/wg_display.toml");
+        assert_eq!(
+            find(Some(explicit), &data_dir),
+            Some(explicit.to_path_buf())
+        );
+        fs::remove_dir_all(data_dir).unwrap();
+    }
+}
@@ -0,0 +1,178 @@
+//! Location of the files of WG Display: the database, the secrets key and the scratch directories of widgets
+//!
+//! The data directory is, in order of precedence,
+//! the `--data-dir` command line option, the `WG_DISPLAY_DATA_DIR` environment variable,
+//! `$XDG_DATA_HOME/wg_display` or `~/.local/share/wg_display`.
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use super::persistence::DB_NAME;
+use super::secrets::KEY_FILE;
+use crate::widgets::running::wasi::SCRATCH_DIRECTORY_ROOT;
+use crate::widgets::utils::logging::LOGGING_PREFIX;
+
+/// Environment variable overriding the data directory
+pub const DATA_DIR_ENV: &str = "WG_DISPLAY_DATA_DIR";
+
+/// Name of the data directory below the XDG data directory
+const APP_DIR_NAME: &str = "wg_display";
+
+/// Files that earlier versions created in the working directory
+const LEGACY_FILES: [&str; 3] = [DB_NAME, KEY_FILE, SCRATCH_DIRECTORY_ROOT];
+
+static DATA_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Determine the data directory
+/// # Arguments
+/// * `cli` - The directory given on the command line
+/// * `env` - Looks up an environment variable
+/// # Returns
+/// The data directory or `None` if neither is configured nor a home directory is known
+pub fn resolve(cli: Option<PathBuf>, env: impl Fn(&str) -> Option<OsString>) -> Option<PathBuf> {
+    let non_empty = |name: &str| env(name).filter(|value| !value.is_empty());
+    cli.or_else(|| non_empty(DATA_DIR_ENV).map(PathBuf::from))
+        .or_else(|| non_empty("XDG_DATA_HOME").map(|dir| PathBuf::from(dir).join(APP_DIR_NAME)))
+        .or_else(|| {
+            non_empty("HOME").map(|home| {
+                PathBuf::from(home)
+                    .join(".local")
+                    .join("share")
+                    .join(APP_DIR_NAME)
+            })
+        })
+}
+
+/// Set the data directory and create it if needed
+/// Files earlier versions left in the working directory are moved to it.
+/// # Arguments
+/// * `cli` - The directory given on the command line
+/// # Returns
+/// The data directory or an error if it could not be created
+pub fn init(cli: Option<PathBuf>) -> io::Result<&'static Path> {
+    let data_dir = match resolve(cli, |name| std::env::var_os(name)) {
+        Some(data_dir) => std::path::absolute(data_dir)?,
+        None => {
+            log::warn!(
+                "{}: No home directory, storing data in the working directory. Set {} to change it.",
+                LOGGING_PREFIX,
+                DATA_DIR_ENV
+            );
+            std::env::current_dir()?
+        }
+    };
+    fs::create_dir_all(&data_dir)?;
+    adopt_legacy_files(&std::env::current_dir()?, &data_dir);
+    Ok(DATA_DIR.get_or_init(|| data_dir))
+}
+
+/// The data directory set by [init]
+/// Falls back to the default location if it was not set, e.g. in tests.
+pub fn get() -> &'static Path {
+    DATA_DIR.get_or_init(|| {
+        resolve(None, |name| std::env::var_os(name)).unwrap_or_else(|| PathBuf::from("."))
+    })
+}
+
+/// Move files earlier versions created in the working directory to the data directory
+/// Files are only moved if the data directory does not contain them yet.
+/// # Arguments
+/// * `working_dir` - The directory the legacy files were created in
+/// * `data_dir` - The data directory
+fn adopt_legacy_files(working_dir: &Path, data_dir: &Path) {
+    if working_dir == data_dir {
+        return;
+    }
+    for name in LEGACY_FILES {
+        let legacy = working_dir.join(name);
+        let target = data_dir.join(name);
+        if !legacy.exists() || target.exists() {
+            continue;
+        }
+        match fs::rename(&legacy, &target) {
+            Ok(()) => log::info!(
+                "{}: Moved {} to {}",
+                LOGGING_PREFIX,
+                legacy.display(),
+                target.display()
+            ),
+            Err(err) => log::warn!(
+                "{}: Could not move {} to {}, move it manually: {}",
+                LOGGING_PREFIX,
+                legacy.display(),
+                target.display(),
+                err
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env<'a>(vars: &'a [(&'a str, &'a str)]) -> impl Fn(&str) -> Option<OsString> + 'a {
+        move |name| {
+            vars.iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| OsString::from(value))
+        }
+    }
+
+    #[test]
+    fn test_resolve_precedence() {
+        let vars = [
+            (DATA_DIR_ENV, "/srv/display"),
+            ("XDG_DATA_HOME", "/home/pi/.data"),
+            ("HOME", "/home/pi"),
+        ];
+        assert_eq!(
+            resolve(Some(PathBuf::from("/data")), env(&vars)),
+            Some(PathBuf::from("/data"))
+        );
+        assert_eq!(
+            resolve(None, env(&vars)),
+            Some(PathBuf::from("/srv/display"))
+        );
+        assert_eq!(
+            resolve(None, env(&vars[1..])),
+            Some(PathBuf::from("/home/pi/.data/wg_display"))
+        );
+        assert_eq!(
+            resolve(None, env(&vars[2..])),
+            Some(PathBuf::from("/home/pi/.local/share/wg_display"))
+        );
+        assert_eq!(resolve(None, env(&[])), None);
+        // Empty variables count as unset
+        assert_eq!(
+            resolve(None, env(&[(DATA_DIR_ENV, ""), ("HOME", "/home/pi")])),
+            Some(PathBuf::from("/home/pi/.local/share/wg_display"))
+        );
+    }
+
+    #[test]
+    fn test_adopt_legacy_files() {
+        let root =
+            std::env::temp_dir().join(format!("wg_display_test_adopt_{}", std::process::id()));
+        let working_dir = root.join("working_dir");
+        let data_dir = root.join("data_dir");
+        fs::create_dir_all(working_dir.join("wg_display.db")).unwrap();
+        fs::write(working_dir.join("wg_display.key"), b"legacy").unwrap();
+        fs::create_dir_all(&data_dir).unwrap();
+        fs::write(data_dir.join("wg_display.key"), b"current").unwrap();
+
+        adopt_legacy_files(&working_dir, &data_dir);
+        assert!(data_dir.join("wg_display.db").is_dir());
+        assert!(!working_dir.join("wg_display.db").exists());
+        // Existing files are not replaced
+        assert_eq!(
+            fs::read(data_dir.join("wg_display.key")).unwrap(),
+            b"current"
+        );
+        assert!(working_dir.join("wg_display.key").exists());
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,261 @@
+//! Migrations of the stored system configuration
+//!
+//! The configuration is stored as JSON together with the version of its schema.
+//! Whenever the layout changes in a way `#[serde(default)]` can not cover (e.g. a renamed field),
+//! bump [CURRENT_SCHEMA_VERSION] and append a migration to [MIGRATIONS].
+//! Add a fixture of the old layout to `fixtures/system_config` so the migration stays tested.
+use common::models::SystemConfiguration;
+use rocket::serde::json::serde_json::{self, Map, Value, json};
+
+/// Version of the schema written by this version of WG Display
+pub const CURRENT_SCHEMA_VERSION: u32 = 1;
+
+/// Key of the schema version in the stored JSON.
+/// Configurations stored before the schema was versioned lack it and have version 0.
+const SCHEMA_VERSION_KEY: &str = "schema_version";
+
+/// A migration takes the configuration of one schema version and returns it in the layout of the next
+type Migration = fn(Value) -> Result<Value, String>;
+
+/// The migration at index `n` migrates from version `n` to version `n + 1`
+const MIGRATIONS: [Migration; CURRENT_SCHEMA_VERSION as usize] = [v0_to_v1];
+
+/// Errors of a migration
+#[derive(Debug, PartialEq)]
+pub enum MigrationError {
+    /// The stored value is not a configuration or a migration failed
+    Invalid(String),
+    /// The configuration was written by a newer version of WG Display
+    UnsupportedVersion(u32),
+}
+
+/// A configuration in the layout of the current schema version
+#[derive(Debug, PartialEq)]
+pub struct Migrated {
+    pub config: Value,
+    /// Schema version the configuration was stored with
+    pub from_version: u32,
+}
+
+impl Migrated {
+    /// Whether any migration was applied
+    pub fn was_migrated(&self) -> bool {
+        self.from_version < CURRENT_SCHEMA_VERSION
+    }
+}
+
+/// Bring a stored configuration to the current schema version
+/// # Arguments
+/// * `bytes` - The stored configuration
+/// # Returns
+/// The configuration in the layout of the current schema, without the schema version
+pub fn migrate(bytes: &[u8]) -> Result<Migrated, MigrationError> {
+    let mut config: Value =
+        serde_json::from_slice(bytes).map_err(|err| MigrationError::Invalid(err.to_string()))?;
+    let object = config
+        .as_object_mut()
+        .ok_or_else(|| MigrationError::Invalid("Configuration is not an object".to_string()))?;
+    let from_version = match object.remove(SCHEMA_VERSION_KEY) {
+        None => 0,
+        Some(version) => version
+            .as_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .ok_or_else(|| {
+                MigrationError::Invalid(format!("Invalid schema version {}", version))
+            })?,
+    };
+    if from_version > CURRENT_SCHEMA_VERSION {
+        return Err(MigrationError::UnsupportedVersion(from_version));
+    }
+
+    for (version, migration) in MIGRATIONS.iter().enumerate().skip(from_version as usize) {
+        config = migration(config).map_err(|err| {
+            MigrationError::Invalid(format!(
+                "Migration from schema version {} failed: {}",
+                version, err
+            ))
+        })?;
+    }
+    Ok(Migrated {
+        config,
+        from_version,
+    })
+}
+
+/// Serialize a configuration for storage, tagged with the current schema version
+/// # Arguments
+/// * `config` - The configuration to store
+/// # Returns
+/// The JSON to store
+pub fn serialize(config: &SystemConfiguration) -> Result<Vec<u8>, serde_json::Error> {
+    let mut value = serde_json::to_value(config)?;
+    if let Some(object) = value.as_object_mut() {
+        object.insert(
+            SCHEMA_VERSION_KEY.to_string(),
+            json!(CURRENT_SCHEMA_VERSION),
+        );
+    }
+    serde_json::to_vec(&value)
+}
+
+fn as_object<'a>(value: &'a mut Value, what: &str) -> Result<&'a mut Map<String, Value>, String> {
+    value
+        .as_object_mut()
+        .ok_or_else(|| format!("{} is not an object", what))
+}
+
+/// Version 0 configurations were written before permissions, WASI grants, rate limits,
+/// the HTTP cache switch, the location and the timezone existed.
+/// Adds them explicitly, so later migrations can rely on every field being present.
+/// Widgets installed before permissions existed are granted none and marked as requiring approval,
+/// so the dashboard asks the user to approve the permissions they request.
+fn v0_to_v1(mut config: Value) -> Result<Value, String> {
+    let object = as_object(&mut config, "Configuration")?;
+    object
+        .entry("background_color")
+        .or_insert_with(|| json!(""));
+    object.entry("disable_http_cache").or_insert(json!(false));
+    object.entry("location").or_insert(Value::Null);
+    object.entry("timezone").or_insert(Value::Null);
+    let widgets = object
+        .entry("widgets")
+        .or_insert_with(|| json!([]))
+        .as_array_mut()
+        .ok_or("Widgets are not a list")?;
+    for widget in widgets {
+        let widget = as_object(widget, "Widget")?;
+        let approval_required = !widget.contains_key("permissions");
+        widget
+            .entry("permissions")
+            .or_insert_with(|| json!({ "http": null }));
+        widget
+            .entry("approval_required")
+            .or_insert(json!(approval_required));
+        widget
+            .entry("wasi_grants")
+            .or_insert_with(|| json!({ "scratch_directory": false }));
+        widget.entry("http_rate_limit").or_insert(Value::Null);
+    }
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::models::{
+        HttpMethod, HttpPermissions, Location, RateLimit, WasiGrants, WidgetInstallationData,
+        WidgetPermissions,
+    };
+
+    /// A configuration as stored by every schema version, indexed by version
+    const FIXTURES: [&str; CURRENT_SCHEMA_VERSION as usize + 1] = [
+        include_str!("../../fixtures/system_config/v0.json"),
+        include_str!("../../fixtures/system_config/v1.json"),
+    ];
+
+    fn load(version: usize) -> SystemConfiguration {
+        let migrated = migrate(FIXTURES[version].as_bytes()).unwrap();
+        assert_eq!(migrated.from_version, version as u32);
+        serde_json::from_value(migrated.config).unwrap()
+    }
+
+    #[test]
+    fn test_fixtures_of_all_versions_load() {
+        for version in 0..FIXTURES.len() {
+            let config = load(version);
+            assert_eq!(config.background_color, "#1E1E2E");
+            assert!(!config.widgets.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_migrate_v0() {
+        let config = load(0);
+        assert_eq!(
+            config.widgets[0],
+            WidgetInstallationData {
+                name: "public_transport".to_string(),
+                description: "Shows the next departures".to_string(),
+                version: "0.3.1".to_string(),
+                json_config: "{\"from\":\"Bern\",\"to\":\"Zürich HB\"}".to_string(),
+                approval_required: true,
+                ..Default::default()
+            }
+        );
+        assert_eq!(config.widgets.len(), 2);
+        assert_eq!(config.location, None);
+        assert_eq!(config.timezone, None);
+    }
+
+    #[test]
+    fn test_load_v1() {
+        let config = load(1);
+        assert!(!config.widgets[0].approval_required);
+        assert_eq!(
+            config.widgets[0].permissions,
+            WidgetPermissions {
+                http: Some(HttpPermissions {
+                    hosts: vec!["transport.opendata.ch".to_string()],
+                    methods: vec![HttpMethod::Get],
+                    max_body_size: 1048576,
+                }),
+            }
+        );
+        assert_eq!(
+            config.widgets[0].wasi_grants,
+            WasiGrants {
+                scratch_directory: true
+            }
+        );
+        assert_eq!(
+            config.widgets[0].http_rate_limit,
+            Some(RateLimit {
+                requests: 10,
+                per_seconds: 60
+            })
+        );
+        assert_eq!(
+            config.location,
+            Some(Location {
+                latitude: 46.948,
+                longitude: 7.447,
+                locality: "Bern".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_roundtrip_is_current_version() {
+        let config = load(0);
+        let migrated = migrate(&serialize(&config).unwrap()).unwrap();
+        assert!(!migrated.was_migrated());
+        assert_eq!(
+            serde_json::from_value::<SystemConfiguration>(migrated.config).unwrap(),
+            config
+        );
+    }
+
+    #[test]
+    fn test_newer_version_is_rejected() {
+        let stored = format!("{{\"schema_version\": {}}}", CURRENT_SCHEMA_VERSION + 1);
+        assert_eq!(
+            migrate(stored.as_bytes()),
+            Err(MigrationError::UnsupportedVersion(
+                CURRENT_SCHEMA_VERSION + 1
+            ))
+        );
+    }
+
+    #[test]
+    fn test_invalid_configuration() {
+        assert!(matches!(migrate(b"[]"), Err(MigrationError::Invalid(_))));
+        assert!(matches!(
+            migrate(b"{\"widgets\": 3}"),
+            Err(MigrationError::Invalid(_))
+        ));
+        assert!(matches!(
+            migrate(b"{\"schema_version\": \"one\"}"),
+            Err(MigrationError::Invalid(_))
+        ));
+    }
+}
@@ -1,3 +1,9 @@
 //! Contains sources shared by [server](crate::server) and [renderer](crate::renderer)
+pub mod bundle;
+pub mod config_file;
+pub mod data_dir;
+pub mod migrations;
 pub mod persistence;
+pub mod revisions;
+pub mod secrets;
 pub mod widget_manager;
@@ -1,119 +1,487 @@
 //! Implementation of the system configuration persistence
-use common::models::{SystemConfiguration, WidgetInstallationData};
+use common::models::{
+    ConfigRevision, InstallAction, QuarantinedEntry, SystemConfiguration, WidgetInstallationData,
+    WidgetPermissions,
+};
 use rocket::serde::json::serde_json;
+use serde::{Deserialize, Serialize};
 
+use std::fmt;
+use std::path::Path;
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, MutexGuard, OnceLock, PoisonError};
+use std::time::SystemTime;
 
+use super::migrations::{self, MigrationError};
 use crate::widgets::running::runtime::CompiledWidget;
+use crate::widgets::store::widget_store::WidgetStoreCache;
+use crate::widgets::utils::logging::LOGGING_PREFIX;
 
-static DB_NAME: &str = "wg_display.db";
-lazy_static! {
-    static ref DB: sled::Db = sled::open(DB_NAME).expect("Could not open DB");
+/// Name of the database in the data directory
+pub const DB_NAME: &str = "wg_display.db";
+
+static GLOBAL: OnceLock<Persistence> = OnceLock::new();
+
+/// Number of revisions of the system configuration that are kept
+const MAX_CONFIG_REVISIONS: usize = 50;
+
+/// A saved state of the system configuration
+#[derive(Serialize, Deserialize)]
+struct StoredRevision {
+    /// Milliseconds since the UNIX epoch
+    timestamp_ms: u64,
+    /// The configuration as stored, tagged with its schema version
+    config: serde_json::Value,
+}
+
+/// Errors of the persistence layer
+#[derive(Debug)]
+pub enum PersistenceError {
+    /// The database could not be opened
+    Unavailable(String),
+    /// The database could not be read or written
+    Database(sled::Error),
+    /// A value could not be serialized
+    Serialization(String),
+    /// A stored value could not be decoded. It was moved to the quarantine.
+    Corrupt { key: String, reason: String },
+    /// The configuration was stored by a newer version with the given schema version
+    UnsupportedVersion(u32),
+}
+
+impl fmt::Display for PersistenceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PersistenceError::Unavailable(err) => write!(f, "Could not open database: {}", err),
+            PersistenceError::Database(err) => write!(f, "Could not access database: {}", err),
+            PersistenceError::Serialization(err) => write!(f, "Could not serialize value: {}", err),
+            PersistenceError::Corrupt { key, reason } => {
+                write!(
+                    f,
+                    "Stored value {} is corrupt and was quarantined: {}",
+                    key, reason
+                )
+            }
+            PersistenceError::UnsupportedVersion(version) => write!(
+                f,
+                "Configuration was stored by a newer version of WG Display (schema version {})",
+                version
+            ),
+        }
+    }
 }
 
-static CONFIG_UPDATED: AtomicBool = AtomicBool::new(false);
+impl std::error::Error for PersistenceError {}
 
-pub struct Persistence {}
+impl From<sled::Error> for PersistenceError {
+    fn from(err: sled::Error) -> Self {
+        PersistenceError::Database(err)
+    }
+}
 
 /// Persists the system configuration.
 /// Uses the SLED embedded database.
+/// The display uses one instance stored in the data directory, see [Persistence::global].
+pub struct Persistence {
+    db: sled::Db,
+    /// Set when the configuration or a compiled widget changed since the last poll
+    config_updated: AtomicBool,
+    /// Held while the system configuration is read and written, so updates are not lost
+    config_lock: Mutex<()>,
+    /// Applied to the configuration whenever it is changed, see [Persistence::set_overlay]
+    overlay: OnceLock<fn(&mut SystemConfiguration)>,
+}
+
 impl Persistence {
     const DB_KEY: &str = "system_configuration";
+    const WIDGET_STORE_CACHE_KEY: &str = "widget_store_cache";
+    const QUARANTINE_TREE: &str = "quarantine";
+    const QUARANTINE_DATA_TREE: &str = "quarantine_data";
+    const BACKUP_TREE: &str = "backups";
+    const WIDGET_COMPONENT_TREE: &str = "widget_components";
+    const CONFIG_REVISION_TREE: &str = "config_revisions";
 
-    /// Save the system configuration
+    /// Open the database at a path
+    /// # Arguments
+    /// * `path` - Directory of the database, created if it does not exist
+    pub fn open(path: &Path) -> Result<Persistence, PersistenceError> {
+        let db = sled::open(path)
+            .map_err(|err| PersistenceError::Unavailable(format!("{}: {}", path.display(), err)))?;
+        Ok(Persistence::with_db(db))
+    }
+
+    /// Open a database that is deleted when it is dropped, e.g. for tests
+    pub fn temporary() -> Result<Persistence, PersistenceError> {
+        let db = sled::Config::new()
+            .temporary(true)
+            .open()
+            .map_err(|err| PersistenceError::Unavailable(err.to_string()))?;
+        Ok(Persistence::with_db(db))
+    }
+
+    fn with_db(db: sled::Db) -> Persistence {
+        Persistence {
+            db,
+            config_updated: AtomicBool::new(false),
+            config_lock: Mutex::new(()),
+            overlay: OnceLock::new(),
+        }
+    }
+
+    /// Open the database of the display in the data directory
+    /// Must be called once at startup, before [Persistence::global] is used.
+    /// # Arguments
+    /// * `data_dir` - The data directory
+    /// # Returns
+    /// The database of the display
+    pub fn init(data_dir: &Path) -> Result<&'static Persistence, PersistenceError> {
+        let persistence = Persistence::open(&data_dir.join(DB_NAME))?;
+        Ok(GLOBAL.get_or_init(|| persistence))
+    }
+
+    /// The database of the display opened by [Persistence::init]
+    /// In tests a temporary database is opened instead if none was opened.
+    /// # Panics
+    /// If the database was not opened at startup
+    pub fn global() -> &'static Persistence {
+        if cfg!(test) {
+            return GLOBAL.get_or_init(|| {
+                Persistence::temporary().expect("Could not open temporary database")
+            });
+        }
+        GLOBAL
+            .get()
+            .expect("Persistence::init must be called at startup")
+    }
+
+    /// Set values that take precedence over the saved ones, e.g. the ones of a configuration file
+    /// The overlay is applied whenever the configuration is saved or updated.
+    /// Only the first overlay set is used.
+    /// # Arguments
+    /// * `overlay` - Sets the values in a configuration
+    pub fn set_overlay(&self, overlay: fn(&mut SystemConfiguration)) {
+        let _ = self.overlay.set(overlay);
+    }
+
+    /// Save the system configuration, replacing the stored one
+    /// Use [Persistence::update_system_config] to change parts of it.
     /// # Arguments
     /// * `config` - The system configuration to save
-    pub fn save_system_config(config: SystemConfiguration) {
-        let serialized = serde_json::to_string(&config).expect("Could not serialize config");
-        DB.insert(Persistence::DB_KEY, serialized.as_bytes())
-            .expect("Could not save configuration");
-        CONFIG_UPDATED.store(true, Ordering::Relaxed);
+    pub fn save_system_config(
+        &self,
+        mut config: SystemConfiguration,
+    ) -> Result<(), PersistenceError> {
+        let _guard = self.lock_config();
+        self.apply_overlay(&mut config);
+        self.store_system_config(config)
     }
 
-    // Adds the default installation data for a widget
-    // # Arguments
-    // * `widget_name` - Name of the widget
-    // * `description` - Description of the widget
-    // * `version` - Version of the widget
-    pub fn add_default_installation_data(widget_name: &str, description: &str, version: &str) {
-        let config = Persistence::get_system_config().unwrap();
-        let mut widgets = config.widgets;
-        widgets.push(WidgetInstallationData {
-            name: widget_name.to_string(),
-            description: description.to_owned(),
-            version: version.to_owned(),
-            json_config: "{}".to_string(),
-        });
-        let new_config = SystemConfiguration { widgets, ..config };
-        Persistence::save_system_config(new_config);
+    /// Change the system configuration atomically
+    /// No other update can happen between loading and saving the configuration.
+    /// Nothing is saved if the configuration is not changed.
+    /// # Arguments
+    /// * `update` - Changes the loaded configuration
+    /// # Returns
+    /// The result of `update`
+    pub fn update_system_config<T>(
+        &self,
+        update: impl FnOnce(&mut SystemConfiguration) -> T,
+    ) -> Result<T, PersistenceError> {
+        self.try_update_system_config(|config| Ok::<T, PersistenceError>(update(config)))
     }
 
-    /// Load the system configuration
+    /// Change the system configuration atomically, if `update` succeeds
+    /// Like [Persistence::update_system_config], but nothing is saved if `update` returns an error.
+    /// # Arguments
+    /// * `update` - Changes the loaded configuration
     /// # Returns
-    /// The system configuration
-    pub fn get_system_config() -> Option<SystemConfiguration> {
-        let config = DB
-            .get(Persistence::DB_KEY)
-            .expect("FATAL: Could not read DB");
-        match config {
-            Some(bytes) => {
-                let config_str = String::from_utf8(bytes.to_vec())
-                    .expect("Could not convert config bytes to string");
-                Some(
-                    serde_json::from_str(&config_str).expect("Could not deserialize configuration"),
-                )
-            }
-            _ => {
-                Persistence::create_default_system_config();
-                Persistence::get_system_config()
+    /// The result of `update`
+    pub fn try_update_system_config<T, E: From<PersistenceError>>(
+        &self,
+        update: impl FnOnce(&mut SystemConfiguration) -> Result<T, E>,
+    ) -> Result<T, E> {
+        let _guard = self.lock_config();
+        let loaded = self.load_system_config()?;
+        let mut config = loaded.clone();
+        let result = update(&mut config)?;
+        self.apply_overlay(&mut config);
+        if config != loaded {
+            self.store_system_config(config)?;
+        }
+        Ok(result)
+    }
+
+    fn apply_overlay(&self, config: &mut SystemConfiguration) {
+        if let Some(overlay) = self.overlay.get() {
+            overlay(config);
+        }
+    }
+
+    fn lock_config(&self) -> MutexGuard<'_, ()> {
+        // The lock guards no data, a panic while holding it can not leave anything inconsistent
+        self.config_lock
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Write the system configuration, the config lock must be held
+    fn store_system_config(&self, config: SystemConfiguration) -> Result<(), PersistenceError> {
+        let serialized = migrations::serialize(&config)
+            .map_err(|err| PersistenceError::Serialization(err.to_string()))?;
+        self.db.insert(Persistence::DB_KEY, serialized.as_slice())?;
+        self.config_updated.store(true, Ordering::Relaxed);
+        self.record_config_revision(&serialized)
+    }
+
+    /// Keep a saved configuration as revision, so it can be restored later
+    /// Nothing is recorded if it equals the latest revision.
+    /// Only the latest `MAX_CONFIG_REVISIONS` revisions are kept.
+    /// # Arguments
+    /// * `serialized` - The configuration as stored
+    fn record_config_revision(&self, serialized: &[u8]) -> Result<(), PersistenceError> {
+        let config: serde_json::Value = serde_json::from_slice(serialized)
+            .map_err(|err| PersistenceError::Serialization(err.to_string()))?;
+        let db = &self.db;
+        let revisions = db.open_tree(Persistence::CONFIG_REVISION_TREE)?;
+        if let Some((_, latest)) = revisions.last()? {
+            let latest: Option<StoredRevision> = serde_json::from_slice(&latest).ok();
+            if latest.is_some_and(|latest| latest.config == config) {
+                return Ok(());
             }
         }
+
+        let revision = StoredRevision {
+            timestamp_ms: now_ms(),
+            config,
+        };
+        let revision = serde_json::to_vec(&revision)
+            .map_err(|err| PersistenceError::Serialization(err.to_string()))?;
+        // Generated ids are increasing, big endian keeps them sorted
+        revisions.insert(db.generate_id()?.to_be_bytes(), revision)?;
+        while revisions.len() > MAX_CONFIG_REVISIONS {
+            revisions.pop_min()?;
+        }
+        Ok(())
+    }
+
+    /// List the kept revisions of the system configuration
+    /// # Returns
+    /// The revisions, newest first
+    pub fn get_config_revisions(&self) -> Result<Vec<ConfigRevision>, PersistenceError> {
+        self.db
+            .open_tree(Persistence::CONFIG_REVISION_TREE)?
+            .iter()
+            .rev()
+            .map(|entry| {
+                let (key, value) = entry?;
+                let revision = Persistence::decode_revision(&key, &value)?;
+                Ok(ConfigRevision {
+                    id: revision_id(&key),
+                    timestamp_ms: revision.timestamp_ms,
+                })
+            })
+            .collect()
     }
 
-    /// Load json configuration for a specific widget
+    /// Load a revision of the system configuration
+    /// # Arguments
+    /// * `id` - Id of the revision
     /// # Returns
-    /// The widget configuration
-    pub fn get_widget_config(widget_name: &str) -> Option<String> {
-        let config = Persistence::get_system_config();
-        let Some(config) = config else {
-            return None;
+    /// The configuration, migrated to the current schema version
+    pub fn get_config_revision(
+        &self,
+        id: u64,
+    ) -> Result<Option<SystemConfiguration>, PersistenceError> {
+        let key = id.to_be_bytes();
+        let Some(value) = self
+            .db
+            .open_tree(Persistence::CONFIG_REVISION_TREE)?
+            .get(key)?
+        else {
+            return Ok(None);
         };
-        for widget in config.widgets {
-            if widget.name == widget_name {
-                return Some(widget.json_config);
+        let revision = Persistence::decode_revision(&key, &value)?;
+        let decoded = serde_json::to_vec(&revision.config)
+            .map_err(|err| err.to_string())
+            .and_then(|bytes| migrations::migrate(&bytes).map_err(|err| format!("{:?}", err)))
+            .and_then(|migrated| {
+                serde_json::from_value(migrated.config).map_err(|err| err.to_string())
+            });
+        decoded.map(Some).map_err(|reason| {
+            PersistenceError::Serialization(format!("Revision {} is invalid: {}", id, reason))
+        })
+    }
+
+    fn decode_revision(key: &[u8], value: &[u8]) -> Result<StoredRevision, PersistenceError> {
+        serde_json::from_slice(value).map_err(|err| {
+            PersistenceError::Serialization(format!(
+                "Revision {} is invalid: {}",
+                revision_id(key),
+                err
+            ))
+        })
+    }
+
+    /// Add the installation data of a widget
+    /// If the widget is installed already, only its version, granted permissions and source are updated,
+    /// so its configuration is kept when it is reinstalled. The granted permissions count as approved.
+    /// # Arguments
+    /// * `widget_name` - Name of the widget
+    /// * `description` - Description of the widget
+    /// * `version` - Version of the widget
+    /// * `permissions` - Permissions granted to the widget
+    /// * `source` - Where the widget was installed from. The recorded source is kept if unknown.
+    pub fn set_installation_data(
+        &self,
+        widget_name: &str,
+        description: &str,
+        version: &str,
+        permissions: WidgetPermissions,
+        source: Option<InstallAction>,
+    ) -> Result<(), PersistenceError> {
+        self.update_system_config(|config| {
+            match config
+                .widgets
+                .iter_mut()
+                .find(|widget| widget.name == widget_name)
+            {
+                Some(widget) => {
+                    widget.version = version.to_owned();
+                    widget.permissions = permissions;
+                    widget.approval_required = false;
+                    widget.reinstall_required = false;
+                    if source.is_some() {
+                        widget.source = source;
+                    }
+                }
+                None => config.widgets.push(WidgetInstallationData {
+                    name: widget_name.to_string(),
+                    description: description.to_owned(),
+                    version: version.to_owned(),
+                    json_config: "{}".to_string(),
+                    permissions,
+                    source,
+                    ..Default::default()
+                }),
             }
-        }
-        None
+        })
     }
 
-    /// Remove the installation data for a specific widget
+    /// Load the installation data of a specific widget
     /// # Returns
-    /// The widget configuration
-    pub fn remove_installation_data(widget_name: &str) {
-        let config = Persistence::get_system_config();
-        let Some(mut config) = config else {
-            return;
-        };
-        if let Some(index) = config
+    /// The installation data if the widget is installed
+    pub fn get_installation_data(
+        &self,
+        widget_name: &str,
+    ) -> Result<Option<WidgetInstallationData>, PersistenceError> {
+        Ok(self
+            .get_system_config()?
             .widgets
-            .iter()
-            .position(|config: &WidgetInstallationData| config.name == widget_name)
-        {
-            config.widgets.swap_remove(index);
+            .into_iter()
+            .find(|widget| widget.name == widget_name))
+    }
+
+    /// Load the system configuration
+    /// A default configuration is created if none is stored yet.
+    /// A configuration stored with an older schema version is migrated and saved again,
+    /// the stored original is kept as backup.
+    /// A configuration that can not be decoded is quarantined and replaced by the default,
+    /// so the display keeps working. The problem is reported by `get_quarantined_entries`.
+    /// # Returns
+    /// The system configuration
+    pub fn get_system_config(&self) -> Result<SystemConfiguration, PersistenceError> {
+        let _guard = self.lock_config();
+        self.load_system_config()
+    }
+
+    /// Load the system configuration, the config lock must be held
+    fn load_system_config(&self) -> Result<SystemConfiguration, PersistenceError> {
+        let Some(bytes) = self.db.get(Persistence::DB_KEY)? else {
+            self.create_default_system_config()?;
+            return Ok(SystemConfiguration::default());
+        };
+        let decoded = migrations::migrate(&bytes).and_then(|migrated| {
+            serde_json::from_value::<SystemConfiguration>(migrated.config.clone())
+                .map(|config| (config, migrated))
+                .map_err(|err| MigrationError::Invalid(err.to_string()))
+        });
+        match decoded {
+            Ok((config, migrated)) => {
+                if migrated.was_migrated() {
+                    self.backup_system_config(&bytes, migrated.from_version)?;
+                    self.store_system_config(config.clone())?;
+                }
+                Ok(config)
+            }
+            Err(MigrationError::UnsupportedVersion(version)) => {
+                Err(PersistenceError::UnsupportedVersion(version))
+            }
+            Err(MigrationError::Invalid(reason)) => {
+                self.quarantine(Persistence::DB_KEY, &bytes, &reason)?;
+                self.create_default_system_config()?;
+                Ok(SystemConfiguration::default())
+            }
         }
-        Persistence::save_system_config(config);
+    }
+
+    /// Keep a system configuration as it was stored before migrating it
+    /// # Arguments
+    /// * `bytes` - The stored configuration
+    /// * `schema_version` - The schema version it was stored with
+    fn backup_system_config(
+        &self,
+        bytes: &[u8],
+        schema_version: u32,
+    ) -> Result<(), PersistenceError> {
+        log::info!(
+            "{}: Migrating system configuration from schema version {} to {}",
+            LOGGING_PREFIX,
+            schema_version,
+            migrations::CURRENT_SCHEMA_VERSION
+        );
+        self.db.open_tree(Persistence::BACKUP_TREE)?.insert(
+            format!("{}.v{}", Persistence::DB_KEY, schema_version),
+            bytes,
+        )?;
+        Ok(())
+    }
+
+    /// Load the backup of the system configuration taken before migrating it from a schema version
+    /// # Arguments
+    /// * `schema_version` - The schema version the configuration was stored with
+    /// # Returns
+    /// The configuration as it was stored
+    pub fn get_system_config_backup(
+        &self,
+        schema_version: u32,
+    ) -> Result<Option<Vec<u8>>, PersistenceError> {
+        Ok(self
+            .db
+            .open_tree(Persistence::BACKUP_TREE)?
+            .get(format!("{}.v{}", Persistence::DB_KEY, schema_version))?
+            .map(|value| value.to_vec()))
+    }
+
+    /// Remove the installation data for a specific widget
+    /// # Arguments
+    /// * `widget_name` - Name of the widget
+    pub fn remove_installation_data(&self, widget_name: &str) -> Result<(), PersistenceError> {
+        self.update_system_config(|config| {
+            config.widgets.retain(|widget| widget.name != widget_name);
+        })
     }
 
     /// Returns Some system configuration if a new one is available
     /// Can be used for polling updates to the system configuration
     /// # Returns
     /// The system configuration if a new one is available
-    pub fn get_system_config_change() -> Option<SystemConfiguration> {
-        if CONFIG_UPDATED.load(Ordering::Relaxed) {
-            CONFIG_UPDATED.store(false, Ordering::Relaxed);
-            Some(Persistence::get_system_config().expect("Could not load config"))
+    pub fn get_system_config_change(
+        &self,
+    ) -> Result<Option<SystemConfiguration>, PersistenceError> {
+        if self.config_updated.swap(false, Ordering::Relaxed) {
+            self.get_system_config().map(Some)
         } else {
-            None
+            Ok(None)
         }
     }
 
@@ -121,74 +489,599 @@ impl Persistence {
     /// # Arguments
     /// * `key` - The key to save the data under
     /// * `compiled_widget` - The precompiled widget to save
-    pub fn save_compiled_widget(key: &str, compiled_widget: &CompiledWidget) {
-        let bytes =
-            bincode::serialize(compiled_widget).expect("Could not serialize compiled widget");
-        DB.insert(key, bytes)
-            .expect("Could not save compiled widget");
-        CONFIG_UPDATED.store(true, Ordering::Relaxed);
+    pub fn save_compiled_widget(
+        &self,
+        key: &str,
+        compiled_widget: &CompiledWidget,
+    ) -> Result<(), PersistenceError> {
+        let bytes = bincode::serialize(compiled_widget)
+            .map_err(|err| PersistenceError::Serialization(err.to_string()))?;
+        self.db.insert(key, bytes)?;
+        self.config_updated.store(true, Ordering::Relaxed);
+        Ok(())
     }
 
     /// Remove compiled widget from the database
     /// # Arguments
     /// * `key` - The key to remove
-    pub fn remove_compiled_widget(key: &str) {
-        DB.remove(key).expect("Could not remove compiled_widget");
-        CONFIG_UPDATED.store(true, Ordering::Relaxed);
+    pub fn remove_compiled_widget(&self, key: &str) -> Result<(), PersistenceError> {
+        self.db.remove(key)?;
+        self.config_updated.store(true, Ordering::Relaxed);
+        Ok(())
     }
 
     // Get compiled widget from the database
+    // A compiled widget that can not be decoded is quarantined, the widget has to be reinstalled
     // # Arguments
     // * `key` - The key to load
     // # Returns
     // The compiled widget
-    pub fn get_compiled_widget(key: &str) -> Option<CompiledWidget> {
-        let bytes = DB.get(key).expect("Could not read binary");
-        match bytes {
-            Some(bytes) => {
-                let compiled_widget =
-                    bincode::deserialize(bytes.as_ref()).expect("Could not deserialize binary");
-                Some(compiled_widget)
+    pub fn get_compiled_widget(
+        &self,
+        key: &str,
+    ) -> Result<Option<CompiledWidget>, PersistenceError> {
+        let Some(bytes) = self.db.get(key)? else {
+            return Ok(None);
+        };
+        match bincode::deserialize(bytes.as_ref()) {
+            Ok(compiled_widget) => Ok(Some(compiled_widget)),
+            Err(err) => {
+                let reason = err.to_string();
+                self.quarantine(key, &bytes, &reason)?;
+                Err(PersistenceError::Corrupt {
+                    key: key.to_owned(),
+                    reason,
+                })
             }
-            _ => None,
         }
     }
 
+    /// Save the WebAssembly component of a widget as it was installed
+    /// # Arguments
+    /// * `widget_name` - Name of the widget
+    /// * `bytes` - The component
+    pub fn save_widget_component(
+        &self,
+        widget_name: &str,
+        bytes: &[u8],
+    ) -> Result<(), PersistenceError> {
+        self.db
+            .open_tree(Persistence::WIDGET_COMPONENT_TREE)?
+            .insert(widget_name, bytes)?;
+        Ok(())
+    }
+
+    /// Load the WebAssembly component of a widget as it was installed
+    /// # Arguments
+    /// * `widget_name` - Name of the widget
+    /// # Returns
+    /// The component, `None` for widgets installed before components were kept
+    pub fn get_widget_component(
+        &self,
+        widget_name: &str,
+    ) -> Result<Option<Vec<u8>>, PersistenceError> {
+        Ok(self
+            .db
+            .open_tree(Persistence::WIDGET_COMPONENT_TREE)?
+            .get(widget_name)?
+            .map(|bytes| bytes.to_vec()))
+    }
+
+    /// Remove the WebAssembly component of a widget
+    /// # Arguments
+    /// * `widget_name` - Name of the widget
+    pub fn remove_widget_component(&self, widget_name: &str) -> Result<(), PersistenceError> {
+        self.db
+            .open_tree(Persistence::WIDGET_COMPONENT_TREE)?
+            .remove(widget_name)?;
+        Ok(())
+    }
+
+    /// Save the widget store listing cache
+    /// Does not count as a configuration change
+    /// # Arguments
+    /// * `cache` - The store listing to cache
+    pub fn save_widget_store_cache(
+        &self,
+        cache: &WidgetStoreCache,
+    ) -> Result<(), PersistenceError> {
+        let serialized = serde_json::to_string(cache)
+            .map_err(|err| PersistenceError::Serialization(err.to_string()))?;
+        self.db
+            .insert(Persistence::WIDGET_STORE_CACHE_KEY, serialized.as_bytes())?;
+        Ok(())
+    }
+
+    /// Load the widget store listing cache
+    /// A cache that can not be decoded (e.g. written by an older version) is treated as missing
+    /// # Returns
+    /// The cached store listing
+    pub fn get_widget_store_cache(&self) -> Result<Option<WidgetStoreCache>, PersistenceError> {
+        let bytes = self.db.get(Persistence::WIDGET_STORE_CACHE_KEY)?;
+        Ok(bytes.and_then(|bytes| serde_json::from_slice(bytes.as_ref()).ok()))
+    }
+
+    /// Load a value from the key-value storage of a widget
+    /// # Arguments
+    /// * `widget_name` - Name of the widget owning the storage
+    /// * `key` - The key to load
+    /// # Returns
+    /// The stored value
+    pub fn get_widget_storage_value(
+        &self,
+        widget_name: &str,
+        key: &str,
+    ) -> Result<Option<Vec<u8>>, PersistenceError> {
+        Ok(self
+            .widget_storage(widget_name)?
+            .get(key)?
+            .map(|value| value.to_vec()))
+    }
+
+    /// Save a value to the key-value storage of a widget
+    /// Does not count as a configuration change
+    /// # Arguments
+    /// * `widget_name` - Name of the widget owning the storage
+    /// * `key` - The key to save the value under
+    /// * `value` - The value to save
+    pub fn set_widget_storage_value(
+        &self,
+        widget_name: &str,
+        key: &str,
+        value: &[u8],
+    ) -> Result<(), PersistenceError> {
+        self.widget_storage(widget_name)?.insert(key, value)?;
+        Ok(())
+    }
+
+    /// Remove a value from the key-value storage of a widget
+    /// # Arguments
+    /// * `widget_name` - Name of the widget owning the storage
+    /// * `key` - The key to remove
+    pub fn delete_widget_storage_value(
+        &self,
+        widget_name: &str,
+        key: &str,
+    ) -> Result<(), PersistenceError> {
+        self.widget_storage(widget_name)?.remove(key)?;
+        Ok(())
+    }
+
+    /// List the keys in the key-value storage of a widget
+    /// # Arguments
+    /// * `widget_name` - Name of the widget owning the storage
+    /// * `prefix` - Only keys starting with this prefix are listed
+    /// # Returns
+    /// The keys in lexicographic order
+    pub fn list_widget_storage_keys(
+        &self,
+        widget_name: &str,
+        prefix: &str,
+    ) -> Result<Vec<String>, PersistenceError> {
+        self.widget_storage(widget_name)?
+            .scan_prefix(prefix)
+            .keys()
+            .map(|key| Ok(String::from_utf8_lossy(&key?).into_owned()))
+            .collect()
+    }
+
+    /// Compute the space used by the key-value storage of a widget
+    /// # Arguments
+    /// * `widget_name` - Name of the widget owning the storage
+    /// # Returns
+    /// The summed up size of all keys and values in bytes
+    pub fn get_widget_storage_usage(&self, widget_name: &str) -> Result<u64, PersistenceError> {
+        self.widget_storage(widget_name)?
+            .iter()
+            .map(|entry| entry.map(|(key, value)| (key.len() + value.len()) as u64))
+            .sum::<Result<u64, sled::Error>>()
+            .map_err(PersistenceError::from)
+    }
+
+    /// Remove the complete key-value storage of a widget
+    /// # Arguments
+    /// * `widget_name` - Name of the widget owning the storage
+    pub fn clear_widget_storage(&self, widget_name: &str) -> Result<(), PersistenceError> {
+        self.db
+            .drop_tree(Persistence::widget_storage_tree_name(widget_name))?;
+        Ok(())
+    }
+
+    fn widget_storage(&self, widget_name: &str) -> Result<sled::Tree, PersistenceError> {
+        Ok(self
+            .db
+            .open_tree(Persistence::widget_storage_tree_name(widget_name))?)
+    }
+
+    fn widget_storage_tree_name(widget_name: &str) -> String {
+        format!("widget_storage:{}", widget_name)
+    }
+
+    /// Save an encrypted secret of a widget
+    /// Does not count as a configuration change
+    /// # Arguments
+    /// * `widget_name` - Name of the widget owning the secret
+    /// * `name` - Name of the secret
+    /// * `encrypted` - The encrypted secret
+    pub fn save_secret(
+        &self,
+        widget_name: &str,
+        name: &str,
+        encrypted: &[u8],
+    ) -> Result<(), PersistenceError> {
+        self.widget_secrets(widget_name)?.insert(name, encrypted)?;
+        Ok(())
+    }
+
+    /// Load an encrypted secret of a widget
+    /// # Arguments
+    /// * `widget_name` - Name of the widget owning the secret
+    /// * `name` - Name of the secret
+    /// # Returns
+    /// The encrypted secret
+    pub fn get_secret(
+        &self,
+        widget_name: &str,
+        name: &str,
+    ) -> Result<Option<Vec<u8>>, PersistenceError> {
+        Ok(self
+            .widget_secrets(widget_name)?
+            .get(name)?
+            .map(|value| value.to_vec()))
+    }
+
+    /// Load the names of all secrets of a widget
+    /// # Arguments
+    /// * `widget_name` - Name of the widget owning the secrets
+    /// # Returns
+    /// The names in lexicographic order
+    pub fn get_secret_names(&self, widget_name: &str) -> Result<Vec<String>, PersistenceError> {
+        self.widget_secrets(widget_name)?
+            .iter()
+            .keys()
+            .map(|key| Ok(String::from_utf8_lossy(&key?).into_owned()))
+            .collect()
+    }
+
+    /// Remove a secret of a widget
+    /// # Arguments
+    /// * `widget_name` - Name of the widget owning the secret
+    /// * `name` - Name of the secret
+    pub fn remove_secret(&self, widget_name: &str, name: &str) -> Result<(), PersistenceError> {
+        self.widget_secrets(widget_name)?.remove(name)?;
+        Ok(())
+    }
+
+    /// Remove all secrets of a widget
+    /// # Arguments
+    /// * `widget_name` - Name of the widget owning the secrets
+    pub fn clear_secrets(&self, widget_name: &str) -> Result<(), PersistenceError> {
+        self.db
+            .drop_tree(format!("widget_secrets:{}", widget_name))?;
+        Ok(())
+    }
+
+    fn widget_secrets(&self, widget_name: &str) -> Result<sled::Tree, PersistenceError> {
+        Ok(self
+            .db
+            .open_tree(format!("widget_secrets:{}", widget_name))?)
+    }
+
+    /// List the entries that were moved to the quarantine because they could not be decoded
+    /// # Returns
+    /// The quarantined entries, oldest first
+    pub fn get_quarantined_entries(&self) -> Result<Vec<QuarantinedEntry>, PersistenceError> {
+        self.db
+            .open_tree(Persistence::QUARANTINE_TREE)?
+            .iter()
+            .values()
+            .map(|value| {
+                let value = value?;
+                serde_json::from_slice(&value)
+                    .map_err(|err| PersistenceError::Serialization(err.to_string()))
+            })
+            .collect()
+    }
+
+    /// Delete all quarantined entries, e.g. after the user took note of them
+    pub fn clear_quarantine(&self) -> Result<(), PersistenceError> {
+        let db = &self.db;
+        db.drop_tree(Persistence::QUARANTINE_TREE)?;
+        db.drop_tree(Persistence::QUARANTINE_DATA_TREE)?;
+        Ok(())
+    }
+
+    /// Move an entry that can not be decoded out of the way, keeping its data for inspection
+    /// # Arguments
+    /// * `key` - Key of the entry
+    /// * `bytes` - The raw value of the entry
+    /// * `reason` - Why the entry could not be decoded
+    fn quarantine(&self, key: &str, bytes: &[u8], reason: &str) -> Result<(), PersistenceError> {
+        log::error!(
+            "{}: Quarantined stored value {} as it could not be decoded: {}",
+            LOGGING_PREFIX,
+            key,
+            reason
+        );
+        let quarantined_at_ms = now_ms();
+        let entry = QuarantinedEntry {
+            key: key.to_owned(),
+            reason: reason.to_owned(),
+            quarantined_at_ms,
+            size: bytes.len() as u64,
+        };
+        let serialized = serde_json::to_vec(&entry)
+            .map_err(|err| PersistenceError::Serialization(err.to_string()))?;
+
+        let db = &self.db;
+        // Sortable by time, unique per key
+        let quarantine_key = format!("{:020}:{}", quarantined_at_ms, key);
+        db.open_tree(Persistence::QUARANTINE_DATA_TREE)?
+            .insert(&quarantine_key, bytes)?;
+        db.open_tree(Persistence::QUARANTINE_TREE)?
+            .insert(&quarantine_key, serialized)?;
+        db.remove(key)?;
+        Ok(())
+    }
+
     /// Create a default system configuration
     /// This is used on systems that never stored a configuration before
-    fn create_default_system_config() {
-        Persistence::save_system_config(SystemConfiguration::default());
+    fn create_default_system_config(&self) -> Result<(), PersistenceError> {
+        self.store_system_config(SystemConfiguration::default())
     }
 }
 
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |now| now.as_millis() as u64)
+}
+
+fn revision_id(key: &[u8]) -> u64 {
+    key.try_into().map_or(0, u64::from_be_bytes)
+}
+
 #[cfg(test)]
 mod tests {
 
     use super::*;
+    use common::models::Location;
 
     #[test]
     fn test_happy_day() {
+        let persistence = Persistence::temporary().unwrap();
         let config = SystemConfiguration {
             background_color: "#FF3A3A".to_string(),
             widgets: vec![],
+            disable_http_cache: false,
+            location: Some(Location {
+                latitude: 46.948,
+                longitude: 7.447,
+                locality: "Bern".to_string(),
+            }),
+            timezone: Some("Europe/Zurich".to_string()),
         };
-        Persistence::save_system_config(config.clone());
-        let read_config = Persistence::get_system_config();
-        assert!(read_config.is_some());
+        persistence.save_system_config(config.clone()).unwrap();
+        let read_config = persistence.get_system_config();
+        assert!(read_config.is_ok());
         assert_eq!(config, read_config.unwrap());
     }
 
+    #[test]
+    fn test_databases_are_separate() {
+        let persistence = Persistence::temporary().unwrap();
+        let other = Persistence::temporary().unwrap();
+        persistence
+            .set_widget_storage_value("widget", "key", b"value")
+            .unwrap();
+        assert_eq!(
+            other.get_widget_storage_value("widget", "key").unwrap(),
+            None
+        );
+        assert!(other.get_system_config_change().unwrap().is_none());
+    }
+
     #[test]
     fn test_get_compiled_widget_not_found() {
+        let persistence = Persistence::temporary().unwrap();
         let key = "non_existent_key";
-        let result = Persistence::get_compiled_widget(key);
+        let result = persistence.get_compiled_widget(key).unwrap();
         assert!(result.is_none());
     }
 
+    #[test]
+    fn test_corrupt_compiled_widget_is_quarantined() {
+        let persistence = Persistence::temporary().unwrap();
+        let key = "test_corrupt_compiled_widget";
+        persistence.db.insert(key, &[0xff, 0x00, 0x13]).unwrap();
+
+        let result = persistence.get_compiled_widget(key);
+        assert!(matches!(result, Err(PersistenceError::Corrupt { .. })));
+        // The entry is moved out of the way, so the next read does not fail again
+        assert!(persistence.get_compiled_widget(key).unwrap().is_none());
+        let quarantined = persistence.get_quarantined_entries().unwrap();
+        let entry = quarantined.iter().find(|entry| entry.key == key).unwrap();
+        assert_eq!(entry.size, 3);
+    }
+
+    #[test]
+    fn test_widget_store_cache() {
+        let persistence = Persistence::temporary().unwrap();
+        let cache = WidgetStoreCache {
+            etag: Some("\"abc\"".to_string()),
+            fetched_at: 42,
+            items: vec![],
+        };
+        persistence.save_widget_store_cache(&cache).unwrap();
+        assert_eq!(persistence.get_widget_store_cache().unwrap(), Some(cache));
+    }
+
+    #[test]
+    fn test_widget_storage() {
+        let persistence = Persistence::temporary().unwrap();
+        let widget = "test_widget_storage";
+        persistence.clear_widget_storage(widget).unwrap();
+        assert_eq!(
+            persistence.get_widget_storage_value(widget, "a").unwrap(),
+            None
+        );
+
+        persistence
+            .set_widget_storage_value(widget, "history/1", b"12")
+            .unwrap();
+        persistence
+            .set_widget_storage_value(widget, "history/2", b"13")
+            .unwrap();
+        persistence
+            .set_widget_storage_value(widget, "last", b"13")
+            .unwrap();
+        assert_eq!(
+            persistence
+                .get_widget_storage_value(widget, "last")
+                .unwrap(),
+            Some(b"13".to_vec())
+        );
+        assert_eq!(
+            persistence
+                .list_widget_storage_keys(widget, "history/")
+                .unwrap(),
+            vec!["history/1", "history/2"]
+        );
+        assert_eq!(
+            persistence.get_widget_storage_usage(widget).unwrap(),
+            9 + 2 + 9 + 2 + 4 + 2
+        );
+        // Storage of other widgets is separate
+        assert!(
+            persistence
+                .list_widget_storage_keys("other_widget", "")
+                .unwrap()
+                .is_empty()
+        );
+
+        persistence
+            .delete_widget_storage_value(widget, "last")
+            .unwrap();
+        assert_eq!(
+            persistence
+                .get_widget_storage_value(widget, "last")
+                .unwrap(),
+            None
+        );
+
+        persistence.clear_widget_storage(widget).unwrap();
+        assert!(
+            persistence
+                .list_widget_storage_keys(widget, "")
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_set_installation_data_keeps_config() {
+        let persistence = Persistence::temporary().unwrap();
+        let permissions = WidgetPermissions::default();
+        persistence
+            .set_installation_data("widget", "A widget", "1.0.0", permissions.clone(), None)
+            .unwrap();
+        persistence
+            .update_system_config(|config| config.widgets[0].json_config = "{\"a\":1}".to_string())
+            .unwrap();
+        persistence
+            .set_installation_data("widget", "A widget", "1.1.0", permissions, None)
+            .unwrap();
+
+        let config = persistence.get_system_config().unwrap();
+        assert_eq!(config.widgets.len(), 1);
+        assert_eq!(config.widgets[0].version, "1.1.0");
+        assert_eq!(config.widgets[0].json_config, "{\"a\":1}");
+    }
+
+    #[test]
+    fn test_failed_update_is_not_saved() {
+        let persistence = Persistence::temporary().unwrap();
+        let result: Result<(), PersistenceError> = persistence.try_update_system_config(|config| {
+            config.background_color = "#FFFFFF".to_string();
+            Err(PersistenceError::Serialization("invalid".to_string()))
+        });
+        assert!(result.is_err());
+        assert_eq!(
+            persistence.get_system_config().unwrap().background_color,
+            ""
+        );
+    }
+
+    #[test]
+    fn test_concurrent_installs() {
+        let persistence = Persistence::temporary().unwrap();
+        std::thread::scope(|scope| {
+            for thread in 0..8 {
+                let persistence = &persistence;
+                scope.spawn(move || {
+                    for widget in 0..10 {
+                        persistence
+                            .set_installation_data(
+                                &format!("widget_{}_{}", thread, widget),
+                                "",
+                                "1.0.0",
+                                WidgetPermissions::default(),
+                                None,
+                            )
+                            .unwrap();
+                    }
+                });
+            }
+        });
+        assert_eq!(persistence.get_system_config().unwrap().widgets.len(), 80);
+    }
+
+    #[test]
+    fn test_concurrent_config_updates() {
+        let persistence = Persistence::temporary().unwrap();
+        persistence
+            .set_installation_data("counter", "", "1.0.0", WidgetPermissions::default(), None)
+            .unwrap();
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    for _ in 0..25 {
+                        persistence
+                            .update_system_config(|config| {
+                                let widget = &mut config.widgets[0];
+                                let count: u32 = widget.json_config.parse().unwrap_or(0);
+                                widget.json_config = (count + 1).to_string();
+                            })
+                            .unwrap();
+                    }
+                });
+            }
+            // Installing and removing widgets in between must not lose any of the updates
+            scope.spawn(|| {
+                for _ in 0..25 {
+                    persistence
+                        .set_installation_data(
+                            "other",
+                            "",
+                            "1.0.0",
+                            WidgetPermissions::default(),
+                            None,
+                        )
+                        .unwrap();
+                    persistence.remove_installation_data("other").unwrap();
+                }
+            });
+        });
+        let config = persistence.get_system_config().unwrap();
+        assert_eq!(config.widgets.len(), 1);
+        assert_eq!(config.widgets[0].json_config, "200");
+    }
+
     #[test]
     fn test_create_default_system_config() {
-        Persistence::create_default_system_config();
-        let read_config = Persistence::get_system_config().unwrap();
+        let persistence = Persistence::temporary().unwrap();
+        persistence.create_default_system_config().unwrap();
+        let read_config = persistence.get_system_config().unwrap();
         assert_eq!(read_config.background_color, "".to_string());
         assert!(read_config.widgets.is_empty());
     }
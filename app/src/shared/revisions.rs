@@ -0,0 +1,202 @@
+//! Comparing and restoring revisions of the system configuration
+//! Every saved configuration is kept as a revision by [Persistence](super::persistence::Persistence).
+use common::models::{ConfigChange, SystemConfiguration};
+use rocket::serde::json::serde_json::{self, Value};
+
+/// Compute the changes between two configurations
+/// Widgets are identified by their name and their JSON configuration is compared field by field.
+/// # Arguments
+/// * `old` - The older configuration
+/// * `new` - The newer configuration
+/// # Returns
+/// The changed values, ordered by path
+pub fn diff(old: &SystemConfiguration, new: &SystemConfiguration) -> Vec<ConfigChange> {
+    let mut changes = Vec::new();
+    diff_values(
+        "",
+        Some(&comparable(old)),
+        Some(&comparable(new)),
+        &mut changes,
+    );
+    changes
+}
+
+/// Build the configuration to save when restoring a revision
+/// Only settings are restored: widgets that are not installed anymore are left out
+/// and the version, granted permissions and source of installed widgets are kept,
+/// as they can only be changed by (re)installing a widget.
+/// The WASI grants are kept as well, restoring must not give a widget access the user revoked since.
+/// # Arguments
+/// * `current` - The current configuration
+/// * `revision` - The configuration of the revision to restore
+/// # Returns
+/// The configuration to save
+pub fn restore(
+    current: &SystemConfiguration,
+    revision: SystemConfiguration,
+) -> SystemConfiguration {
+    let widgets = current
+        .widgets
+        .iter()
+        .map(|installed| {
+            let mut widget = installed.clone();
+            if let Some(restored) = revision
+                .widgets
+                .iter()
+                .find(|widget| widget.name == installed.name)
+            {
+                widget.json_config = restored.json_config.clone();
+                widget.http_rate_limit = restored.http_rate_limit.clone();
+            }
+            widget
+        })
+        .collect();
+    SystemConfiguration {
+        widgets,
+        ..revision
+    }
+}
+
+/// Convert a configuration to JSON that can be compared field by field
+/// Widgets become an object keyed by name and their JSON configuration is parsed.
+fn comparable(config: &SystemConfiguration) -> Value {
+    let mut value = serde_json::to_value(config).unwrap_or_default();
+    let widgets = config
+        .widgets
+        .iter()
+        .map(|widget| {
+            let mut widget_value = serde_json::to_value(widget).unwrap_or_default();
+            if let (Some(object), Ok(json_config)) = (
+                widget_value.as_object_mut(),
+                serde_json::from_str::<Value>(&widget.json_config),
+            ) {
+                object.insert("json_config".to_string(), json_config);
+            }
+            (widget.name.clone(), widget_value)
+        })
+        .collect();
+    if let Some(object) = value.as_object_mut() {
+        object.insert("widgets".to_string(), Value::Object(widgets));
+    }
+    value
+}
+
+fn diff_values(
+    path: &str,
+    old: Option<&Value>,
+    new: Option<&Value>,
+    changes: &mut Vec<ConfigChange>,
+) {
+    match (old, new) {
+        (Some(Value::Object(old)), Some(Value::Object(new))) => {
+            let mut keys: Vec<&String> = old.keys().chain(new.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                let path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                diff_values(&path, old.get(key), new.get(key), changes);
+            }
+        }
+        (old, new) if old != new => changes.push(ConfigChange {
+            path: path.to_string(),
+            old: old.map(Value::to_string),
+            new: new.map(Value::to_string),
+        }),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::models::{WasiGrants, WidgetInstallationData};
+
+    fn widget(name: &str, json_config: &str) -> WidgetInstallationData {
+        WidgetInstallationData {
+            name: name.to_string(),
+            version: "1.0.0".to_string(),
+            json_config: json_config.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn config(widgets: Vec<WidgetInstallationData>) -> SystemConfiguration {
+        SystemConfiguration {
+            background_color: "#000000".to_string(),
+            widgets,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_diff_identical() {
+        let config = config(vec![widget("weather", "{\"city\":\"Bern\"}")]);
+        assert!(diff(&config, &config).is_empty());
+    }
+
+    #[test]
+    fn test_diff_widget_config() {
+        let old = config(vec![widget(
+            "weather",
+            "{\"city\":\"Bern\",\"unit\":\"C\"}",
+        )]);
+        let mut new = config(vec![widget("weather", "{\"city\":\"Thun\"}")]);
+        new.background_color = "#FFFFFF".to_string();
+        assert_eq!(
+            diff(&old, &new),
+            vec![
+                ConfigChange {
+                    path: "background_color".to_string(),
+                    old: Some("\"#000000\"".to_string()),
+                    new: Some("\"#FFFFFF\"".to_string()),
+                },
+                ConfigChange {
+                    path: "widgets.weather.json_config.city".to_string(),
+                    old: Some("\"Bern\"".to_string()),
+                    new: Some("\"Thun\"".to_string()),
+                },
+                ConfigChange {
+                    path: "widgets.weather.json_config.unit".to_string(),
+                    old: Some("\"C\"".to_string()),
+                    new: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diff_added_widget() {
+        let old = config(vec![]);
+        let new = config(vec![widget("weather", "{}")]);
+        let changes = diff(&old, &new);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].path, "widgets.weather");
+        assert_eq!(changes[0].old, None);
+    }
+
+    #[test]
+    fn test_restore_keeps_installed_widgets() {
+        let mut installed = widget("weather", "{\"city\":\"Thun\"}");
+        installed.version = "2.0.0".to_string();
+        let current = config(vec![installed, widget("clock", "{}")]);
+
+        let mut restored_weather = widget("weather", "{\"city\":\"Bern\"}");
+        restored_weather.wasi_grants = WasiGrants {
+            scratch_directory: true,
+        };
+        let mut revision = config(vec![restored_weather, widget("deinstalled", "{}")]);
+        revision.background_color = "#FFFFFF".to_string();
+
+        let restored = restore(&current, revision);
+        assert_eq!(restored.background_color, "#FFFFFF");
+        assert_eq!(restored.widgets.len(), 2);
+        assert_eq!(restored.widgets[0].json_config, "{\"city\":\"Bern\"}");
+        assert_eq!(restored.widgets[0].version, "2.0.0");
+        assert!(!restored.widgets[0].wasi_grants.scratch_directory);
+        assert_eq!(restored.widgets[1].name, "clock");
+    }
+}
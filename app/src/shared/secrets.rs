@@ -0,0 +1,285 @@
+//! Secrets of widgets, such as API tokens
+//!
+//! Secrets are stored encrypted in the database using a key kept in a separate file.
+//! They can be written and deleted through the dashboard, but only the widget owning them can read them.
+use std::{
+    ffi::OsString,
+    fs, io,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use anyhow::{anyhow, Context, Error};
+use chacha20poly1305::{
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
+    ChaCha20Poly1305, Key, Nonce,
+};
+
+use super::data_dir;
+use super::persistence::Persistence;
+
+/// Name of the key file in the data directory
+pub const KEY_FILE: &str = "wg_display.key";
+
+/// Length of the nonce prepended to every encrypted secret
+const NONCE_LENGTH: usize = 12;
+
+/// Secrets of the widgets stored in a database, encrypted with the key in a key file
+pub struct Secrets<'a> {
+    persistence: &'a Persistence,
+    key_file: PathBuf,
+}
+
+impl Secrets<'_> {
+    /// The secrets of the display, stored in its database with the key in the data directory
+    pub fn global() -> Secrets<'static> {
+        Secrets::new(Persistence::global(), data_dir::get().join(KEY_FILE))
+    }
+
+    /// Access the secrets stored in a database
+    /// # Arguments
+    /// * `persistence` - The database storing the encrypted secrets
+    /// * `key_file` - The file holding the encryption key, created on first use
+    pub fn new(persistence: &Persistence, key_file: PathBuf) -> Secrets<'_> {
+        Secrets {
+            persistence,
+            key_file,
+        }
+    }
+
+    /// Save a secret of a widget
+    /// # Arguments
+    /// * `widget_name` - Name of the widget owning the secret
+    /// * `name` - Name of the secret
+    /// * `value` - The secret
+    /// # Returns
+    /// An error if the secret could not be encrypted
+    pub fn set_secret(&self, widget_name: &str, name: &str, value: &str) -> Result<(), Error> {
+        let cipher = self.cipher()?;
+        let encrypted = encrypt(&cipher, widget_name, name, value)?;
+        self.persistence.save_secret(widget_name, name, &encrypted)?;
+        Ok(())
+    }
+
+    /// Load a secret of a widget
+    /// # Arguments
+    /// * `widget_name` - Name of the widget owning the secret
+    /// * `name` - Name of the secret
+    /// # Returns
+    /// The secret if it exists or an error if it could not be decrypted
+    pub fn get_secret(&self, widget_name: &str, name: &str) -> Result<Option<String>, Error> {
+        let Some(encrypted) = self.persistence.get_secret(widget_name, name)? else {
+            return Ok(None);
+        };
+        let cipher = self.cipher()?;
+        decrypt(&cipher, widget_name, name, &encrypted).map(Some)
+    }
+
+    /// Load the names of all secrets of a widget. Values are never returned.
+    /// # Arguments
+    /// * `widget_name` - Name of the widget owning the secrets
+    pub fn get_secret_names(&self, widget_name: &str) -> Result<Vec<String>, Error> {
+        Ok(self.persistence.get_secret_names(widget_name)?)
+    }
+
+    /// Remove a secret of a widget
+    /// # Arguments
+    /// * `widget_name` - Name of the widget owning the secret
+    /// * `name` - Name of the secret
+    pub fn remove_secret(&self, widget_name: &str, name: &str) -> Result<(), Error> {
+        Ok(self.persistence.remove_secret(widget_name, name)?)
+    }
+
+    /// Remove all secrets of a widget
+    /// # Arguments
+    /// * `widget_name` - Name of the widget owning the secrets
+    pub fn clear_secrets(&self, widget_name: &str) -> Result<(), Error> {
+        Ok(self.persistence.clear_secrets(widget_name)?)
+    }
+
+    /// Load the encryption key, generates it on first use
+    fn cipher(&self) -> Result<ChaCha20Poly1305, Error> {
+        let path = self.key_file.as_path();
+        let key = match fs::read(path) {
+            Ok(key) => key,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => create_key_file(path)?,
+            Err(err) => return Err(err).context("Could not read secrets key"),
+        };
+        if key.len() != 32 {
+            return Err(anyhow!("Secrets key in {} is corrupt", path.display()));
+        }
+        Ok(ChaCha20Poly1305::new(Key::from_slice(&key)))
+    }
+}
+
+/// Generate the key and create the key file
+/// The key is written to a temporary file that is linked into place once complete, so the key file
+/// is never seen half written. If another thread or process created the key file first, its key is used.
+/// # Returns
+/// The key in the key file
+fn create_key_file(path: &Path) -> Result<Vec<u8>, Error> {
+    static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+    let mut temporary_path = OsString::from(path);
+    temporary_path.push(format!(
+        ".{}-{}.tmp",
+        std::process::id(),
+        NEXT_ID.fetch_add(1, Ordering::Relaxed)
+    ));
+    let temporary_path = PathBuf::from(temporary_path);
+
+    let key = ChaCha20Poly1305::generate_key(&mut OsRng);
+    let linked = write_key_file(&temporary_path, key.as_slice())
+        .and_then(|_| fs::hard_link(&temporary_path, path).context("Could not create secrets key"));
+    let _ = fs::remove_file(&temporary_path);
+    match linked {
+        Ok(()) => Ok(key.to_vec()),
+        Err(err)
+            if err
+                .downcast_ref::<io::Error>()
+                .is_some_and(|err| err.kind() == io::ErrorKind::AlreadyExists) =>
+        {
+            fs::read(path).context("Could not read secrets key")
+        }
+        Err(err) => Err(err),
+    }
+}
+
+/// Write the key so that it is only readable by the current user
+fn write_key_file(path: &Path, key: &[u8]) -> Result<(), Error> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options
+        .open(path)
+        .context("Could not create secrets key")?;
+    // Set explicitly, as the mode passed on creation is masked by the umask
+    #[cfg(unix)]
+    file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(0o600))
+        .context("Could not restrict access to secrets key")?;
+    file.write_all(key).context("Could not write secrets key")?;
+    Ok(())
+}
+
+/// The owner and name of a secret are authenticated together with it,
+/// so an encrypted secret can not be moved to another widget
+fn associated_data(widget_name: &str, name: &str) -> Vec<u8> {
+    [widget_name.as_bytes(), b"\0", name.as_bytes()].concat()
+}
+
+fn encrypt(
+    cipher: &ChaCha20Poly1305,
+    widget_name: &str,
+    name: &str,
+    value: &str,
+) -> Result<Vec<u8>, Error> {
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let payload = Payload {
+        msg: value.as_bytes(),
+        aad: &associated_data(widget_name, name),
+    };
+    let ciphertext = cipher
+        .encrypt(&nonce, payload)
+        .map_err(|_| anyhow!("Could not encrypt secret"))?;
+    Ok([nonce.as_slice(), &ciphertext].concat())
+}
+
+fn decrypt(
+    cipher: &ChaCha20Poly1305,
+    widget_name: &str,
+    name: &str,
+    encrypted: &[u8],
+) -> Result<String, Error> {
+    if encrypted.len() < NONCE_LENGTH {
+        return Err(anyhow!("Secret {} of {} is corrupt", name, widget_name));
+    }
+    let (nonce, ciphertext) = encrypted.split_at(NONCE_LENGTH);
+    let payload = Payload {
+        msg: ciphertext,
+        aad: &associated_data(widget_name, name),
+    };
+    let plaintext = cipher
+        .decrypt(Nonce::from_slice(nonce), payload)
+        .map_err(|_| anyhow!("Could not decrypt secret {} of {}", name, widget_name))?;
+    Ok(String::from_utf8(plaintext)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encryption_roundtrip() {
+        let cipher = ChaCha20Poly1305::new(&ChaCha20Poly1305::generate_key(&mut OsRng));
+        let encrypted = encrypt(&cipher, "Weather", "api_key", "s3cr3t").unwrap();
+        assert!(!encrypted
+            .windows("s3cr3t".len())
+            .any(|window| window == b"s3cr3t"));
+        assert_eq!(
+            decrypt(&cipher, "Weather", "api_key", &encrypted).unwrap(),
+            "s3cr3t"
+        );
+        // Secrets are bound to their owner and name
+        assert!(decrypt(&cipher, "Other", "api_key", &encrypted).is_err());
+        assert!(decrypt(&cipher, "Weather", "token", &encrypted).is_err());
+    }
+
+    #[test]
+    fn test_set_and_get_secret() {
+        let persistence = Persistence::temporary().unwrap();
+        let key_file = std::env::temp_dir().join(format!(
+            "wg_display_test_secrets_{}.key",
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&key_file);
+        let secrets = Secrets::new(&persistence, key_file.clone());
+
+        let widget = "test_set_and_get_secret";
+        secrets.set_secret(widget, "api_key", "s3cr3t").unwrap();
+        assert_eq!(
+            secrets.get_secret(widget, "api_key").unwrap(),
+            Some("s3cr3t".to_string())
+        );
+        assert_eq!(secrets.get_secret_names(widget).unwrap(), vec!["api_key"]);
+        assert_eq!(secrets.get_secret("other_widget", "api_key").unwrap(), None);
+
+        secrets.remove_secret(widget, "api_key").unwrap();
+        assert_eq!(secrets.get_secret(widget, "api_key").unwrap(), None);
+
+        secrets.set_secret(widget, "token", "t0k3n").unwrap();
+        secrets.clear_secrets(widget).unwrap();
+        assert!(secrets.get_secret_names(widget).unwrap().is_empty());
+        fs::remove_file(key_file).unwrap();
+    }
+
+    #[test]
+    fn test_concurrent_key_creation() {
+        let key_file = std::env::temp_dir().join(format!(
+            "wg_display_test_concurrent_{}.key",
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&key_file);
+
+        let keys: Vec<Vec<u8>> = std::thread::scope(|scope| {
+            let threads: Vec<_> = (0..8)
+                .map(|_| scope.spawn(|| create_key_file(&key_file).unwrap()))
+                .collect();
+            threads
+                .into_iter()
+                .map(|thread| thread.join().unwrap())
+                .collect()
+        });
+        // All threads use the key that was created first
+        let key = fs::read(&key_file).unwrap();
+        assert_eq!(key.len(), 32);
+        assert!(keys.iter().all(|other| *other == key));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&key_file).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        fs::remove_file(key_file).unwrap();
+    }
+}
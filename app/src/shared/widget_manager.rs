@@ -1,39 +1,161 @@
+use crate::widgets::running::health::HealthRegistry;
+use crate::widgets::running::http_client::shared_http_client;
 use crate::widgets::running::runtime::{CompiledWidget, Runtime};
-use anyhow::Error;
+use crate::widgets::running::wasi::remove_scratch_directory;
+use crate::widgets::store::widget_store::WidgetStore;
+use crate::widgets::utils::logging::LOGGING_PREFIX;
+use crate::widgets::utils::widget_logs::WidgetLogs;
+use anyhow::{bail, Error};
+use common::config_schema;
+use common::models::{
+    InstallAction, InstallResponse, LogLevel, LogSource, TestRunReport, WidgetInstallationData,
+    WidgetPermissions,
+};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
 
 use super::persistence::Persistence;
+use super::secrets::Secrets;
+
+/// Longest time a test run may take, the widget is interrupted then
+const TEST_RUN_TIMEOUT: Duration = Duration::from_secs(10);
 
 pub struct WidgetManager;
 
 impl WidgetManager {
     /// Download and persist a widget
     /// Also adds a default configuration for the widget
+    /// The installation only completes once the user approved the permissions the widget requests
     /// # Arguments
     /// * `download_url` - The URL to download the widget from
     /// * `description` - A description of the widget
+    /// * `source` - Where the widget is installed from, recorded to install it again on another display
+    /// * `granted_permissions` - The permissions the user approved
     /// # Returns
+    /// Whether the widget was installed or its permissions need to be approved first.
     /// An error if the download or persisting failed
-    pub async fn install_widget(download_url: &str, description: &str) -> Result<(), Error> {
-        let response = reqwest::get(download_url).await?;
-        let bytes = response.bytes().await?.to_vec();
+    pub async fn install_widget(
+        download_url: &str,
+        description: &str,
+        source: InstallAction,
+        granted_permissions: Option<&WidgetPermissions>,
+    ) -> Result<InstallResponse, Error> {
+        let bytes = WidgetManager::download_widget(download_url).await?;
+        WidgetManager::install_widget_bytes(&bytes, description, Some(source), granted_permissions)
+    }
+
+    /// Download a widget
+    /// # Arguments
+    /// * `download_url` - The URL to download the widget from
+    /// # Returns
+    /// The WebAssembly component of the widget
+    pub async fn download_widget(download_url: &str) -> Result<Vec<u8>, Error> {
+        let response = reqwest::get(download_url).await?.error_for_status()?;
+        Ok(response.bytes().await?.to_vec())
+    }
+
+    /// Download a widget again from where it was installed from
+    /// # Arguments
+    /// * `source` - Where the widget was installed from
+    /// * `store` - The widget store, fetched on first use
+    /// # Returns
+    /// The WebAssembly component of the widget
+    pub async fn download_from_source(
+        source: &InstallAction,
+        store: &mut Option<WidgetStore>,
+    ) -> Result<Vec<u8>, Error> {
+        let download_url = match source {
+            InstallAction::FromUrl(url) => url.clone(),
+            InstallAction::FromStoreItemName(name) => {
+                if store.is_none() {
+                    let mut fetched = WidgetStore::new();
+                    fetched.fetch_from_store().await?;
+                    *store = Some(fetched);
+                }
+                let item = store
+                    .as_ref()
+                    .and_then(|store| store.find_item(name))
+                    .ok_or_else(|| {
+                        anyhow::anyhow!("Widget {} does not exist in the store", name)
+                    })?;
+                if !item.supports_host_version(env!("CARGO_PKG_VERSION")) {
+                    bail!(
+                        "Widget {} requires WG Display {} or newer",
+                        name,
+                        item.min_host_version.clone().unwrap_or_default()
+                    );
+                }
+                item.get_download_url()
+            }
+        };
+        WidgetManager::download_widget(&download_url).await
+    }
 
+    /// Compile and persist a widget
+    /// Also adds a default configuration for the widget.
+    /// An upgrade keeps the configuration and logs a warning if it does not match the new schema.
+    /// The installation only completes once the user approved the permissions the widget requests
+    /// # Arguments
+    /// * `bytes` - The WebAssembly component of the widget
+    /// * `description` - A description of the widget
+    /// * `source` - Where the widget was installed from, if known
+    /// * `granted_permissions` - The permissions the user approved
+    /// # Returns
+    /// Whether the widget was installed or its permissions need to be approved first.
+    /// An error if compiling or persisting failed
+    pub fn install_widget_bytes(
+        bytes: &[u8],
+        description: &str,
+        source: Option<InstallAction>,
+        granted_permissions: Option<&WidgetPermissions>,
+    ) -> Result<InstallResponse, Error> {
         let mut runtime = Runtime::new();
-        let compiled_widget = runtime.compile_widget(&bytes)?;
+        let compiled_widget = runtime.compile_widget(bytes)?;
         let widget = runtime.instantiate_widget(&compiled_widget)?;
-        let widget_name = runtime.get_widget_name(&widget)?;
+        let widget_name = widget.name().to_owned();
         let version = runtime.get_widget_version(&widget)?;
+        let permissions = runtime.get_widget_permissions(&widget)?;
+
+        if !permissions.is_empty() && granted_permissions != Some(&permissions) {
+            return Ok(InstallResponse::ApprovalRequired {
+                widget_name,
+                permissions,
+            });
+        }
 
-        Persistence::save_compiled_widget(widget_name.as_str(), &compiled_widget);
+        let previous = Persistence::global().get_installation_data(widget_name.as_str())?;
+        Persistence::global().save_compiled_widget(widget_name.as_str(), &compiled_widget)?;
+        // Compiled widgets only run on the platform they were compiled on, the component is kept for exports
+        Persistence::global().save_widget_component(widget_name.as_str(), bytes)?;
 
-        if Persistence::get_widget_config(widget_name.as_str()).is_none() {
-            Persistence::add_default_installation_data(
-                widget_name.as_str(),
-                description,
-                version.as_str(),
-            );
+        Persistence::global().set_installation_data(
+            widget_name.as_str(),
+            description,
+            version.as_str(),
+            permissions,
+            source,
+        )?;
+
+        // An upgrade keeps the configuration, which may not fit the new version
+        if let Some(previous) = previous {
+            let schema = runtime.get_config_schema(&widget)?;
+            if let Err(errors) = config_schema::validate_str(&schema, &previous.json_config) {
+                let errors: Vec<String> = errors.iter().map(ToString::to_string).collect();
+                WidgetLogs::record(
+                    &widget_name,
+                    LogLevel::Warn,
+                    LogSource::Host,
+                    &format!(
+                        "The configuration does not match version {}, configure the widget again: {}",
+                        version,
+                        errors.join("; ")
+                    ),
+                );
+            }
         }
 
-        Ok(())
+        Ok(InstallResponse::Installed)
     }
 
     /// Deinstall a widget
@@ -42,32 +164,316 @@ impl WidgetManager {
     /// # Returns
     /// An error if the deinstallation failed
     pub async fn deinstall_widget(widget_name: &str) -> Result<(), Error> {
-        Persistence::remove_compiled_widget(widget_name);
-        Persistence::remove_installation_data(widget_name);
+        Persistence::global().remove_compiled_widget(widget_name)?;
+        Persistence::global().remove_widget_component(widget_name)?;
+        Persistence::global().remove_installation_data(widget_name)?;
+        Persistence::global().clear_widget_storage(widget_name)?;
+        Secrets::global().clear_secrets(widget_name)?;
+        WidgetLogs::clear(widget_name);
+        HealthRegistry::clear(widget_name);
+        shared_http_client().forget_widget(widget_name);
+        remove_scratch_directory(widget_name)?;
         Ok(())
     }
 
+    /// Get the permissions an installed widget requests
+    /// # Arguments
+    /// * `widget_name` - The name of the widget
+    /// # Returns
+    /// The permissions declared by the widget, which may differ from the granted ones
+    pub fn get_requested_permissions(widget_name: &str) -> Result<WidgetPermissions, Error> {
+        let mut runtime = Runtime::new();
+        let compiled_widget = WidgetManager::get_widget(widget_name)?;
+        let widget = runtime.instantiate_widget(&compiled_widget)?;
+        runtime.get_widget_permissions(&widget)
+    }
+
+    /// Grant an installed widget the permissions it requests
+    /// Used for widgets that were installed before widgets had to request permissions.
+    /// # Arguments
+    /// * `widget_name` - The name of the widget
+    /// * `granted_permissions` - The permissions the user approved
+    /// # Returns
+    /// Whether the permissions were granted.
+    /// `false` if they do not match the permissions the widget requests
+    pub fn approve_permissions(
+        widget_name: &str,
+        granted_permissions: &WidgetPermissions,
+    ) -> Result<bool, Error> {
+        let requested = WidgetManager::get_requested_permissions(widget_name)?;
+        if &requested != granted_permissions {
+            return Ok(false);
+        }
+        Persistence::global().update_system_config(|config| {
+            for widget in config.widgets.iter_mut() {
+                if widget.name == widget_name {
+                    widget.permissions = requested.clone();
+                    widget.approval_required = false;
+                }
+            }
+        })?;
+        Ok(true)
+    }
+
+    /// Get the configuration schema of an installed widget
+    /// # Arguments
+    /// * `widget_name` - The name of the widget
+    /// # Returns
+    /// The JSON Schema the widget's configuration has to match
+    pub fn get_config_schema(widget_name: &str) -> Result<String, Error> {
+        let mut runtime = Runtime::new();
+        let compiled_widget = WidgetManager::get_widget(widget_name)?;
+        let widget = runtime.instantiate_widget(&compiled_widget)?;
+        runtime.get_config_schema(&widget)
+    }
+
+    /// Check a configuration of an installed widget against the widget's configuration schema
+    /// # Arguments
+    /// * `widget_name` - The name of the widget
+    /// * `json_config` - The configuration to check
+    /// # Returns
+    /// The configuration, normalized to compact JSON, or an error naming the fields that do not match
+    pub fn validate_config(widget_name: &str, json_config: &str) -> Result<String, Error> {
+        let schema = WidgetManager::get_config_schema(widget_name)?;
+        config_schema::validate_str(&schema, json_config)
+            .map(|config| config.to_string())
+            .map_err(|errors| {
+                let errors: Vec<String> = errors.iter().map(ToString::to_string).collect();
+                anyhow::anyhow!(
+                    "The configuration does not match the schema of {}: {}",
+                    widget_name,
+                    errors.join("; ")
+                )
+            })
+    }
+
+    /// Run an installed widget once with a configuration that is not saved
+    /// The run uses a fresh runtime, so the widget is not affected by the display's update cycle.
+    /// It is interrupted if it does not finish within 10 seconds.
+    /// Changes to its storage and scratch directory are discarded afterwards.
+    /// Everything else is as in a regular run, e.g. its HTTP requests count against its rate limit.
+    /// # Arguments
+    /// * `widget_name` - The name of the widget
+    /// * `json_config` - The configuration to run the widget with
+    /// # Returns
+    /// The output or error of the run together with its logs and duration.
+    /// An error if the widget could not be loaded
+    pub fn test_run(widget_name: &str, json_config: String) -> Result<TestRunReport, Error> {
+        let installation = Persistence::global()
+            .get_installation_data(widget_name)?
+            .ok_or(anyhow::anyhow!("Widget {} is not installed", widget_name))?;
+        let installation = WidgetInstallationData {
+            json_config,
+            ..installation
+        };
+
+        let mut runtime = Runtime::new();
+        runtime.apply_system_config(&Persistence::global().get_system_config()?);
+        let compiled_widget = WidgetManager::get_widget(widget_name)?;
+        let widget = runtime.instantiate_widget(&compiled_widget)?;
+
+        // Interrupts the run once the deadline passed, unless it finished and dropped `finished` before
+        let (finished, finished_rx) = mpsc::channel::<()>();
+        let interrupter = runtime.interrupter();
+        let deadline = thread::spawn(move || {
+            let timed_out = matches!(
+                finished_rx.recv_timeout(TEST_RUN_TIMEOUT),
+                Err(RecvTimeoutError::Timeout)
+            );
+            if timed_out {
+                interrupter.interrupt();
+            }
+            timed_out
+        });
+
+        let start = Instant::now();
+        let (result, logs) = WidgetLogs::capture(widget_name, || {
+            runtime.test_run_widget(&widget, &installation)
+        });
+        let duration = start.elapsed();
+        drop(finished);
+        let timed_out = deadline.join().unwrap_or(false);
+        let (output, error) = match result {
+            Ok(output) => (Some(output), None),
+            Err(_) if timed_out => (
+                None,
+                Some(format!(
+                    "The widget did not finish within {} seconds",
+                    TEST_RUN_TIMEOUT.as_secs()
+                )),
+            ),
+            Err(err) => (None, Some(format!("{:#}", err))),
+        };
+        Ok(TestRunReport {
+            output,
+            error,
+            logs,
+            duration_ms: duration.as_millis() as u64,
+        })
+    }
+
     /// Get a previously installed widget
     /// # Arguments
     /// * `widget_name` - The name of the widget to get
     /// # Returns
     /// The compiled widget
     pub fn get_widget(widget_name: &str) -> Result<CompiledWidget, Error> {
-        let widget = Persistence::get_compiled_widget(widget_name)
-            .ok_or(anyhow::anyhow!("Could not get widget"))?;
-        Ok(widget)
+        WidgetManager::load_widget(widget_name)?.ok_or(anyhow::anyhow!("Could not get widget"))
+    }
+
+    /// Load a previously installed widget, recompiling it if needed
+    /// Widgets are precompiled for the settings of the engine, which can change with an update of WG Display.
+    /// # Arguments
+    /// * `widget_name` - The name of the widget to load
+    /// # Returns
+    /// The compiled widget or `None` if it is not installed
+    fn load_widget(widget_name: &str) -> Result<Option<CompiledWidget>, Error> {
+        let Some(widget) = Persistence::global().get_compiled_widget(widget_name)? else {
+            return Ok(None);
+        };
+        if !widget.needs_recompilation() {
+            return Ok(Some(widget));
+        }
+        let bytes = Persistence::global()
+            .get_widget_component(widget_name)?
+            .ok_or(anyhow::anyhow!(
+                "Widget {} was compiled by another version of WG Display, reinstall it",
+                widget_name
+            ))?;
+        log::info!("{}: Recompiling widget {}", LOGGING_PREFIX, widget_name);
+        let widget = Runtime::new().compile_widget(&bytes)?;
+        Persistence::global().save_compiled_widget(widget_name, &widget)?;
+        Ok(Some(widget))
+    }
+
+    /// Recompile the widgets that were compiled by a previous version of WG Display, run on startup
+    /// Widgets installed before their component was kept are downloaded again from their source,
+    /// as long as it still provides the installed version. Widgets that can not be recompiled are
+    /// marked as `reinstall_required`, so the dashboard asks the user to install them again.
+    pub async fn recompile_outdated_widgets() {
+        let config = match Persistence::global().get_system_config() {
+            Ok(config) => config,
+            Err(err) => {
+                log::error!(
+                    "{}: Could not load installed widgets: {}",
+                    LOGGING_PREFIX,
+                    err
+                );
+                return;
+            }
+        };
+        let mut store = None;
+        for widget in config.widgets {
+            let outdated = Persistence::global()
+                .get_compiled_widget(&widget.name)
+                .ok()
+                .flatten()
+                .is_some_and(|compiled| compiled.needs_recompilation());
+            if !outdated {
+                continue;
+            }
+            let result = match Persistence::global().get_widget_component(&widget.name) {
+                Ok(Some(_)) => WidgetManager::load_widget(&widget.name).map(|_| ()),
+                Ok(None) => WidgetManager::restore_from_source(&widget, &mut store).await,
+                Err(err) => Err(err.into()),
+            };
+            if let Err(err) = &result {
+                WidgetLogs::record(
+                    &widget.name,
+                    LogLevel::Error,
+                    LogSource::Host,
+                    &format!("Could not recompile the widget, reinstall it: {:#}", err),
+                );
+            }
+            let reinstall_required = result.is_err();
+            let saved = Persistence::global().update_system_config(|config| {
+                for installed in config.widgets.iter_mut() {
+                    if installed.name == widget.name {
+                        installed.reinstall_required = reinstall_required;
+                    }
+                }
+            });
+            if let Err(err) = saved {
+                log::error!(
+                    "{}: Could not save that widget {} has to be reinstalled: {}",
+                    LOGGING_PREFIX,
+                    widget.name,
+                    err
+                );
+            }
+        }
+    }
+
+    /// Download and compile a widget again that was installed without keeping its component
+    /// Only the installed version is accepted, so the widget does not get permissions the user did not approve.
+    /// # Arguments
+    /// * `widget` - The installation data of the widget
+    /// * `store` - The widget store, fetched on first use
+    async fn restore_from_source(
+        widget: &WidgetInstallationData,
+        store: &mut Option<WidgetStore>,
+    ) -> Result<(), Error> {
+        let Some(source) = &widget.source else {
+            bail!("The widget was installed before its source was recorded");
+        };
+        let bytes = WidgetManager::download_from_source(source, store).await?;
+        let mut runtime = Runtime::new();
+        let compiled_widget = runtime.compile_widget(&bytes)?;
+        let instance = runtime.instantiate_widget(&compiled_widget)?;
+        let name = instance.name().to_owned();
+        let version = runtime.get_widget_version(&instance)?;
+        let permissions = runtime.get_widget_permissions(&instance)?;
+        if name != widget.name || version != widget.version {
+            bail!(
+                "The source provides {} {} instead of {} {}",
+                name,
+                version,
+                widget.name,
+                widget.version
+            );
+        }
+        // Widgets still awaiting approval run without permissions, the approval checks them later
+        if !widget.approval_required && permissions != widget.permissions {
+            bail!("The source provides the widget with other permissions than the approved ones");
+        }
+        log::info!(
+            "{}: Recompiled widget {} from its source",
+            LOGGING_PREFIX,
+            name
+        );
+        Persistence::global().save_compiled_widget(&name, &compiled_widget)?;
+        Persistence::global().save_widget_component(&name, &bytes)?;
+        Ok(())
     }
 
     /// Get all installed and configured widgets
+    /// Widgets that can not be loaded are skipped, so the others are still displayed
     /// # Returns
     /// A vector of compiled widgets
     pub fn get_widgets() -> Vec<CompiledWidget> {
+        let config = match Persistence::global().get_system_config() {
+            Ok(config) => config,
+            Err(err) => {
+                log::error!(
+                    "{}: Could not load installed widgets: {}",
+                    LOGGING_PREFIX,
+                    err
+                );
+                return Vec::new();
+            }
+        };
         let mut widgets = Vec::new();
-        for widget in Persistence::get_system_config().unwrap().widgets {
-            let bytes = Persistence::get_compiled_widget(&widget.name);
-            if let Some(b) = bytes {
-                widgets.push(b);
-            };
+        for widget in config.widgets {
+            match WidgetManager::load_widget(&widget.name) {
+                Ok(Some(compiled_widget)) => widgets.push(compiled_widget),
+                Ok(None) => {}
+                Err(err) => WidgetLogs::record(
+                    &widget.name,
+                    LogLevel::Error,
+                    LogSource::Host,
+                    &format!("Could not load widget, reinstall it: {}", err),
+                ),
+            }
         }
         widgets
     }
@@ -1,15 +1,40 @@
-use std::time::UNIX_EPOCH;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
 
 use crate::widgets::running::runtime::widget::widget::clocks;
 use crate::widgets::running::runtime::WidgetState;
 
+/// Longest a polled widget can sleep in one call, as it blocks the rendering of the display
+const MAX_POLLED_SLEEP: Duration = Duration::from_millis(50);
+
+/// Longest a streaming widget can sleep in one call, it runs on its own thread
+const MAX_STREAMING_SLEEP: Duration = Duration::from_secs(5);
+
+/// Reference point of the monotonic clock
+static MONOTONIC_START: OnceLock<Instant> = OnceLock::new();
+
+/// Get the time elapsed since the monotonic clock was first read
+/// Unlike the wall clock, it never jumps (e.g. when NTP corrects the clock at boot)
+pub fn monotonic_now() -> Duration {
+    MONOTONIC_START.get_or_init(Instant::now).elapsed()
+}
+
 impl clocks::Host for WidgetState {
     fn now(&mut self) -> clocks::Datetime {
-        let now = std::time::SystemTime::now();
-        let now = now.duration_since(UNIX_EPOCH).unwrap();
-        clocks::Datetime {
-            seconds: now.as_secs(),
-            nanoseconds: now.subsec_nanos(),
-        }
+        clocks::Datetime::now()
+    }
+
+    fn monotonic_now(&mut self) -> clocks::Instant {
+        monotonic_now().as_nanos() as u64
+    }
+
+    /// Polled widgets only sleep briefly, they should request their next run instead of waiting
+    fn sleep(&mut self, nanoseconds: u64) {
+        let max_sleep = if self.push_target.is_some() {
+            MAX_STREAMING_SLEEP
+        } else {
+            MAX_POLLED_SLEEP
+        };
+        std::thread::sleep(Duration::from_nanos(nanoseconds).min(max_sleep));
     }
 }
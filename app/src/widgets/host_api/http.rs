@@ -1,37 +1,384 @@
+use std::error::Error as _;
+use std::time::Duration;
+
+use common::models::{HttpMethod, LogLevel, LogSource};
+use reqwest::header::{
+    HeaderMap, HeaderName, HeaderValue, AUTHORIZATION, COOKIE, LOCATION, PROXY_AUTHORIZATION,
+    USER_AGENT,
+};
+use reqwest::{Method, StatusCode};
+
+use crate::widgets::running::http_client::{shared_http_client, HttpResponse, SendError};
+use crate::widgets::running::permissions::check_http_request;
 use crate::widgets::running::runtime::widget::widget::http;
 use crate::widgets::running::runtime::WidgetState;
+use crate::widgets::utils::widget_logs::WidgetLogs;
+
+/// Timeout used if the widget does not specify one
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Upper bound for timeouts requested by widgets
+const MAX_TIMEOUT: Duration = Duration::from_secs(120);
+
+impl WidgetState {
+    /// Log a denied request and report it to the widget
+    /// The URL may contain secrets handed out to the widget (e.g. an API key as query parameter), they are redacted.
+    fn deny_http_request(&self, url: &str, reason: String) -> Result<http::Response, http::Error> {
+        WidgetLogs::record(
+            &self.widget_name,
+            LogLevel::Warn,
+            LogSource::Host,
+            &self.redact(&format!("Denied HTTP request to {}: {}", url, reason)),
+        );
+        Err(http::Error::PermissionDenied(reason))
+    }
+}
+
+/// Map a failed request to the error reported to the widget
+fn to_widget_error(err: &reqwest::Error) -> http::Error {
+    if err.is_timeout() {
+        return http::Error::Timeout;
+    }
+    if err.is_builder() {
+        return http::Error::InvalidRequest(err.to_string());
+    }
+    if let Some(status) = err.status() {
+        return http::Error::Status(status.as_u16());
+    }
+
+    // reqwest does not expose the cause of connection errors, so the source chain is inspected
+    let mut message = err.to_string();
+    let mut source = err.source();
+    while let Some(cause) = source {
+        message = format!("{}: {}", message, cause);
+        source = cause.source();
+    }
+    classify_connection_error(message, err.is_connect())
+}
+
+fn classify_connection_error(message: String, is_connect: bool) -> http::Error {
+    let lowercase = message.to_lowercase();
+    if lowercase.contains("dns error") || lowercase.contains("failed to lookup address") {
+        http::Error::Dns(message)
+    } else if lowercase.contains("certificate")
+        || lowercase.contains("ssl")
+        || lowercase.contains("tls")
+    {
+        http::Error::Tls(message)
+    } else if is_connect {
+        http::Error::Connection(message)
+    } else {
+        http::Error::Other(message)
+    }
+}
+
+/// Redirects followed until the request fails
+const MAX_REDIRECTS: usize = 10;
+
+/// The request to send after a redirect
+#[derive(PartialEq, Debug)]
+struct Redirect {
+    url: String,
+    method: HttpMethod,
+    /// Whether the body is sent again, it is dropped if the method changes to GET
+    keep_body: bool,
+}
+
+/// Get where a response redirects to
+/// # Arguments
+/// * `response` - The response to the request
+/// * `url` - The URL of the request
+/// * `method` - The method of the request
+/// # Returns
+/// The request to send next or `None` if the response is no redirect
+fn redirect_target(response: &HttpResponse, url: &str, method: HttpMethod) -> Option<Redirect> {
+    let keep_method = match response.status {
+        301 | 302 => !matches!(method, HttpMethod::Post),
+        303 => method == HttpMethod::Head,
+        307 | 308 => true,
+        _ => return None,
+    };
+    let location = response
+        .headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(LOCATION.as_str()))
+        .map(|(_, value)| value)?;
+    let url = reqwest::Url::parse(url).ok()?.join(location).ok()?;
+    Some(Redirect {
+        url: url.to_string(),
+        method: if keep_method { method } else { HttpMethod::Get },
+        keep_body: keep_method,
+    })
+}
+
+fn to_reqwest_method(method: HttpMethod) -> Method {
+    match method {
+        HttpMethod::Get => Method::GET,
+        HttpMethod::Head => Method::HEAD,
+        HttpMethod::Post => Method::POST,
+        HttpMethod::Put => Method::PUT,
+        HttpMethod::Delete => Method::DELETE,
+    }
+}
+
+fn host_of(url: &str) -> Option<String> {
+    reqwest::Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_owned))
+}
+
+impl WidgetState {
+    /// Send a single request of the widget, without following redirects
+    /// The request is checked against the permissions of the widget first.
+    fn send_http_request(
+        &self,
+        method: HttpMethod,
+        url: &str,
+        headers: &HeaderMap,
+        body: Option<&Vec<u8>>,
+        timeout: Duration,
+    ) -> Result<HttpResponse, http::Error> {
+        let body_size = body.map_or(0, Vec::len);
+        if let Err(reason) = check_http_request(&self.permissions, method, url, body_size) {
+            return self.deny_http_request(url, reason);
+        }
+
+        let client = shared_http_client();
+        let mut req = client
+            .client()
+            .request(to_reqwest_method(method), url)
+            .timeout(timeout)
+            .headers(headers.clone());
+        if let Some(body) = body {
+            req = req.body(body.clone());
+        }
+        let req = req.build().map_err(|err| to_widget_error(&err))?;
+
+        let max_body_size = self
+            .permissions
+            .http
+            .as_ref()
+            .map_or(0, |http| http.max_body_size);
+        let response = client.send(
+            &self.widget_name,
+            self.http_rate_limit.as_ref(),
+            req,
+            max_body_size,
+        );
+        match response {
+            Ok(response) => Ok(response),
+            Err(SendError::Request(err)) => Err(to_widget_error(&err)),
+            // reqwest reports errors while reading the body as IO errors wrapping its own error
+            Err(SendError::Body(err)) => Err(
+                match err
+                    .get_ref()
+                    .and_then(|cause| cause.downcast_ref::<reqwest::Error>())
+                {
+                    Some(err) => to_widget_error(err),
+                    None => http::Error::Other(err.to_string()),
+                },
+            ),
+            Err(SendError::BodyTooLarge { size, max_body_size }) => self.deny_http_request(
+                url,
+                format!(
+                    "response body of at least {} bytes exceeds the granted maximum of {} bytes",
+                    size, max_body_size
+                ),
+            ),
+            Err(SendError::RateLimited) => {
+                WidgetLogs::record(
+                    &self.widget_name,
+                    LogLevel::Warn,
+                    LogSource::Host,
+                    "Exceeded the HTTP rate limit",
+                );
+                Err(http::Error::RateLimited)
+            }
+        }
+    }
+}
 
 impl http::Host for WidgetState {
-    fn request(
-        &mut self,
-        method: http::Method,
-        url: String,
-        body: Option<Vec<u8>>,
-    ) -> Result<http::Response, ()> {
-        let client = reqwest::blocking::Client::new();
-        let response = match method {
-            http::Method::Get => client.get(url).send(),
-            http::Method::Head => client.head(url).send(),
-            http::Method::Post => {
-                let mut req = client.post(url);
-                if let Some(body) = body {
-                    req = req.body(body);
+    fn request(&mut self, request: http::OutgoingRequest) -> Result<http::Response, http::Error> {
+        let http::OutgoingRequest {
+            method,
+            url,
+            headers,
+            body,
+            timeout_ms,
+            error_for_status,
+        } = request;
+
+        let timeout = timeout_ms
+            .map(|ms| Duration::from_millis(ms.into()))
+            .unwrap_or(DEFAULT_TIMEOUT)
+            .min(MAX_TIMEOUT);
+        let mut header_map = HeaderMap::new();
+        for header in headers {
+            let name = HeaderName::from_bytes(header.name.as_bytes()).map_err(|err| {
+                http::Error::InvalidRequest(format!("Invalid header name {}: {}", header.name, err))
+            })?;
+            let value = HeaderValue::from_str(&header.value).map_err(|err| {
+                http::Error::InvalidRequest(format!("Invalid value of header {}: {}", header.name, err))
+            })?;
+            header_map.append(name, value);
+        }
+        if !header_map.contains_key(USER_AGENT) {
+            header_map.insert(
+                USER_AGENT,
+                HeaderValue::from_static(concat!("wg_display/", env!("CARGO_PKG_VERSION"))),
+            );
+        }
+
+        // Redirects are followed here, so every hop is checked against the granted hosts
+        let mut method = HttpMethod::from(method);
+        let mut url = url;
+        let mut body = body;
+        let mut redirects = 0;
+        let response = loop {
+            let response =
+                self.send_http_request(method, &url, &header_map, body.as_ref(), timeout)?;
+            let Some(redirect) = redirect_target(&response, &url, method) else {
+                break response;
+            };
+            redirects += 1;
+            if redirects > MAX_REDIRECTS {
+                return Err(http::Error::Other(format!(
+                    "more than {} redirects",
+                    MAX_REDIRECTS
+                )));
+            }
+            // Credentials are not forwarded to other hosts
+            if host_of(&redirect.url) != host_of(&url) {
+                for name in [AUTHORIZATION, COOKIE, PROXY_AUTHORIZATION] {
+                    header_map.remove(name);
                 }
-                req.send()
             }
-            http::Method::Put => client.put(url).send(),
-            http::Method::Delete => client.delete(url).send(),
+            if !redirect.keep_body {
+                body = None;
+            }
+            method = redirect.method;
+            url = redirect.url;
         };
-        match response {
-            Ok(response) => Ok(http::Response {
-                content_length: response.content_length(),
-                status: response.status().as_u16(),
-                bytes: match response.bytes() {
-                    Ok(bytes) => bytes.to_vec(),
-                    Err(_) => Vec::new(),
-                },
-            }),
-            Err(_) => Err(()),
+
+        let status = StatusCode::from_u16(response.status).unwrap_or(StatusCode::OK);
+        if error_for_status && (status.is_client_error() || status.is_server_error()) {
+            return Err(http::Error::Status(response.status));
         }
+        Ok(http::Response {
+            content_length: response.content_length,
+            status: response.status,
+            headers: response
+                .headers
+                .into_iter()
+                .map(|(name, value)| http::Header { name, value })
+                .collect(),
+            bytes: response.body,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::models::{HttpPermissions, WidgetPermissions};
+
+    #[test]
+    fn test_classify_connection_error() {
+        let classify = |message: &str, is_connect| {
+            classify_connection_error(message.to_string(), is_connect)
+        };
+        assert!(matches!(
+            classify("error sending request: client error (Connect): dns error: failed to lookup address information", true),
+            http::Error::Dns(_)
+        ));
+        assert!(matches!(
+            classify("error sending request: error:0A000086:SSL routines::certificate verify failed", true),
+            http::Error::Tls(_)
+        ));
+        assert!(matches!(
+            classify("error sending request: tcp connect error: Connection refused", true),
+            http::Error::Connection(_)
+        ));
+        assert!(matches!(
+            classify("error decoding response body", false),
+            http::Error::Other(_)
+        ));
+    }
+
+    fn redirect(status: u16, location: &str) -> HttpResponse {
+        HttpResponse {
+            status,
+            content_length: None,
+            headers: vec![("location".to_string(), location.to_string())],
+            body: vec![],
+        }
+    }
+
+    #[test]
+    fn test_redirect_target() {
+        let url = "https://transport.opendata.ch/v1/stationboard";
+        assert_eq!(
+            redirect_target(&redirect(301, "/v2/stationboard"), url, HttpMethod::Get),
+            Some(Redirect {
+                url: "https://transport.opendata.ch/v2/stationboard".to_string(),
+                method: HttpMethod::Get,
+                keep_body: true,
+            })
+        );
+        assert_eq!(
+            redirect_target(&redirect(303, "/result"), url, HttpMethod::Post),
+            Some(Redirect {
+                url: "https://transport.opendata.ch/result".to_string(),
+                method: HttpMethod::Get,
+                keep_body: false,
+            })
+        );
+        assert_eq!(
+            redirect_target(&redirect(307, "/retry"), url, HttpMethod::Post).map(|r| r.method),
+            Some(HttpMethod::Post)
+        );
+        assert_eq!(redirect_target(&redirect(200, "/other"), url, HttpMethod::Get), None);
+    }
+
+    #[test]
+    fn test_redirect_to_other_host_is_checked() {
+        let permissions = WidgetPermissions {
+            http: Some(HttpPermissions {
+                hosts: vec!["transport.opendata.ch".to_string()],
+                methods: vec![HttpMethod::Get],
+                max_body_size: 1024,
+            }),
+        };
+        let redirect = redirect_target(
+            &redirect(302, "http://169.254.169.254/latest/meta-data"),
+            "https://transport.opendata.ch/v1/stationboard",
+            HttpMethod::Get,
+        )
+        .unwrap();
+        assert!(check_http_request(&permissions, redirect.method, &redirect.url, 0).is_err());
+    }
+
+    #[test]
+    fn test_denied_request_is_logged_redacted() {
+        let widget = "test_denied_request_is_logged_redacted";
+        let mut state = WidgetState::new();
+        state.widget_name = widget.to_string();
+        state.reveal_secret("s3cr3t");
+        let res = state.send_http_request(
+            HttpMethod::Get,
+            "https://api.example.com/data?apikey=s3cr3t",
+            &HeaderMap::new(),
+            None,
+            DEFAULT_TIMEOUT,
+        );
+        assert!(matches!(res, Err(http::Error::PermissionDenied(_))));
+        let messages: Vec<String> = WidgetLogs::get(widget, LogLevel::Debug)
+            .into_iter()
+            .map(|entry| entry.message)
+            .collect();
+        assert_eq!(messages.len(), 1);
+        assert!(messages[0].contains("apikey=***"));
+        assert!(!messages[0].contains("s3cr3t"));
     }
 }
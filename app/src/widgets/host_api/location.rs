@@ -0,0 +1,12 @@
+use crate::widgets::running::runtime::widget::widget::location;
+use crate::widgets::running::runtime::WidgetState;
+
+impl location::Host for WidgetState {
+    fn get(&mut self) -> Option<location::Location> {
+        self.location.as_ref().map(|location| location::Location {
+            latitude: location.latitude,
+            longitude: location.longitude,
+            locality: location.locality.clone(),
+        })
+    }
+}
@@ -1,5 +1,8 @@
+use common::models::{LogLevel, LogSource};
+
 use crate::widgets::running::runtime::widget::widget::logging;
 use crate::widgets::running::runtime::WidgetState;
+use crate::widgets::utils::widget_logs::WidgetLogs;
 
 impl logging::Host for WidgetState {
     fn log(
@@ -8,12 +11,13 @@ impl logging::Host for WidgetState {
         context: String,
         message: String,
     )  {
-        const PREFIX: &str = "WIDGET";
-        match level {
-            logging::Level::Debug => log::debug!("{}: {}: {}", PREFIX, context, message),
-            logging::Level::Info => log::info!("{}: {}: {}", PREFIX, context, message),
-            logging::Level::Warn => log::warn!("{}: {}: {}", PREFIX, context, message),
-            logging::Level::Error => log::error!("{}: {}: {}", PREFIX, context, message),
-        }
+        let level = match level {
+            logging::Level::Debug => LogLevel::Debug,
+            logging::Level::Info => LogLevel::Info,
+            logging::Level::Warn => LogLevel::Warn,
+            logging::Level::Error => LogLevel::Error,
+        };
+        let message = self.redact(&format!("{}: {}", context, message));
+        WidgetLogs::record(&self.widget_name, level, LogSource::Widget, &message);
     }
 }
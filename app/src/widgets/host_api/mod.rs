@@ -1,5 +1,10 @@
 pub mod clocks;
 pub mod http;
+pub mod location;
 pub mod logging;
 pub mod random;
+pub mod secrets;
+pub mod storage;
+pub mod timezone;
 pub mod types;
+pub mod updates;
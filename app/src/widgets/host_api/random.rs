@@ -1,15 +1,112 @@
+//! Random numbers for widgets
+//!
+//! Unless a seed is set, all values come from a cryptographically secure generator
+//! (`ThreadRng`, ChaCha12 periodically reseeded from the operating system),
+//! so widgets can use them for nonces and other secrets.
+use common::models::{LogLevel, LogSource};
+
 use crate::widgets::running::runtime::widget::widget::random;
 use crate::widgets::running::runtime::WidgetState;
+use crate::widgets::utils::widget_logs::WidgetLogs;
 use rand::prelude::*;
+use rand_chacha::ChaCha20Rng;
+
+/// Maximum number of bytes a widget can request at once, longer requests are truncated
+const MAX_RANDOM_BYTES: u32 = 64 * 1024;
+
+/// Source of the random values handed to widgets
+pub enum WidgetRng {
+    /// Cryptographically secure and unpredictable
+    Secure,
+    /// Deterministic, used to reproduce the output of widgets (e.g. in snapshot tests)
+    Seeded(ChaCha20Rng),
+}
+
+impl WidgetRng {
+    /// Create a deterministic generator
+    /// Every run of a widget starts from the same state, so its output only depends on its input.
+    /// # Arguments
+    /// * `seed` - The seed shared by all widgets
+    /// * `widget_name` - Name of the widget, so widgets don't get the same values
+    pub fn seeded(seed: u64, widget_name: &str) -> Self {
+        WidgetRng::Seeded(seeded_rng(seed, widget_name))
+    }
+
+    fn fill_bytes(&mut self, bytes: &mut [u8]) {
+        match self {
+            WidgetRng::Secure => thread_rng().fill_bytes(bytes),
+            WidgetRng::Seeded(rng) => rng.fill_bytes(bytes),
+        }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        match self {
+            WidgetRng::Secure => thread_rng().next_u64(),
+            WidgetRng::Seeded(rng) => rng.next_u64(),
+        }
+    }
+}
+
+/// Create a deterministic generator
+/// # Arguments
+/// * `seed` - The seed shared by all widgets
+/// * `stream` - Selects one of the independent streams of the seed, e.g. the name of the widget
+pub fn seeded_rng(seed: u64, stream: &str) -> ChaCha20Rng {
+    let mut rng = ChaCha20Rng::seed_from_u64(seed);
+    rng.set_stream(fnv1a(stream.as_bytes()));
+    rng
+}
 
-fn get_random() -> u64 {
-    let mut rng = thread_rng();
-    rng.next_u64()
+/// Stable hash of the widget name, `DefaultHasher` may change between Rust versions
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Format 16 random bytes as version 4 UUID
+fn format_uuid(mut bytes: [u8; 16]) -> String {
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    )
 }
 
 impl random::Host for WidgetState {
     fn get_random(&mut self) -> u64 {
-        get_random()
+        self.rng.next_u64()
+    }
+
+    /// Returns at most [MAX_RANDOM_BYTES] bytes, the interface has no way to report an error.
+    /// Truncated requests are logged, so the widget author notices.
+    fn get_random_bytes(&mut self, len: u32) -> Vec<u8> {
+        if len > MAX_RANDOM_BYTES {
+            WidgetLogs::record(
+                &self.widget_name,
+                LogLevel::Warn,
+                LogSource::Host,
+                &format!(
+                    "Requested {} random bytes, only {} are returned per call",
+                    len, MAX_RANDOM_BYTES
+                ),
+            );
+        }
+        let mut bytes = vec![0; len.min(MAX_RANDOM_BYTES) as usize];
+        self.rng.fill_bytes(&mut bytes);
+        bytes
+    }
+
+    fn get_uuid(&mut self) -> String {
+        let mut bytes = [0; 16];
+        self.rng.fill_bytes(&mut bytes);
+        format_uuid(bytes)
     }
 }
 
@@ -19,7 +116,29 @@ mod tests {
 
     #[test]
     fn test_get_random() {
-        let result = get_random();
-        assert!(result > 0);
+        let mut rng = WidgetRng::Secure;
+        assert_ne!(rng.next_u64(), rng.next_u64());
+    }
+
+    #[test]
+    fn test_seeded_is_reproducible() {
+        let mut first = WidgetRng::seeded(42, "Weather");
+        let mut second = WidgetRng::seeded(42, "Weather");
+        assert_eq!(first.next_u64(), second.next_u64());
+        let (mut a, mut b) = ([0; 32], [0; 32]);
+        first.fill_bytes(&mut a);
+        second.fill_bytes(&mut b);
+        assert_eq!(a, b);
+
+        // Widgets get different values from the same seed
+        let mut other = WidgetRng::seeded(42, "Transit");
+        assert_ne!(WidgetRng::seeded(42, "Weather").next_u64(), other.next_u64());
+    }
+
+    #[test]
+    fn test_format_uuid() {
+        let uuid = format_uuid([0xff; 16]);
+        assert_eq!(uuid, "ffffffff-ffff-4fff-bfff-ffffffffffff");
+        assert_eq!(format_uuid([0; 16]), "00000000-0000-4000-8000-000000000000");
     }
 }
@@ -0,0 +1,31 @@
+use common::models::{LogLevel, LogSource};
+
+use crate::shared::secrets::Secrets;
+use crate::widgets::running::runtime::widget::widget::secrets;
+use crate::widgets::running::runtime::WidgetState;
+use crate::widgets::utils::widget_logs::WidgetLogs;
+
+/// Secrets are only available while a widget is run, as they are scoped to the widget
+impl secrets::Host for WidgetState {
+    fn get(&mut self, name: String) -> Option<String> {
+        if self.widget_name.is_empty() {
+            return None;
+        }
+        match Secrets::global().get_secret(&self.widget_name, &name) {
+            Ok(Some(value)) => {
+                self.reveal_secret(&value);
+                Some(value)
+            }
+            Ok(None) => None,
+            Err(err) => {
+                WidgetLogs::record(
+                    &self.widget_name,
+                    LogLevel::Error,
+                    LogSource::Host,
+                    &format!("Could not read secret {}: {}", name, err),
+                );
+                None
+            }
+        }
+    }
+}
@@ -0,0 +1,222 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::shared::persistence::{Persistence, PersistenceError};
+use crate::widgets::running::runtime::widget::widget::storage;
+use crate::widgets::running::runtime::WidgetState;
+
+/// Maximum space a widget can use in its storage (keys and values)
+const STORAGE_QUOTA_BYTES: u64 = 1024 * 1024;
+
+/// Maximum length of a key in bytes
+const MAX_KEY_LENGTH: usize = 256;
+
+/// Check whether a value can be stored without exceeding the quota
+/// # Arguments
+/// * `usage` - The space currently used by the storage
+/// * `replaced_size` - The size of the entry that is replaced, 0 if the key is new
+/// * `new_size` - The size of the new entry
+fn check_quota(usage: u64, replaced_size: u64, new_size: u64) -> Result<(), storage::Error> {
+    if usage.saturating_sub(replaced_size) + new_size > STORAGE_QUOTA_BYTES {
+        return Err(storage::Error::QuotaExceeded);
+    }
+    Ok(())
+}
+
+fn check_key(key: &str) -> Result<(), storage::Error> {
+    if key.is_empty() || key.len() > MAX_KEY_LENGTH {
+        return Err(storage::Error::InvalidKey(format!(
+            "Keys must have between 1 and {} bytes",
+            MAX_KEY_LENGTH
+        )));
+    }
+    Ok(())
+}
+
+/// Changes a widget makes to its storage during a test run
+/// They are kept in memory and dropped after the run, the stored values are only read.
+#[derive(Default)]
+pub struct StorageChanges {
+    /// Values by key, `None` for deleted keys
+    values: BTreeMap<String, Option<Vec<u8>>>,
+}
+
+impl StorageChanges {
+    /// Get a value as the widget sees it
+    fn get(&self, widget_name: &str, key: &str) -> Result<Option<Vec<u8>>, PersistenceError> {
+        match self.values.get(key) {
+            Some(value) => Ok(value.clone()),
+            None => Persistence::global().get_widget_storage_value(widget_name, key),
+        }
+    }
+
+    /// Get the space used by the storage as the widget sees it
+    fn usage(&self, widget_name: &str) -> Result<u64, PersistenceError> {
+        let mut usage = Persistence::global().get_widget_storage_usage(widget_name)?;
+        for (key, value) in &self.values {
+            if let Some(stored) =
+                Persistence::global().get_widget_storage_value(widget_name, key)?
+            {
+                usage -= (key.len() + stored.len()) as u64;
+            }
+            if let Some(value) = value {
+                usage += (key.len() + value.len()) as u64;
+            }
+        }
+        Ok(usage)
+    }
+
+    /// List the keys starting with a prefix as the widget sees them
+    fn list(&self, widget_name: &str, prefix: &str) -> Result<Vec<String>, PersistenceError> {
+        let mut keys: BTreeSet<String> = Persistence::global()
+            .list_widget_storage_keys(widget_name, prefix)?
+            .into_iter()
+            .collect();
+        let changed = self
+            .values
+            .range(prefix.to_owned()..)
+            .take_while(|(key, _)| key.starts_with(prefix));
+        for (key, value) in changed {
+            if value.is_some() {
+                keys.insert(key.clone());
+            } else {
+                keys.remove(key);
+            }
+        }
+        Ok(keys.into_iter().collect())
+    }
+}
+
+/// Storage is only available while a widget is run, as it is scoped to the widget
+/// During test runs the changes are not saved, see [StorageChanges]
+impl storage::Host for WidgetState {
+    fn get(&mut self, key: String) -> Option<Vec<u8>> {
+        if self.widget_name.is_empty() {
+            return None;
+        }
+        match &self.test_run_storage {
+            Some(changes) => changes.get(&self.widget_name, &key),
+            None => Persistence::global().get_widget_storage_value(&self.widget_name, &key),
+        }
+        .ok()
+        .flatten()
+    }
+
+    fn set(&mut self, key: String, value: Vec<u8>) -> Result<(), storage::Error> {
+        if self.widget_name.is_empty() {
+            return Err(storage::Error::Unavailable);
+        }
+        check_key(&key)?;
+        let (replaced, usage) = match &self.test_run_storage {
+            Some(changes) => (
+                changes.get(&self.widget_name, &key),
+                changes.usage(&self.widget_name),
+            ),
+            None => (
+                Persistence::global().get_widget_storage_value(&self.widget_name, &key),
+                Persistence::global().get_widget_storage_usage(&self.widget_name),
+            ),
+        };
+        let replaced_size = replaced
+            .map_err(|_| storage::Error::Unavailable)?
+            .map_or(0, |old| (key.len() + old.len()) as u64);
+        check_quota(
+            usage.map_err(|_| storage::Error::Unavailable)?,
+            replaced_size,
+            (key.len() + value.len()) as u64,
+        )?;
+        match &mut self.test_run_storage {
+            Some(changes) => {
+                changes.values.insert(key, Some(value));
+                Ok(())
+            }
+            None => Persistence::global()
+                .set_widget_storage_value(&self.widget_name, &key, &value)
+                .map_err(|_| storage::Error::Unavailable),
+        }
+    }
+
+    fn delete(&mut self, key: String) {
+        if self.widget_name.is_empty() {
+            return;
+        }
+        match &mut self.test_run_storage {
+            Some(changes) => {
+                changes.values.insert(key, None);
+            }
+            None => {
+                let _ = Persistence::global().delete_widget_storage_value(&self.widget_name, &key);
+            }
+        }
+    }
+
+    fn list(&mut self, prefix: String) -> Vec<String> {
+        if self.widget_name.is_empty() {
+            return Vec::new();
+        }
+        match &self.test_run_storage {
+            Some(changes) => changes.list(&self.widget_name, &prefix),
+            None => Persistence::global().list_widget_storage_keys(&self.widget_name, &prefix),
+        }
+        .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_quota() {
+        assert!(check_quota(0, 0, STORAGE_QUOTA_BYTES).is_ok());
+        assert!(check_quota(1, 0, STORAGE_QUOTA_BYTES).is_err());
+        assert!(check_quota(STORAGE_QUOTA_BYTES, 10, 10).is_ok());
+        assert!(check_quota(STORAGE_QUOTA_BYTES, 10, 11).is_err());
+    }
+
+    #[test]
+    fn test_check_key() {
+        assert!(check_key("history/1").is_ok());
+        assert!(check_key("").is_err());
+        assert!(check_key(&"k".repeat(MAX_KEY_LENGTH + 1)).is_err());
+    }
+
+    #[test]
+    fn test_storage_changes_are_not_saved() {
+        let widget = "test_storage_changes";
+        let persistence = Persistence::global();
+        persistence.clear_widget_storage(widget).unwrap();
+        persistence
+            .set_widget_storage_value(widget, "history/1", b"12")
+            .unwrap();
+        persistence
+            .set_widget_storage_value(widget, "history/2", b"13")
+            .unwrap();
+
+        let mut changes = StorageChanges::default();
+        changes.values.insert("history/2".to_string(), None);
+        changes
+            .values
+            .insert("history/3".to_string(), Some(b"14".to_vec()));
+        changes
+            .values
+            .insert("last".to_string(), Some(b"1400".to_vec()));
+
+        assert_eq!(
+            changes.get(widget, "history/1").unwrap(),
+            Some(b"12".to_vec())
+        );
+        assert_eq!(changes.get(widget, "history/2").unwrap(), None);
+        assert_eq!(
+            changes.list(widget, "history/").unwrap(),
+            vec!["history/1", "history/3"]
+        );
+        // history/1 (11 bytes), history/3 (11 bytes) and last (8 bytes)
+        assert_eq!(changes.usage(widget).unwrap(), 30);
+        // The stored values are unchanged
+        assert_eq!(
+            persistence.list_widget_storage_keys(widget, "").unwrap(),
+            vec!["history/1", "history/2"]
+        );
+        persistence.clear_widget_storage(widget).unwrap();
+    }
+}
@@ -0,0 +1,101 @@
+use jiff::{tz::TimeZone, Timestamp};
+
+use crate::widgets::running::runtime::widget::widget::{clocks, timezone};
+use crate::widgets::running::runtime::WidgetState;
+use crate::widgets::utils::logging::LOGGING_PREFIX;
+
+/// Look up the timezone configured for the display
+/// # Arguments
+/// * `name` - IANA name of the timezone
+/// # Returns
+/// The timezone or the timezone of the host system if none or an unknown one is configured
+pub fn resolve_timezone(name: Option<&str>) -> TimeZone {
+    let Some(name) = name else {
+        return TimeZone::system();
+    };
+    TimeZone::get(name).unwrap_or_else(|err| {
+        log::warn!(
+            "{}: Unknown timezone {}, using the system timezone: {}",
+            LOGGING_PREFIX,
+            name,
+            err
+        );
+        TimeZone::system()
+    })
+}
+
+/// Convert a point in time to the local time of a timezone
+/// # Arguments
+/// * `tz` - The timezone
+/// * `when` - The point in time
+/// # Returns
+/// The local date and time, clamped to the range supported by jiff
+fn to_local(tz: &TimeZone, when: &clocks::Datetime) -> timezone::LocalDatetime {
+    let seconds = i64::try_from(when.seconds).unwrap_or(i64::MAX);
+    let timestamp = Timestamp::new(seconds, when.nanoseconds as i32).unwrap_or(Timestamp::MAX);
+    let zoned = timestamp.to_zoned(tz.clone());
+    let datetime = zoned.datetime();
+    timezone::LocalDatetime {
+        year: datetime.year(),
+        month: datetime.month() as u8,
+        day: datetime.day() as u8,
+        hour: datetime.hour() as u8,
+        minute: datetime.minute() as u8,
+        second: datetime.second() as u8,
+        nanosecond: datetime.subsec_nanosecond() as u32,
+        utc_offset_seconds: zoned.offset().seconds(),
+    }
+}
+
+impl timezone::Host for WidgetState {
+    fn name(&mut self) -> String {
+        self.timezone.iana_name().unwrap_or("UTC").to_string()
+    }
+
+    fn to_local(&mut self, when: clocks::Datetime) -> timezone::LocalDatetime {
+        to_local(&self.timezone, &when)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_local() {
+        let tz = resolve_timezone(Some("Europe/Zurich"));
+        // 2023-11-14 22:13:20 UTC, standard time
+        let local = to_local(
+            &tz,
+            &clocks::Datetime {
+                seconds: 1_700_000_000,
+                nanoseconds: 5,
+            },
+        );
+        assert_eq!(
+            (local.year, local.month, local.day, local.hour, local.minute),
+            (2023, 11, 14, 23, 13)
+        );
+        assert_eq!(local.nanosecond, 5);
+        assert_eq!(local.utc_offset_seconds, 3600);
+
+        // 2023-07-01 12:00:00 UTC, daylight saving time
+        let local = to_local(
+            &tz,
+            &clocks::Datetime {
+                seconds: 1_688_212_800,
+                nanoseconds: 0,
+            },
+        );
+        assert_eq!(local.hour, 14);
+        assert_eq!(local.utc_offset_seconds, 7200);
+    }
+
+    #[test]
+    fn test_resolve_unknown_timezone() {
+        assert_eq!(
+            resolve_timezone(Some("Mars/Olympus_Mons")),
+            TimeZone::system()
+        );
+    }
+}
@@ -0,0 +1,30 @@
+use common::models::{LogLevel, LogSource};
+
+use crate::widgets::running::runtime::widget::widget::updates;
+use crate::widgets::running::runtime::{PushedUpdate, Runtime, WidgetState};
+use crate::widgets::utils::widget_logs::WidgetLogs;
+
+/// Updates can only be pushed by widgets run in streaming mode
+impl updates::Host for WidgetState {
+    fn push(&mut self, data: String) -> bool {
+        let Some(target) = &self.push_target else {
+            WidgetLogs::record(
+                &self.widget_name,
+                LogLevel::Warn,
+                LogSource::Host,
+                "Pushed an update but is not run in streaming mode",
+            );
+            return false;
+        };
+        let update = PushedUpdate {
+            widget_name: self.widget_name.clone(),
+            data: self.redact(&data),
+            failed: false,
+        };
+        // The receiver is dropped when the widget is stopped (e.g. on a configuration change)
+        let keep_running = target.send(update).is_ok() && !self.stop_after_push;
+        // Streaming widgets may never return from `run`, so their output is forwarded on every update
+        Runtime::forward_widget_output(self, &self.widget_name);
+        keep_running
+    }
+}
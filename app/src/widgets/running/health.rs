@@ -0,0 +1,141 @@
+//! Tracks the health of widgets across their runs
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+use common::models::WidgetHealth;
+
+/// Delay after the first failure, doubled with every further consecutive failure
+const INITIAL_BACKOFF: Duration = Duration::from_secs(5);
+
+/// Upper bound of the delay between two runs of a failing widget
+const MAX_BACKOFF: Duration = Duration::from_secs(10 * 60);
+
+lazy_static! {
+    static ref HEALTH: Mutex<HashMap<String, WidgetHealth>> = Mutex::new(HashMap::new());
+}
+
+/// Compute how long to wait before running a failing widget again
+/// # Arguments
+/// * `consecutive_failures` - Number of failed runs since the last successful one
+/// # Returns
+/// The delay, zero if the widget did not fail
+pub fn backoff(consecutive_failures: u32) -> Duration {
+    if consecutive_failures == 0 {
+        return Duration::ZERO;
+    }
+    let factor = 2u32.saturating_pow(consecutive_failures - 1);
+    INITIAL_BACKOFF.saturating_mul(factor).min(MAX_BACKOFF)
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |now| now.as_millis() as u64)
+}
+
+pub struct HealthRegistry;
+
+impl HealthRegistry {
+    /// Record a successful run of a widget
+    /// # Arguments
+    /// * `widget_name` - Name of the widget
+    /// * `duration` - Duration of the run
+    pub fn record_success(widget_name: &str, duration: Duration) {
+        HealthRegistry::update(widget_name, Some(duration), |health| {
+            health.last_success_ms = Some(now_ms());
+            health.consecutive_failures = 0;
+        });
+    }
+
+    /// Record a failed run of a widget
+    /// # Arguments
+    /// * `widget_name` - Name of the widget
+    /// * `duration` - Duration of the run, `None` if it failed before the widget was called
+    /// * `error` - Why the run failed
+    /// # Returns
+    /// How long to wait before running the widget again
+    pub fn record_failure(widget_name: &str, duration: Option<Duration>, error: &str) -> Duration {
+        HealthRegistry::update(widget_name, duration, |health| {
+            health.last_failure_ms = Some(now_ms());
+            health.consecutive_failures += 1;
+            health.last_error = Some(error.to_owned());
+        })
+    }
+
+    /// Get the health of all widgets that ran at least once
+    pub fn get_all() -> HashMap<String, WidgetHealth> {
+        HEALTH.lock().expect("Widget health poisoned").clone()
+    }
+
+    /// Forget the health of a widget
+    /// # Arguments
+    /// * `widget_name` - Name of the widget
+    pub fn clear(widget_name: &str) {
+        HEALTH.lock().expect("Widget health poisoned").remove(widget_name);
+    }
+
+    fn update(
+        widget_name: &str,
+        duration: Option<Duration>,
+        apply: impl FnOnce(&mut WidgetHealth),
+    ) -> Duration {
+        let mut all = HEALTH.lock().expect("Widget health poisoned");
+        let health = all.entry(widget_name.to_owned()).or_default();
+        health.runs += 1;
+        if let Some(duration) = duration {
+            health.timed_runs += 1;
+            health.average_run_ms += (duration.as_secs_f64() * 1000.0 - health.average_run_ms)
+                / health.timed_runs as f64;
+        }
+        apply(health);
+        let backoff = backoff(health.consecutive_failures);
+        health.backoff_ms = backoff.as_millis() as u64;
+        backoff
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff() {
+        assert_eq!(backoff(0), Duration::ZERO);
+        assert_eq!(backoff(1), INITIAL_BACKOFF);
+        assert_eq!(backoff(2), INITIAL_BACKOFF * 2);
+        assert_eq!(backoff(4), INITIAL_BACKOFF * 8);
+        assert_eq!(backoff(20), MAX_BACKOFF);
+        assert_eq!(backoff(u32::MAX), MAX_BACKOFF);
+    }
+
+    #[test]
+    fn test_health() {
+        let widget = "test_health";
+        HealthRegistry::clear(widget);
+        HealthRegistry::record_success(widget, Duration::from_millis(10));
+        assert_eq!(
+            HealthRegistry::record_failure(widget, Some(Duration::from_millis(30)), "timeout"),
+            INITIAL_BACKOFF
+        );
+        HealthRegistry::record_failure(widget, Some(Duration::from_millis(20)), "timeout");
+        // Failures before the widget was called don't count towards the average
+        HealthRegistry::record_failure(widget, None, "timeout");
+
+        let health = HealthRegistry::get_all().remove(widget).unwrap();
+        assert_eq!(health.runs, 4);
+        assert_eq!(health.timed_runs, 3);
+        assert_eq!(health.consecutive_failures, 3);
+        assert_eq!(health.last_error.as_deref(), Some("timeout"));
+        assert!(health.last_success_ms.is_some());
+        assert!((health.average_run_ms - 20.0).abs() < 1e-9);
+        assert!(!health.is_healthy());
+
+        HealthRegistry::record_success(widget, Duration::from_millis(20));
+        let health = HealthRegistry::get_all().remove(widget).unwrap();
+        assert!(health.is_healthy());
+        assert_eq!(health.backoff_ms, 0);
+        // The last error is kept for reference
+        assert_eq!(health.last_error.as_deref(), Some("timeout"));
+    }
+}
@@ -1 +1,6 @@
+pub mod health;
+pub mod http_client;
+pub mod permissions;
 pub mod runtime;
+pub mod streaming;
+pub mod wasi;
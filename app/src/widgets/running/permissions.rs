@@ -0,0 +1,146 @@
+//! Conversion and enforcement of the permissions widgets declare in their manifest
+use common::models::{HttpMethod, HttpPermissions, WidgetPermissions};
+
+use crate::widgets::running::runtime::widget::widget::http;
+use crate::widgets::running::runtime::Permissions;
+
+impl From<http::Method> for HttpMethod {
+    fn from(method: http::Method) -> Self {
+        match method {
+            http::Method::Get => HttpMethod::Get,
+            http::Method::Head => HttpMethod::Head,
+            http::Method::Post => HttpMethod::Post,
+            http::Method::Put => HttpMethod::Put,
+            http::Method::Delete => HttpMethod::Delete,
+        }
+    }
+}
+
+impl From<Permissions> for WidgetPermissions {
+    fn from(permissions: Permissions) -> Self {
+        WidgetPermissions {
+            http: permissions.http.map(|http| HttpPermissions {
+                hosts: http.hosts,
+                methods: http.methods.into_iter().map(HttpMethod::from).collect(),
+                max_body_size: http.max_body_size,
+            }),
+        }
+    }
+}
+
+/// Check whether a widget may send an HTTP request
+/// # Arguments
+/// * `permissions` - The permissions granted to the widget
+/// * `method` - The method of the request
+/// * `url` - The URL of the request
+/// * `body_size` - Size of the request body in bytes
+/// # Returns
+/// The reason the request is denied
+pub fn check_http_request(
+    permissions: &WidgetPermissions,
+    method: HttpMethod,
+    url: &str,
+    body_size: usize,
+) -> Result<(), String> {
+    let Some(http) = &permissions.http else {
+        return Err("widget was not granted HTTP access".to_string());
+    };
+    let parsed_url = reqwest::Url::parse(url).map_err(|err| format!("invalid URL: {}", err))?;
+    let Some(host) = parsed_url.host_str() else {
+        return Err("URL has no host".to_string());
+    };
+    if !http.hosts.iter().any(|allowed| host_matches(allowed, host)) {
+        return Err(format!("host {} was not granted", host));
+    }
+    if !http.methods.contains(&method) {
+        return Err(format!("method {:?} was not granted", method));
+    }
+    check_body_size(permissions, body_size)
+}
+
+/// Check whether a request or response body exceeds the granted maximum body size
+/// # Arguments
+/// * `permissions` - The permissions granted to the widget
+/// * `body_size` - Size of the body in bytes
+/// # Returns
+/// The reason the body is denied
+fn check_body_size(permissions: &WidgetPermissions, body_size: usize) -> Result<(), String> {
+    let max_body_size = permissions
+        .http
+        .as_ref()
+        .map_or(0, |http| http.max_body_size);
+    if body_size as u64 > max_body_size {
+        return Err(format!(
+            "body of {} bytes exceeds the granted maximum of {} bytes",
+            body_size, max_body_size
+        ));
+    }
+    Ok(())
+}
+
+fn host_matches(allowed: &str, host: &str) -> bool {
+    let allowed = allowed.to_ascii_lowercase();
+    let host = host.to_ascii_lowercase();
+    match allowed.strip_prefix("*.") {
+        Some(domain) => host.ends_with(&format!(".{}", domain)),
+        None => host == allowed,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn permissions() -> WidgetPermissions {
+        WidgetPermissions {
+            http: Some(HttpPermissions {
+                hosts: vec![
+                    "transport.opendata.ch".to_string(),
+                    "*.existenz.ch".to_string(),
+                ],
+                methods: vec![HttpMethod::Get],
+                max_body_size: 1024,
+            }),
+        }
+    }
+
+    #[test]
+    fn test_granted_request() {
+        let permissions = permissions();
+        assert!(check_http_request(
+            &permissions,
+            HttpMethod::Get,
+            "https://transport.opendata.ch/v1/stationboard",
+            0
+        )
+        .is_ok());
+        assert!(check_http_request(
+            &permissions,
+            HttpMethod::Get,
+            "https://aareguru.existenz.ch/v2018/current",
+            0
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn test_denied_request() {
+        let permissions = permissions();
+        let check = |method, url: &str, body_size| {
+            check_http_request(&permissions, method, url, body_size)
+        };
+        assert!(check(HttpMethod::Get, "https://example.com", 0).is_err());
+        assert!(check(HttpMethod::Get, "https://existenz.ch", 0).is_err());
+        assert!(check(HttpMethod::Get, "https://transport.opendata.ch.evil.com", 0).is_err());
+        assert!(check(HttpMethod::Post, "https://transport.opendata.ch", 0).is_err());
+        assert!(check(HttpMethod::Get, "https://transport.opendata.ch", 1025).is_err());
+        assert!(check(HttpMethod::Get, "not a url", 0).is_err());
+        assert!(check_http_request(
+            &WidgetPermissions::default(),
+            HttpMethod::Get,
+            "https://transport.opendata.ch",
+            0
+        )
+        .is_err());
+    }
+}
@@ -1,60 +1,338 @@
 use std::{
     collections::{hash_map::DefaultHasher, HashMap},
     hash::{Hash, Hasher},
-    time::SystemTime,
+    sync::mpsc::Sender,
+    time::{Duration, Instant},
 };
 
 use anyhow::{bail, Error};
+use common::models::{
+    Location, LogLevel, LogSource, RateLimit, SystemConfiguration, WasiGrants, WidgetInstallationData,
+    WidgetPermissions,
+};
+use jiff::tz::TimeZone;
 use serde::{Deserialize, Serialize};
 use wasmtime::{
     self,
-    component::{Component, Linker},
+    component::{Component, ComponentType, Instance, Lift, Linker},
     Config, Engine, Store,
 };
-use wasmtime_wasi::{ResourceTable, WasiCtx, WasiCtxBuilder, WasiView};
+use wasmtime_wasi::{ResourceTable, WasiCtx, WasiView};
 
+use crate::widgets::host_api::random::WidgetRng;
+use crate::widgets::host_api::storage::StorageChanges;
+use crate::widgets::host_api::timezone::resolve_timezone;
 use crate::widgets::running::runtime::widget::widget::clocks::Datetime;
+use crate::widgets::running::health::HealthRegistry;
+use crate::widgets::running::wasi::{build_wasi_ctx, CapturedOutput, ScratchDirectoryCopy};
 use crate::widgets::utils::logging::LOGGING_PREFIX;
+use crate::widgets::utils::widget_logs::WidgetLogs;
 
 wasmtime::component::bindgen!({ path: "../wg_display_widget_wit/wit" });
 
+/// How a widget wants to be run, returned by the optional `get-run-mode` export
+#[derive(ComponentType, Lift, Clone, Copy, PartialEq, Debug)]
+#[component(enum)]
+#[repr(u8)]
+pub enum RunMode {
+    #[component(name = "polled")]
+    Polled,
+    #[component(name = "streaming")]
+    Streaming,
+}
+
+/// Permissions a widget requests, returned by the optional `get-permissions` export
+#[derive(ComponentType, Lift, Clone, PartialEq, Debug)]
+#[component(record)]
+pub struct Permissions {
+    pub http: Option<HttpPermissions>,
+}
+
+#[derive(ComponentType, Lift, Clone, PartialEq, Debug)]
+#[component(record)]
+pub struct HttpPermissions {
+    pub hosts: Vec<String>,
+    pub methods: Vec<widget::widget::http::Method>,
+    #[component(name = "max-body-size")]
+    pub max_body_size: u64,
+}
+
+/// A widget instantiated by [Runtime::instantiate_widget]
+/// Exports added to the widget world after widgets were published are optional and looked up by name,
+/// so widgets built against an earlier version of the world can still be instantiated.
+pub struct WidgetInstance {
+    /// Queried once on instantiation, e.g. to attribute the output of the widget
+    name: String,
+    bindings: Widget,
+    instance: Instance,
+}
+
+impl WidgetInstance {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
 #[derive(Serialize, Deserialize, PartialEq, Debug)]
 pub struct CompiledWidget {
     pub data: Vec<u8>,
     compatibility_hash: u64,
 }
 
+lazy_static! {
+    /// Hash of the engine settings a precompiled widget depends on
+    static ref COMPATIBILITY_HASH: u64 = {
+        let engine = Engine::new(&engine_config()).expect("Could not create engine");
+        let mut hasher = DefaultHasher::new();
+        engine.precompile_compatibility_hash().hash(&mut hasher);
+        hasher.finish()
+    };
+    /// Seed for deterministic random values, see [Runtime::set_random_seed]
+    pub static ref RANDOM_SEED: Option<u64> = read_random_seed();
+}
+
+/// Read the seed for deterministic random values from the environment
+/// A warning is logged if it is set, as widgets can't keep secrets with predictable random values.
+fn read_random_seed() -> Option<u64> {
+    let value = std::env::var(RANDOM_SEED_ENV).ok()?;
+    match value.parse() {
+        Ok(seed) => {
+            log::warn!(
+                "{}: {} is set, widgets get predictable random values. Only use it for tests",
+                LOGGING_PREFIX,
+                RANDOM_SEED_ENV
+            );
+            Some(seed)
+        }
+        Err(_) => {
+            log::warn!(
+                "{}: {} is not a number, it is ignored",
+                LOGGING_PREFIX,
+                RANDOM_SEED_ENV
+            );
+            None
+        }
+    }
+}
+
+impl CompiledWidget {
+    /// Check if a widget needs to be recompiled
+    /// This can happen if the engine was updated or configured in a non backwards compatible way
+    /// # Returns
+    /// True if the widget needs to be recompiled
+    pub fn needs_recompilation(&self) -> bool {
+        self.compatibility_hash != *COMPATIBILITY_HASH
+    }
+}
+
+/// Configuration of the engines, widgets are precompiled for it
+fn engine_config() -> Config {
+    let mut config = Config::new();
+    Config::wasm_component_model(&mut config, true);
+    // Lets the widget calls be interrupted from another thread, see [Interrupter]
+    config.epoch_interruption(true);
+    config
+}
+
+/// Interrupts the widget calls of a [Runtime] from another thread, e.g. when they take too long
+/// The interrupted call fails with [wasmtime::Trap::Interrupt], as does every later call in the runtime.
+/// Calls blocked in the host (e.g. on an HTTP request) are only interrupted once they return to the widget.
+#[derive(Clone)]
+pub struct Interrupter {
+    engine: Engine,
+}
+
+impl Interrupter {
+    /// Interrupt the running and all later widget calls of the runtime
+    pub fn interrupt(&self) {
+        self.engine.increment_epoch();
+    }
+}
+
+/// Whether a widget call failed as it was interrupted by an [Interrupter]
+/// # Arguments
+/// * `err` - The error of the call
+pub fn is_interrupt(err: &Error) -> bool {
+    err.downcast_ref::<wasmtime::Trap>() == Some(&wasmtime::Trap::Interrupt)
+}
+
 pub struct WidgetState {
+    /// WASI context used outside of widget runs
     ctx: WasiCtx,
+    /// WASI contexts of the widgets together with the grants they were built from
+    widget_ctxs: HashMap<String, (WasiGrants, WasiCtx)>,
     table: ResourceTable,
+    stdout: CapturedOutput,
+    stderr: CapturedOutput,
+    /// Name of the widget that is currently run
+    pub widget_name: String,
+    /// Permissions granted to the widget that is currently run
+    pub permissions: WidgetPermissions,
+    /// Rate limit of the HTTP requests of the widget that is currently run
+    pub http_rate_limit: Option<RateLimit>,
+    /// Secrets handed out to the widget that is currently run, they are redacted from its output
+    revealed_secrets: Vec<String>,
+    /// Location of the display
+    pub location: Option<Location>,
+    /// Timezone of the display
+    pub timezone: TimeZone,
+    /// Receives the updates pushed by the widget that is currently run in streaming mode
+    pub push_target: Option<Sender<PushedUpdate>>,
+    /// Asks a widget run in streaming mode to stop after its first update, used for test runs
+    pub stop_after_push: bool,
+    /// Source of the random values of the widget that is currently run
+    pub rng: WidgetRng,
+    /// Changes to the storage during a test run, they are not saved
+    pub test_run_storage: Option<StorageChanges>,
+    /// Copy of the scratch directory mounted during a test run
+    test_run_scratch: Option<ScratchDirectoryCopy>,
+}
+
+/// Update pushed by a widget run in streaming mode
+pub struct PushedUpdate {
+    pub widget_name: String,
+    pub data: String,
+    /// The data is an error message, as the run of the widget failed
+    pub failed: bool,
+}
+
+impl WidgetState {
+    /// Create the state of a runtime, no widget is run yet
+    pub fn new() -> Self {
+        let stdout = CapturedOutput::default();
+        let stderr = CapturedOutput::default();
+        Self {
+            ctx: build_wasi_ctx("", &WasiGrants::default(), None, None, &stdout, &stderr)
+                .expect("Could not build WASI context"),
+            widget_ctxs: HashMap::new(),
+            table: ResourceTable::new(),
+            stdout,
+            stderr,
+            widget_name: String::new(),
+            permissions: WidgetPermissions::default(),
+            http_rate_limit: None,
+            revealed_secrets: Vec::new(),
+            location: None,
+            timezone: TimeZone::system(),
+            push_target: None,
+            stop_after_push: false,
+            rng: WidgetRng::Secure,
+            test_run_storage: None,
+            test_run_scratch: None,
+        }
+    }
+
+    /// Make sure the WASI context of a widget matches the grants of the widget
+    /// During a test run a context mounting the copy of the scratch directory is built,
+    /// it is replaced before the next regular run.
+    /// With a random seed the context is built for every run, so every run gets the same random values.
+    /// # Arguments
+    /// * `widget_name` - Name of the widget
+    /// * `grants` - The optional parts of WASI granted to the widget
+    /// * `random_seed` - The seed of the random values, see [Runtime::set_random_seed]
+    fn prepare_wasi_ctx(
+        &mut self,
+        widget_name: &str,
+        grants: &WasiGrants,
+        random_seed: Option<u64>,
+    ) -> Result<(), Error> {
+        let is_up_to_date = self.test_run_scratch.is_none()
+            && random_seed.is_none()
+            && self
+                .widget_ctxs
+                .get(widget_name)
+                .is_some_and(|(ctx_grants, _)| ctx_grants == grants);
+        if !is_up_to_date {
+            let ctx = build_wasi_ctx(
+                widget_name,
+                grants,
+                self.test_run_scratch.as_ref(),
+                random_seed,
+                &self.stdout,
+                &self.stderr,
+            )?;
+            self.widget_ctxs
+                .insert(widget_name.to_owned(), (grants.clone(), ctx));
+        }
+        Ok(())
+    }
+
+    /// Remember a secret handed out to the current widget so it can be redacted from its output
+    /// # Arguments
+    /// * `value` - The value of the secret
+    pub fn reveal_secret(&mut self, value: &str) {
+        if !value.is_empty() && !self.revealed_secrets.iter().any(|known| known == value) {
+            self.revealed_secrets.push(value.to_owned());
+        }
+    }
+
+    /// Replace all secrets handed out to the current widget in a message
+    /// # Arguments
+    /// * `message` - The message written by the widget
+    /// # Returns
+    /// The message without secrets
+    pub fn redact(&self, message: &str) -> String {
+        self.revealed_secrets
+            .iter()
+            .fold(message.to_owned(), |message, secret| message.replace(secret, "***"))
+    }
 }
 
 impl WasiView for WidgetState {
-    fn ctx(&mut self) -> &mut WasiCtx { &mut self.ctx }
+    fn ctx(&mut self) -> &mut WasiCtx {
+        match self.widget_ctxs.get_mut(&self.widget_name) {
+            Some((_, ctx)) => ctx,
+            None => &mut self.ctx,
+        }
+    }
     fn table(&mut self) -> &mut ResourceTable { &mut self.table }
 }
 
+/// Environment variable holding the seed for deterministic random values
+const RANDOM_SEED_ENV: &str = "WG_DISPLAY_RANDOM_SEED";
+
+/// Shortest time between two runs of a widget, also applies to the hints of widgets
+const MIN_RUN_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Point in time a widget was last run
+struct LastRun {
+    /// Wall clock time passed to the widget
+    datetime: Datetime,
+    /// Monotonic time the widget is due to run again, it is not affected by changes of the wall clock
+    due: Instant,
+}
+
+/// Compute when a widget is due to run again
+/// # Arguments
+/// * `finished` - Monotonic time the last run finished
+/// * `update_cycle` - The time between two runs declared by the widget
+/// * `hint` - The time until the next run requested by the last run, takes precedence over the update cycle
+/// * `backoff` - Minimum delay because of failed runs
+fn next_due(
+    finished: Instant,
+    update_cycle: Duration,
+    hint: Option<Duration>,
+    backoff: Duration,
+) -> Instant {
+    finished + hint.unwrap_or(update_cycle).max(MIN_RUN_INTERVAL).max(backoff)
+}
+
 pub struct Runtime {
     engine: Engine,
     store: Store<WidgetState>,
     linker: Linker<WidgetState>,
-    last_run: HashMap<String, Datetime>,
+    last_run: HashMap<String, LastRun>,
+    /// Makes the random values handed to widgets deterministic
+    random_seed: Option<u64>,
 }
 
 impl Runtime {
     pub fn new() -> Self {
-        let mut config = Config::new();
-        Config::wasm_component_model(&mut config, true);
-
-        let engine = Engine::new(&config).expect("Could not create engine");
+        let engine = Engine::new(&engine_config()).expect("Could not create engine");
         let mut linker = Linker::<WidgetState>::new(&engine);
+        // All of WASI is linked, the WASI context restricts what widgets can actually reach
         wasmtime_wasi::add_to_linker_sync(&mut linker).expect("Could not link WASI functions");
-        let mut builder = WasiCtxBuilder::new();
-        let state = WidgetState {
-            ctx: builder.build(),
-            table: ResourceTable::new(),
-        };
-        let store = Store::new(&engine, state);
+        let store = Runtime::new_store(&engine, WidgetState::new());
         Widget::add_to_linker(&mut linker, |state: &mut WidgetState| state)
             .expect("Could not link host API");
 
@@ -63,9 +341,53 @@ impl Runtime {
             store,
             linker,
             last_run: HashMap::new(),
+            random_seed: *RANDOM_SEED,
         }
     }
 
+    fn new_store(engine: &Engine, state: WidgetState) -> Store<WidgetState> {
+        let mut store = Store::new(engine, state);
+        // The calls trap once the epoch of the engine is incremented
+        store.set_epoch_deadline(1);
+        store
+    }
+
+    /// Drop all widgets instantiated in this runtime and free their memory
+    /// A store never frees its instances, so this has to be done before a widget is instantiated again.
+    /// The schedule and the system configuration are kept, the dropped widgets can not be called anymore.
+    pub fn reset_store(&mut self) {
+        let previous = self.store.data();
+        let mut state = WidgetState::new();
+        state.location = previous.location.clone();
+        state.timezone = previous.timezone.clone();
+        self.store = Runtime::new_store(&self.engine, state);
+    }
+
+    /// Get a handle to interrupt the widget calls of this runtime from another thread
+    pub fn interrupter(&self) -> Interrupter {
+        Interrupter {
+            engine: self.engine.clone(),
+        }
+    }
+
+    /// Make the random values handed to widgets deterministic, e.g. to reproduce their output in tests.
+    /// Every run of a widget then gets the same values, from the `random` interface as well as from WASI.
+    /// Can also be set using the `WG_DISPLAY_RANDOM_SEED` environment variable.
+    /// # Arguments
+    /// * `seed` - The seed or None to use a cryptographically secure generator
+    pub fn set_random_seed(&mut self, seed: Option<u64>) {
+        self.random_seed = seed;
+    }
+
+    /// Apply the system wide settings widgets can read, such as location and timezone
+    /// # Arguments
+    /// * `config` - The system configuration
+    pub fn apply_system_config(&mut self, config: &SystemConfiguration) {
+        let state = self.store.data_mut();
+        state.location = config.location.clone();
+        state.timezone = resolve_timezone(config.timezone.as_deref());
+    }
+
     /// Precompile a widget from a binary
     /// The resulting binary can be persisted and later instantiated using `instantiate_widget`
     /// # Arguments
@@ -73,28 +395,21 @@ impl Runtime {
     /// # Returns
     /// The precompiled widget
     pub fn compile_widget(&self, bytes: &[u8]) -> Result<CompiledWidget, Error> {
-        let mut hasher = DefaultHasher::new();
-        let compatibility_hash = self.engine.precompile_compatibility_hash();
-        compatibility_hash.hash(&mut hasher);
-        let compatibility_hash = hasher.finish();
-
         let data = self.engine.precompile_component(bytes)?;
         Ok(CompiledWidget {
             data,
-            compatibility_hash,
+            compatibility_hash: *COMPATIBILITY_HASH,
         })
     }
 
     /// Instantiate a widget from a binary that can then be run using `run_widget`
+    /// The name of the widget is queried once, see [WidgetInstance::name]
     /// # Arguments
     /// * `widget` - The precompiled widget. Can be produced by `compile_widget`
     /// # Returns
     /// The instantiated widget
-    pub fn instantiate_widget(&mut self, widget: &CompiledWidget) -> Result<Widget, Error> {
-        // TODO: refactor
-        self.last_run.clear();
-
-        if self.needs_recompilation(widget) {
+    pub fn instantiate_widget(&mut self, widget: &CompiledWidget) -> Result<WidgetInstance, Error> {
+        if widget.needs_recompilation() {
             bail!("Widget needs to be recompiled");
         }
         // Load precompiled component
@@ -102,54 +417,100 @@ impl Runtime {
         // https://docs.rs/wasmtime/9.0.2/wasmtime/component/struct.Component.html#method.deserialize
         let start = std::time::Instant::now();
         let component = unsafe { Component::deserialize(&self.engine, &widget.data) }?;
-        let widget = Widget::instantiate(&mut self.store, &component, &self.linker)?;
+        let instance = self
+            .linker
+            .instantiate(&mut self.store, &component)
+            .and_then(|instance| {
+                let bindings = Widget::new(&mut self.store, &instance)?;
+                let name = bindings.call_get_name(&mut self.store)?;
+                Ok(WidgetInstance {
+                    name,
+                    bindings,
+                    instance,
+                })
+            });
+        let widget = match instance {
+            Ok(widget) => widget,
+            Err(err) => {
+                self.discard_output();
+                return Err(err);
+            }
+        };
         let duration = start.elapsed();
         log::info!(
             "{}: Deserialized and instantiated widget in {} ms",
             LOGGING_PREFIX,
             duration.as_millis()
         );
+        self.forward_output_of(&widget);
         Ok(widget)
     }
 
     /// Run a widget with a given configuration
+    /// A widget that failed can not be called again, as its instance may have trapped.
+    /// It has to be instantiated again after `reset_store`, the backoff of the failure is kept.
     /// # Arguments
     /// * `widget` - The widget to run. Can be produced by `instantiate_widget`
-    /// * `config` - The configuration to run the widget with. Must be valid JSON and match the schema returned by `get_config_schema`
+    /// * `installation` - The installation data of the widget.
+    ///   Its configuration must be valid JSON and match the schema returned by `get_config_schema`.
+    ///   The widget is run with the permissions and WASI grants it contains.
     /// # Returns
-    /// The result of the widget run
+    /// The result of the widget run, secrets handed out to the widget are redacted from its data and errors
     pub fn run_widget(
         &mut self,
-        widget: &Widget,
-        config: &str,
+        widget: &WidgetInstance,
+        installation: &WidgetInstallationData,
     ) -> wasmtime::Result<Option<WidgetResult>> {
-        let name = self.get_widget_name(widget).unwrap();
-        let last_invocation = self.last_run.get(name.as_str());
-
-        let update_cycle_seconds = widget
-            .call_get_run_update_cycle_seconds(&mut self.store)
-            .unwrap();
-        if let Some(last_invocation) = last_invocation {
-            if (last_invocation.seconds + update_cycle_seconds as u64)
-                > SystemTime::now()
-                    .duration_since(SystemTime::UNIX_EPOCH)
-                    .unwrap()
-                    .as_secs()
-            {
-                // Do not updated widget as it is not time yet
-                return Ok(None);
-            }
+        let name = widget.name.clone();
+        let last_run = self.last_run.get(name.as_str());
+        if last_run.is_some_and(|run| Instant::now() < run.due) {
+            // Do not updated widget as it is not time yet
+            return Ok(None);
         }
 
         let context = WidgetContext {
-            last_invocation: *last_invocation.unwrap_or(&Datetime::now()),
-            config: config.to_owned(),
+            last_invocation: last_run.map_or_else(Datetime::now, |run| run.datetime),
+            config: installation.json_config.clone(),
+        };
+        // Queried before the run, a widget that trapped can not be called anymore
+        let update_cycle = match self.get_update_cycle_seconds(widget) {
+            Ok(seconds) => Duration::from_secs(seconds as u64),
+            Err(err) => return Err(self.fail_before_run(name, err)),
         };
 
+        if let Err(err) = self.begin_run(&name, installation) {
+            self.end_run(&name);
+            return Err(self.fail_before_run(name, err));
+        }
         let start = std::time::Instant::now();
-        let res = widget.call_run(&mut self.store, &context);
+        let res = widget.bindings.call_run(&mut self.store, &context);
         let duration = start.elapsed();
-        self.last_run.insert(name, Datetime::now());
+        // The revealed secrets are forgotten by `end_run`, so they are redacted before
+        let state = self.store.data();
+        let res = match res {
+            Ok(res) => Ok(WidgetResult {
+                data: state.redact(&res.data),
+                ..res
+            }),
+            Err(err) => Err(Error::msg(state.redact(&format!("{:#}", err)))),
+        };
+        self.end_run(&name);
+
+        let backoff = match &res {
+            Ok(_) => {
+                HealthRegistry::record_success(&name, duration);
+                Duration::ZERO
+            }
+            Err(err) => {
+                HealthRegistry::record_failure(&name, Some(duration), &format!("{:#}", err))
+            }
+        };
+        let hint = res
+            .as_ref()
+            .ok()
+            .and_then(|res| res.next_run_in_ms)
+            .map(|ms| Duration::from_millis(ms as u64));
+        self.schedule(name, update_cycle, hint, backoff);
 
         log::info!(
             "{}: Widget invocation took {} ms",
@@ -159,13 +520,231 @@ impl Runtime {
         res.map(Some)
     }
 
-    /// Get the name of a widget dynamically
+    /// Run a widget in streaming mode. Blocks until the widget returns from `run`.
+    /// The widget pushes its updates, the result of `run` is pushed as last update.
+    /// # Arguments
+    /// * `widget` - The widget to run. Can be produced by `instantiate_widget`
+    /// * `installation` - The installation data of the widget, see `run_widget`
+    /// * `updates` - Receives the updates of the widget.
+    ///   The widget is asked to stop once the receiving end is dropped.
+    /// # Returns
+    /// The minimum delay before the widget should be run again because of failures.
+    /// The error of the call if it was interrupted, see [is_interrupt]
+    pub fn run_streaming_widget(
+        &mut self,
+        widget: &WidgetInstance,
+        installation: &WidgetInstallationData,
+        updates: Sender<PushedUpdate>,
+    ) -> wasmtime::Result<Duration> {
+        let name = widget.name.clone();
+        let context = WidgetContext {
+            last_invocation: Datetime::now(),
+            config: installation.json_config.clone(),
+        };
+
+        // Recorded as failure by the caller, see `StreamingWidget::spawn`
+        if let Err(err) = self.begin_run(&name, installation) {
+            self.end_run(&name);
+            return Err(err);
+        }
+        self.store.data_mut().push_target = Some(updates.clone());
+        let start = std::time::Instant::now();
+        let res = widget.bindings.call_run(&mut self.store, &context);
+        let duration = start.elapsed();
+        let state = self.store.data_mut();
+        state.push_target = None;
+        let (data, backoff) = match res {
+            Ok(res) => {
+                HealthRegistry::record_success(&name, duration);
+                (state.redact(&res.data), Duration::ZERO)
+            }
+            // The widget was stopped, it did not fail
+            Err(err) if is_interrupt(&err) => {
+                self.end_run(&name);
+                return Err(err);
+            }
+            Err(err) => {
+                let message = state.redact(&format!("{:#}", err));
+                WidgetLogs::record(
+                    &name,
+                    LogLevel::Error,
+                    LogSource::Host,
+                    &format!("Run failed: {}", message),
+                );
+                let backoff = HealthRegistry::record_failure(&name, Some(duration), &message);
+                (message, backoff)
+            }
+        };
+        self.end_run(&name);
+
+        // The receiver is gone if the widget was stopped, nothing to show then
+        let _ = updates.send(PushedUpdate {
+            widget_name: name,
+            data,
+            failed: !backoff.is_zero(),
+        });
+        Ok(backoff)
+    }
+
+    /// Run a widget once, e.g. to try a configuration before saving it
+    /// Unlike `run_widget` the update cycle is ignored and the health of the widget is not recorded.
+    /// Widgets in streaming mode are asked to stop after their first update.
+    /// The widget works on a copy of its storage and scratch directory, its changes are discarded.
+    /// # Arguments
+    /// * `widget` - The widget to run. Can be produced by `instantiate_widget`
+    /// * `installation` - The installation data of the widget, see `run_widget`
+    /// # Returns
+    /// What the widget would display or why the run failed, secrets are redacted from both
+    pub fn test_run_widget(
+        &mut self,
+        widget: &WidgetInstance,
+        installation: &WidgetInstallationData,
+    ) -> wasmtime::Result<String> {
+        let name = widget.name.clone();
+        let context = WidgetContext {
+            last_invocation: Datetime::now(),
+            config: installation.json_config.clone(),
+        };
+
+        let scratch = if installation.wasi_grants.scratch_directory {
+            Some(ScratchDirectoryCopy::create(&name)?)
+        } else {
+            None
+        };
+        let state = self.store.data_mut();
+        state.test_run_storage = Some(StorageChanges::default());
+        state.test_run_scratch = scratch;
+        if let Err(err) = self.begin_run(&name, installation) {
+            self.end_test_run(&name);
+            return Err(err);
+        }
+        // Only streaming widgets get a push target, as the host behaves differently for them (e.g. `sleep`).
+        // Updates are not shown, the result of `run` is what the widget displays once it stopped.
+        let streaming = matches!(self.get_run_mode(widget), Ok(RunMode::Streaming));
+        let (updates, _received) = std::sync::mpsc::channel();
+        let state = self.store.data_mut();
+        state.push_target = streaming.then_some(updates);
+        state.stop_after_push = true;
+        let res = widget.bindings.call_run(&mut self.store, &context);
+        let state = self.store.data_mut();
+        state.push_target = None;
+        state.stop_after_push = false;
+        let res = match res {
+            Ok(res) => Ok(state.redact(&res.data)),
+            Err(err) => Err(Error::msg(state.redact(&format!("{:#}", err)))),
+        };
+        self.end_run(&name);
+        self.end_test_run(&name);
+        res
+    }
+
+    /// Discard the changes of a test run
+    /// # Arguments
+    /// * `name` - Name of the widget
+    fn end_test_run(&mut self, name: &str) {
+        let state = self.store.data_mut();
+        state.test_run_storage = None;
+        if state.test_run_scratch.is_some() {
+            // The context mounts the copy, which is removed with it
+            state.widget_ctxs.remove(name);
+            state.test_run_scratch = None;
+        }
+    }
+
+    /// Get how a widget wants to be run
+    /// # Arguments
+    /// * `widget` - The widget to get the run mode of. Can be produced by `instantiate_widget`
+    /// # Returns
+    /// The run mode of the widget, widgets not exporting `get-run-mode` are polled
+    pub fn get_run_mode(&mut self, widget: &WidgetInstance) -> wasmtime::Result<RunMode> {
+        let res = self.call_optional_export(widget, "get-run-mode");
+        self.forward_output_of(widget);
+        res.map(|mode| mode.unwrap_or(RunMode::Polled))
+    }
+
+    /// Get the time between two runs a widget declares
+    /// # Arguments
+    /// * `widget` - The widget to get the update cycle of. Can be produced by `instantiate_widget`
+    /// # Returns
+    /// The update cycle in seconds
+    pub fn get_update_cycle_seconds(&mut self, widget: &WidgetInstance) -> wasmtime::Result<u32> {
+        let res = widget
+            .bindings
+            .call_get_run_update_cycle_seconds(&mut self.store);
+        self.forward_output_of(widget);
+        res
+    }
+
+    /// Record a run that failed before the widget was called, so it is backed off like a failed run
+    /// # Arguments
+    /// * `name` - Name of the widget
+    /// * `err` - Why the run failed
+    /// # Returns
+    /// The error
+    fn fail_before_run(&mut self, name: String, err: Error) -> Error {
+        let backoff = HealthRegistry::record_failure(&name, None, &format!("{:#}", err));
+        self.schedule(name, Duration::ZERO, None, backoff);
+        err
+    }
+
+    /// Record that a widget ran and compute when it is due again
     /// # Arguments
-    /// * `widget` - The widget to get the name of. Can be produced by `instantiate_widget`
+    /// * `name` - Name of the widget
+    /// * `update_cycle` - The time between two runs declared by the widget
+    /// * `hint` - The time until the next run requested by the run
+    /// * `backoff` - Minimum delay because of failed runs
+    fn schedule(
+        &mut self,
+        name: String,
+        update_cycle: Duration,
+        hint: Option<Duration>,
+        backoff: Duration,
+    ) {
+        self.last_run.insert(
+            name,
+            LastRun {
+                datetime: Datetime::now(),
+                due: next_due(Instant::now(), update_cycle, hint, backoff),
+            },
+        );
+    }
+
+    /// Get the point in time the next polled widget is due to run
     /// # Returns
-    /// The name of the widget
-    pub fn get_widget_name(&mut self, widget: &Widget) -> wasmtime::Result<String> {
-        widget.call_get_name(&mut self.store)
+    /// The monotonic time or None if no widget ran yet
+    pub fn next_due(&self) -> Option<Instant> {
+        self.last_run.values().map(|run| run.due).min()
+    }
+
+    /// Grant a widget its permissions and WASI context for a run
+    /// # Arguments
+    /// * `name` - Name of the widget
+    /// * `installation` - The installation data of the widget
+    fn begin_run(&mut self, name: &str, installation: &WidgetInstallationData) -> Result<(), Error> {
+        let state = self.store.data_mut();
+        state.prepare_wasi_ctx(name, &installation.wasi_grants, self.random_seed)?;
+        state.widget_name = name.to_owned();
+        state.permissions = installation.permissions.clone();
+        state.http_rate_limit = installation.http_rate_limit.clone();
+        state.rng = match self.random_seed {
+            Some(seed) => WidgetRng::seeded(seed, name),
+            None => WidgetRng::Secure,
+        };
+        Ok(())
+    }
+
+    /// Revoke everything granted by `begin_run` and forward the output of the widget
+    /// Calls outside of `run` (e.g. `get-name`) never get any permissions
+    /// # Arguments
+    /// * `name` - Name of the widget
+    fn end_run(&mut self, name: &str) {
+        let state = self.store.data_mut();
+        state.permissions = WidgetPermissions::default();
+        state.http_rate_limit = None;
+        state.widget_name = String::new();
+        state.rng = WidgetRng::Secure;
+        Runtime::forward_widget_output(state, name);
+        state.revealed_secrets.clear();
     }
 
     /// Get the configuration schema of a widget
@@ -173,8 +752,10 @@ impl Runtime {
     /// * `widget` - The widget to get the configuration schema of. Can be produced by `instantiate_widget`
     /// # Returns
     /// The configuration schema of the widget as string
-    pub fn get_config_schema(&mut self, widget: &Widget) -> wasmtime::Result<String> {
-        widget.call_get_config_schema(&mut self.store)
+    pub fn get_config_schema(&mut self, widget: &WidgetInstance) -> wasmtime::Result<String> {
+        let res = widget.bindings.call_get_config_schema(&mut self.store);
+        self.forward_output_of(widget);
+        res
     }
 
     /// Get the version of a widget
@@ -182,22 +763,119 @@ impl Runtime {
     /// * `widget` - The widget to get the version of. Can be produced by `instantiate_widget`
     /// # Returns
     /// The version of the widget as string
-    pub fn get_widget_version(&mut self, widget: &Widget) -> wasmtime::Result<String> {
-        widget.call_get_version(&mut self.store)
+    pub fn get_widget_version(&mut self, widget: &WidgetInstance) -> wasmtime::Result<String> {
+        let res = widget.bindings.call_get_version(&mut self.store);
+        self.forward_output_of(widget);
+        res
     }
 
-    /// Check if a widget needs to be recompiled
-    /// This can happen if the engine was updated in a non backwards compatible way
+    /// Get the permissions a widget declares in its manifest
     /// # Arguments
-    /// * `widget` - The widget to check
+    /// * `widget` - The widget to get the permissions of. Can be produced by `instantiate_widget`
     /// # Returns
-    /// True if the widget needs to be recompiled
-    fn needs_recompilation(&self, widget: &CompiledWidget) -> bool {
-        let mut hasher = DefaultHasher::new();
-        self.engine
-            .precompile_compatibility_hash()
-            .hash(&mut hasher);
-        let compatibility_hash = hasher.finish();
-        compatibility_hash != widget.compatibility_hash
+    /// The permissions the widget requests, widgets not exporting `get-permissions` request none
+    pub fn get_widget_permissions(
+        &mut self,
+        widget: &WidgetInstance,
+    ) -> wasmtime::Result<WidgetPermissions> {
+        let res = self.call_optional_export::<Permissions>(widget, "get-permissions");
+        self.forward_output_of(widget);
+        res.map(|permissions| permissions.map(WidgetPermissions::from).unwrap_or_default())
+    }
+
+    /// Call an export of a widget that takes no arguments and that older widgets may not have
+    /// # Arguments
+    /// * `widget` - The widget to call. Can be produced by `instantiate_widget`
+    /// * `name` - Name of the export
+    /// # Returns
+    /// The result of the call or `None` if the widget does not have the export
+    fn call_optional_export<T: ComponentType + Lift>(
+        &mut self,
+        widget: &WidgetInstance,
+        name: &str,
+    ) -> wasmtime::Result<Option<T>> {
+        let Some(func) = widget.instance.get_func(&mut self.store, name) else {
+            return Ok(None);
+        };
+        let func = func.typed::<(), (T,)>(&self.store)?;
+        let (res,) = func.call(&mut self.store, ())?;
+        func.post_return(&mut self.store)?;
+        Ok(Some(res))
+    }
+
+    /// Forward what a widget wrote to stdout and stderr outside of `run` to its log,
+    /// so it is not attributed to the next widget that runs
+    /// # Arguments
+    /// * `widget` - The widget that was called
+    fn forward_output_of(&self, widget: &WidgetInstance) {
+        Runtime::forward_widget_output(self.store.data(), &widget.name);
+    }
+
+    /// Drop what was written to stdout and stderr by a widget whose name is unknown
+    fn discard_output(&mut self) {
+        let state = self.store.data();
+        state.stdout.take();
+        state.stderr.take();
+    }
+
+    /// Forward what a widget wrote to stdout and stderr to the log
+    /// # Arguments
+    /// * `state` - The state holding the captured output
+    /// * `widget_name` - Name of the widget that produced the output
+    pub fn forward_widget_output(state: &WidgetState, widget_name: &str) {
+        for line in state.redact(&state.stdout.take()).lines() {
+            WidgetLogs::record(widget_name, LogLevel::Info, LogSource::Stdout, line);
+        }
+        for line in state.redact(&state.stderr.take()).lines() {
+            WidgetLogs::record(widget_name, LogLevel::Warn, LogSource::Stderr, line);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_due() {
+        let cycle = Duration::from_secs(60);
+        let now = Instant::now();
+        let no_backoff = Duration::ZERO;
+        assert_eq!(next_due(now, cycle, None, no_backoff), now + cycle);
+        // Hints take precedence over the update cycle, in both directions
+        let hint = Duration::from_millis(250);
+        assert_eq!(next_due(now, cycle, Some(hint), no_backoff), now + hint);
+        let hint = Duration::from_secs(600);
+        assert_eq!(next_due(now, cycle, Some(hint), no_backoff), now + hint);
+        // Widgets can not run in a busy loop
+        assert_eq!(
+            next_due(now, cycle, Some(Duration::ZERO), no_backoff),
+            now + MIN_RUN_INTERVAL
+        );
+        assert_eq!(
+            next_due(now, Duration::ZERO, None, no_backoff),
+            now + MIN_RUN_INTERVAL
+        );
+        // Failing widgets are run less often, but never more often than they asked for
+        let backoff = Duration::from_secs(20);
+        assert_eq!(
+            next_due(now, Duration::from_secs(1), None, backoff),
+            now + backoff
+        );
+        assert_eq!(next_due(now, cycle, None, backoff), now + cycle);
+    }
+
+    #[test]
+    fn test_failed_run_is_backed_off() {
+        let mut runtime = Runtime::new();
+        assert_eq!(runtime.next_due(), None);
+        // A trapped run has no hint, only the backoff of the failure delays the next run
+        let backoff = Duration::from_secs(20);
+        runtime.schedule("widget".to_string(), Duration::from_secs(1), None, backoff);
+        let due = runtime.next_due().unwrap();
+        assert!(due >= Instant::now() + backoff - Duration::from_secs(1));
+        // The widget is instantiated again in a new store, it is not run before the backoff passed
+        runtime.reset_store();
+        assert_eq!(runtime.next_due(), Some(due));
     }
 }
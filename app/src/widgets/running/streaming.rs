@@ -0,0 +1,137 @@
+//! Runs widgets in streaming mode
+//!
+//! Streaming widgets are not polled. Their `run` is called once on a dedicated thread and pushes
+//! updates (e.g. from a streaming HTTP response) until the host asks them to stop.
+use std::{
+    sync::{
+        mpsc::{self, RecvTimeoutError, Sender},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use common::models::{LogLevel, LogSource, SystemConfiguration, WidgetInstallationData};
+
+use crate::shared::persistence::Persistence;
+use crate::shared::widget_manager::WidgetManager;
+use crate::widgets::running::health::HealthRegistry;
+use crate::widgets::running::runtime::{Interrupter, PushedUpdate, Runtime};
+use crate::widgets::utils::widget_logs::WidgetLogs;
+
+/// Time to wait before a streaming widget that returned from `run` is started again
+const MIN_RESTART_DELAY: Duration = Duration::from_secs(1);
+
+/// Handle to a widget running in streaming mode
+/// The widget is stopped when the handle is dropped
+pub struct StreamingWidget {
+    /// Wakes the thread up while it waits to restart the widget
+    _stop: Sender<()>,
+    stop: Arc<Mutex<StopState>>,
+}
+
+/// Shared between the handle and the thread running the widget
+#[derive(Default)]
+struct StopState {
+    stopped: bool,
+    /// Interrupts the runtime the widget currently runs in
+    interrupter: Option<Interrupter>,
+}
+
+impl Drop for StreamingWidget {
+    /// Interrupt the widget, as it only notices that it should stop if it pushes an update
+    fn drop(&mut self) {
+        let mut stop = self.stop.lock().unwrap();
+        stop.stopped = true;
+        if let Some(interrupter) = &stop.interrupter {
+            interrupter.interrupt();
+        }
+    }
+}
+
+impl StreamingWidget {
+    /// Start a widget in streaming mode on a dedicated thread
+    /// The widget gets its own runtime, so it does not block the polled widgets.
+    /// # Arguments
+    /// * `widget_name` - Name of the installed widget
+    /// * `config` - The system configuration
+    /// * `updates` - Receives the updates of the widget
+    /// # Returns
+    /// The handle to the widget
+    pub fn spawn(
+        widget_name: String,
+        config: SystemConfiguration,
+        updates: Sender<PushedUpdate>,
+    ) -> Self {
+        let (stop_tx, stop_rx) = mpsc::channel::<()>();
+        let stop = Arc::new(Mutex::new(StopState::default()));
+        let thread_stop = stop.clone();
+        thread::spawn(move || loop {
+            let restart_delay = match run_once(&widget_name, &config, updates.clone(), &thread_stop)
+            {
+                Ok(delay) => delay,
+                // Interrupted as the widget was stopped
+                Err(_) if thread_stop.lock().unwrap().stopped => break,
+                Err(err) => {
+                    WidgetLogs::record(
+                        &widget_name,
+                        LogLevel::Error,
+                        LogSource::Host,
+                        &format!("Streaming run failed: {}", err),
+                    );
+                    // Failed before or after the call of `run`, which records its own failures
+                    let backoff =
+                        HealthRegistry::record_failure(&widget_name, None, &err.to_string());
+                    let _ = updates.send(PushedUpdate {
+                        widget_name: widget_name.clone(),
+                        data: err.to_string(),
+                        failed: true,
+                    });
+                    backoff.max(MIN_RESTART_DELAY)
+                }
+            };
+            // Wait before restarting, unless the handle was dropped in the meantime
+            if let Err(RecvTimeoutError::Disconnected) = stop_rx.recv_timeout(restart_delay) {
+                break;
+            }
+        });
+        Self {
+            _stop: stop_tx,
+            stop,
+        }
+    }
+}
+
+/// Instantiate and run a streaming widget until it returns from `run` or is interrupted
+/// # Returns
+/// The time to wait before starting the widget again
+fn run_once(
+    widget_name: &str,
+    config: &SystemConfiguration,
+    updates: Sender<PushedUpdate>,
+    stop: &Mutex<StopState>,
+) -> anyhow::Result<Duration> {
+    let mut runtime = Runtime::new();
+    {
+        let mut stop = stop.lock().unwrap();
+        if stop.stopped {
+            anyhow::bail!("The widget was stopped");
+        }
+        stop.interrupter = Some(runtime.interrupter());
+    }
+    runtime.apply_system_config(config);
+    let widget = runtime.instantiate_widget(&WidgetManager::get_widget(widget_name)?)?;
+    let installation_data = Persistence::global()
+        .get_installation_data(widget_name)?
+        .unwrap_or_else(|| WidgetInstallationData {
+            name: widget_name.to_string(),
+            json_config: "{}".to_string(),
+            ..Default::default()
+        });
+    let backoff = runtime.run_streaming_widget(&widget, &installation_data, updates)?;
+
+    let update_cycle_seconds = runtime.get_update_cycle_seconds(&widget)?;
+    Ok(Duration::from_secs(update_cycle_seconds as u64)
+        .max(backoff)
+        .max(MIN_RESTART_DELAY))
+}
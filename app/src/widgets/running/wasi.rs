@@ -0,0 +1,302 @@
+//! The WASI context exposed to widgets
+//!
+//! All WASI interfaces are linked, so widgets built for `wasm32-wasip2` can be instantiated,
+//! but the context behind them is minimal:
+//! - No preopened directories, unless the user grants a scratch directory
+//! - No TCP, UDP or name lookups. Widgets use the `http` host API instead
+//! - No environment variables and no arguments
+//! - An empty stdin. Stdout and stderr are captured and forwarded to the log of the widget
+//! - Wall and monotonic clocks and secure random numbers of the host
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use anyhow::Result;
+use bytes::Bytes;
+use common::models::WasiGrants;
+use wasmtime_wasi::{
+    DirPerms, FilePerms, HostOutputStream, StdoutStream, StreamResult, Subscribe, WasiCtx,
+    WasiCtxBuilder,
+};
+
+use crate::shared::data_dir;
+use crate::widgets::host_api::random::seeded_rng;
+
+/// Directory in the data directory containing the scratch directories of all widgets
+pub const SCRATCH_DIRECTORY_ROOT: &str = "wg_display_scratch";
+
+/// Directory in the data directory containing the copies of scratch directories used by test runs
+const TEST_RUN_SCRATCH_ROOT: &str = "wg_display_scratch_test_runs";
+
+/// Path under which the scratch directory is visible to the widget
+const SCRATCH_DIRECTORY_GUEST_PATH: &str = "/scratch";
+
+/// Maximum number of bytes buffered per output stream until it is forwarded to the log
+/// Output exceeding it is dropped instead of failing the widget
+const MAX_CAPTURED_BYTES: usize = 64 * 1024;
+
+/// Captures what widgets write to stdout or stderr
+#[derive(Clone, Default)]
+pub struct CapturedOutput {
+    buffer: Arc<Mutex<Vec<u8>>>,
+}
+
+impl CapturedOutput {
+    /// Take everything written since the last call
+    /// # Returns
+    /// The captured output, invalid UTF-8 is replaced
+    pub fn take(&self) -> String {
+        let bytes = std::mem::take(&mut *self.buffer.lock().expect("Output buffer poisoned"));
+        String::from_utf8_lossy(&bytes).into_owned()
+    }
+}
+
+#[async_trait::async_trait]
+impl Subscribe for CapturedOutput {
+    async fn ready(&mut self) {}
+}
+
+impl HostOutputStream for CapturedOutput {
+    fn write(&mut self, bytes: Bytes) -> StreamResult<()> {
+        let mut buffer = self.buffer.lock().expect("Output buffer poisoned");
+        let free = MAX_CAPTURED_BYTES.saturating_sub(buffer.len());
+        buffer.extend_from_slice(&bytes[..bytes.len().min(free)]);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> StreamResult<()> {
+        Ok(())
+    }
+
+    fn check_write(&mut self) -> StreamResult<usize> {
+        Ok(MAX_CAPTURED_BYTES)
+    }
+}
+
+impl StdoutStream for CapturedOutput {
+    fn stream(&self) -> Box<dyn HostOutputStream> {
+        Box::new(self.clone())
+    }
+
+    fn isatty(&self) -> bool {
+        false
+    }
+}
+
+/// Copy of the scratch directory of a widget, so a test run does not change the original
+/// The copy is removed when dropped.
+pub struct ScratchDirectoryCopy {
+    path: PathBuf,
+}
+
+impl ScratchDirectoryCopy {
+    /// Copy the scratch directory of a widget
+    /// # Arguments
+    /// * `widget_name` - Name of the widget
+    /// # Returns
+    /// The copy, empty if the widget has no scratch directory yet
+    pub fn create(widget_name: &str) -> io::Result<ScratchDirectoryCopy> {
+        // Test runs of the same widget can overlap, e.g. when started from two dashboards
+        static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+        let path = data_dir::get().join(TEST_RUN_SCRATCH_ROOT).join(format!(
+            "{}_{}",
+            directory_name(widget_name),
+            NEXT_ID.fetch_add(1, Ordering::Relaxed)
+        ));
+        // Left over if the display stopped during a test run
+        remove_directory(&path)?;
+        let copy = ScratchDirectoryCopy { path };
+        copy_directory(&scratch_directory(widget_name), &copy.path)?;
+        Ok(copy)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for ScratchDirectoryCopy {
+    fn drop(&mut self) {
+        let _ = remove_directory(&self.path);
+    }
+}
+
+/// Build the WASI context of a widget
+/// # Arguments
+/// * `widget_name` - Name of the widget, used to locate its scratch directory
+/// * `grants` - The optional parts of WASI the user granted to the widget
+/// * `scratch_copy` - Used instead of the scratch directory of the widget, e.g. for test runs
+/// * `random_seed` - Makes the random values of WASI deterministic, see `Runtime::set_random_seed`
+/// * `stdout` - Receives what the widget writes to stdout
+/// * `stderr` - Receives what the widget writes to stderr
+/// # Returns
+/// The WASI context or an error if the scratch directory could not be created
+pub fn build_wasi_ctx(
+    widget_name: &str,
+    grants: &WasiGrants,
+    scratch_copy: Option<&ScratchDirectoryCopy>,
+    random_seed: Option<u64>,
+    stdout: &CapturedOutput,
+    stderr: &CapturedOutput,
+) -> Result<WasiCtx> {
+    let mut builder = WasiCtxBuilder::new();
+    builder
+        .stdout(stdout.clone())
+        .stderr(stderr.clone())
+        .allow_tcp(false)
+        .allow_udp(false)
+        .allow_ip_name_lookup(false);
+
+    if let Some(seed) = random_seed {
+        builder
+            .secure_random(seeded_rng(seed, &format!("{}/wasi/secure", widget_name)))
+            .insecure_random(seeded_rng(seed, &format!("{}/wasi/insecure", widget_name)))
+            .insecure_random_seed(seed as u128);
+    }
+
+    if grants.scratch_directory {
+        let directory = match scratch_copy {
+            Some(copy) => copy.path().to_path_buf(),
+            None => scratch_directory(widget_name),
+        };
+        fs::create_dir_all(&directory)?;
+        builder.preopened_dir(
+            &directory,
+            SCRATCH_DIRECTORY_GUEST_PATH,
+            DirPerms::all(),
+            FilePerms::all(),
+        )?;
+    }
+    Ok(builder.build())
+}
+
+/// Remove the scratch directory of a widget, if it has one
+/// # Arguments
+/// * `widget_name` - Name of the widget
+pub fn remove_scratch_directory(widget_name: &str) -> io::Result<()> {
+    remove_directory(&scratch_directory(widget_name))
+}
+
+/// Remove a directory, if it exists
+fn remove_directory(path: &Path) -> io::Result<()> {
+    match fs::remove_dir_all(path) {
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        res => res,
+    }
+}
+
+/// Copy a directory with its files and subdirectories
+/// Symbolic links are skipped, they could point outside of the directory.
+/// # Arguments
+/// * `from` - The directory to copy, a missing directory is copied as empty one
+/// * `to` - Where to create the copy
+fn copy_directory(from: &Path, to: &Path) -> io::Result<()> {
+    fs::create_dir_all(to)?;
+    let entries = match fs::read_dir(from) {
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+        entries => entries?,
+    };
+    for entry in entries {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        let target = to.join(entry.file_name());
+        if file_type.is_dir() {
+            copy_directory(&entry.path(), &target)?;
+        } else if file_type.is_file() {
+            fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
+/// Name of the scratch directory of a widget, safe to use as a single path component
+/// All bytes except ASCII letters and digits are percent-encoded, so widgets never share a directory.
+fn directory_name(widget_name: &str) -> String {
+    widget_name
+        .bytes()
+        .map(|byte| {
+            if byte.is_ascii_alphanumeric() {
+                (byte as char).to_string()
+            } else {
+                format!("%{:02X}", byte)
+            }
+        })
+        .collect()
+}
+
+/// Host path of the scratch directory of a widget
+fn scratch_directory(widget_name: &str) -> PathBuf {
+    data_dir::get()
+        .join(SCRATCH_DIRECTORY_ROOT)
+        .join(directory_name(widget_name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_captured_output() {
+        let output = CapturedOutput::default();
+        let mut stream = output.stream();
+        stream.write(Bytes::from_static(b"hello\n")).unwrap();
+        assert_eq!(output.take(), "hello\n");
+        assert_eq!(output.take(), "");
+
+        stream
+            .write(Bytes::from(vec![b'a'; MAX_CAPTURED_BYTES + 1]))
+            .unwrap();
+        assert_eq!(output.take().len(), MAX_CAPTURED_BYTES);
+    }
+
+    #[test]
+    fn test_scratch_directory() {
+        assert_eq!(
+            scratch_directory("Public Transport/../x"),
+            data_dir::get()
+                .join(SCRATCH_DIRECTORY_ROOT)
+                .join("Public%20Transport%2F%2E%2E%2Fx")
+        );
+        // Names that only differ in punctuation get their own directories
+        let names = ["A B", "A-B", "A_B", "A%20B"].map(directory_name);
+        for (i, name) in names.iter().enumerate() {
+            assert!(!names[i + 1..].contains(name));
+        }
+    }
+
+    #[test]
+    fn test_copy_directory() {
+        let root = std::env::temp_dir().join(format!(
+            "wg_display_test_copy_directory_{}",
+            std::process::id()
+        ));
+        let original = root.join("original");
+        fs::create_dir_all(original.join("cache")).unwrap();
+        fs::write(original.join("cache/data.json"), "{}").unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink("/etc/hostname", original.join("link")).unwrap();
+
+        let copy = root.join("copy");
+        copy_directory(&original, &copy).unwrap();
+        assert_eq!(
+            fs::read_to_string(copy.join("cache/data.json")).unwrap(),
+            "{}"
+        );
+        assert!(!copy.join("link").exists());
+        // Changes of the copy do not reach the original
+        fs::write(copy.join("cache/data.json"), "[]").unwrap();
+        assert_eq!(
+            fs::read_to_string(original.join("cache/data.json")).unwrap(),
+            "{}"
+        );
+
+        copy_directory(&root.join("missing"), &root.join("empty")).unwrap();
+        assert_eq!(fs::read_dir(root.join("empty")).unwrap().count(), 0);
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -1,9 +1,39 @@
-use anyhow::Result;
+use std::time::SystemTime;
+
+use anyhow::{bail, Result};
 use common::models::WidgetStoreItem;
+use reqwest::header::{ETAG, IF_NONE_MATCH};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+
+use crate::shared::persistence::Persistence;
+use crate::widgets::utils::logging::LOGGING_PREFIX;
 
 const WIDGET_LISTING_URL: &str =
     "https://raw.githubusercontent.com/eliabieri/wg_display/main/widget_store.json";
 
+/// Time after which a cached store listing is revalidated against the store
+const CACHE_TTL_SECONDS: u64 = 60 * 60;
+
+/// Store listing as last fetched from the store
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct WidgetStoreCache {
+    /// ETag of the listing, used to revalidate it cheaply
+    pub etag: Option<String>,
+    /// Unix timestamp (seconds) of the last successful fetch or revalidation
+    pub fetched_at: u64,
+    pub items: Vec<WidgetStoreItem>,
+}
+
+impl WidgetStoreCache {
+    /// Whether the cached listing can be used without asking the store
+    /// # Arguments
+    /// * `now` - The current unix timestamp in seconds
+    fn is_fresh(&self, now: u64) -> bool {
+        now.saturating_sub(self.fetched_at) < CACHE_TTL_SECONDS
+    }
+}
+
 pub struct WidgetStore {
     store_items: Vec<WidgetStoreItem>,
 }
@@ -23,17 +53,97 @@ impl WidgetStore {
         &self.store_items
     }
 
-    /// Fetch the store from the internet
+    /// Find an item in the store by its name
+    /// Use `fetch_from_store` to fetch the store before
+    /// # Arguments
+    /// * `name` - The name of the store item
+    /// # Returns
+    /// The item if the store contains one with the given name
+    pub fn find_item(&self, name: &str) -> Option<&WidgetStoreItem> {
+        self.store_items.iter().find(|item| item.name == name)
+    }
+
+    /// Fetch the store listing
+    /// The listing is cached in the database and only revalidated once the cache expired.
+    /// If the store can not be reached, a stale cached listing is used.
     /// # Returns
-    /// An error if the fetch failed
+    /// An error if the fetch failed and no cached listing is available
     pub async fn fetch_from_store(&mut self) -> Result<()> {
-        let response = reqwest::get(WIDGET_LISTING_URL).await?;
+        let cache = Persistence::global().get_widget_store_cache().ok().flatten();
+        if let Some(cache) = &cache {
+            if cache.is_fresh(now_seconds()) {
+                self.store_items = cache.items.clone();
+                return Ok(());
+            }
+        }
+
+        match WidgetStore::fetch_listing(cache.as_ref()).await {
+            Ok(fetched) => {
+                if let Err(err) = Persistence::global().save_widget_store_cache(&fetched) {
+                    log::warn!("{}: Could not cache widget store listing: {}", LOGGING_PREFIX, err);
+                }
+                self.store_items = fetched.items;
+                Ok(())
+            }
+            Err(err) => {
+                let Some(stale) = cache else {
+                    return Err(err);
+                };
+                log::warn!(
+                    "{}: Could not reach widget store, using cached listing: {}",
+                    LOGGING_PREFIX,
+                    err
+                );
+                self.store_items = stale.items;
+                Ok(())
+            }
+        }
+    }
+
+    /// Fetch the listing from the internet
+    /// Sends the ETag of the cached listing so the store can answer with `304 Not Modified`
+    /// # Arguments
+    /// * `cache` - The currently cached listing
+    /// # Returns
+    /// The fetched or revalidated listing
+    async fn fetch_listing(cache: Option<&WidgetStoreCache>) -> Result<WidgetStoreCache> {
+        let mut request = reqwest::Client::new().get(WIDGET_LISTING_URL);
+        if let Some(etag) = cache.and_then(|cache| cache.etag.as_ref()) {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        let response = request.send().await?.error_for_status()?;
+
+        if response.status() == StatusCode::NOT_MODIFIED {
+            let Some(cache) = cache else {
+                bail!("Store answered with 304 Not Modified but no listing is cached");
+            };
+            return Ok(WidgetStoreCache {
+                fetched_at: now_seconds(),
+                ..cache.clone()
+            });
+        }
+
+        let etag = response
+            .headers()
+            .get(ETAG)
+            .and_then(|etag| etag.to_str().ok())
+            .map(str::to_owned);
         let body = response.text().await?;
-        self.store_items = serde_json::from_str::<Vec<WidgetStoreItem>>(&body)?;
-        Ok(())
+        Ok(WidgetStoreCache {
+            etag,
+            fetched_at: now_seconds(),
+            items: serde_json::from_str::<Vec<WidgetStoreItem>>(&body)?,
+        })
     }
 }
 
+fn now_seconds() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .expect("Could not get system time")
+        .as_secs()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -43,11 +153,22 @@ mod tests {
         let mut store = WidgetStore::new();
         store.fetch_from_store().await.unwrap();
         assert!(!store.get_items().is_empty());
-        let reference_item = store
-            .get_items()
-            .iter()
-            .find(|item: &&WidgetStoreItem| item.name == "Time")
-            .unwrap();
+        let reference_item = store.find_item("Time").unwrap();
         assert_eq!(reference_item.description, "Display the current time");
+        assert!(store.find_item("Non existent widget").is_none());
+    }
+
+    #[test]
+    fn test_cache_freshness() {
+        let cache = WidgetStoreCache {
+            etag: None,
+            fetched_at: 1000,
+            items: vec![],
+        };
+        assert!(cache.is_fresh(1000));
+        assert!(cache.is_fresh(1000 + CACHE_TTL_SECONDS - 1));
+        assert!(!cache.is_fresh(1000 + CACHE_TTL_SECONDS));
+        // Clock went backwards
+        assert!(cache.is_fresh(0));
     }
 }
@@ -0,0 +1,208 @@
+//! Keeps the most recent log lines of every widget in memory, so they can be shown on the dashboard
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use common::models::{LogLevel, LogSource, WidgetLogEntry};
+
+use crate::widgets::utils::logging::LOGGING_PREFIX;
+
+/// Number of log lines kept per widget
+const MAX_ENTRIES: usize = 200;
+
+/// Log messages longer than this are truncated
+const MAX_MESSAGE_LENGTH: usize = 1024;
+
+lazy_static! {
+    static ref LOGS: Mutex<HashMap<String, VecDeque<WidgetLogEntry>>> = Mutex::new(HashMap::new());
+}
+
+thread_local! {
+    /// Widget whose log lines are captured on this thread instead of being kept, see [WidgetLogs::capture]
+    static CAPTURE: RefCell<Option<(String, Vec<WidgetLogEntry>)>> = const { RefCell::new(None) };
+}
+
+pub struct WidgetLogs;
+
+impl WidgetLogs {
+    /// Log a message concerning a widget
+    /// The message is forwarded to the global logger and kept in the log buffer of the widget
+    /// # Arguments
+    /// * `widget_name` - Name of the widget
+    /// * `level` - Severity of the message
+    /// * `source` - Origin of the message
+    /// * `message` - The message
+    pub fn record(widget_name: &str, level: LogLevel, source: LogSource, message: &str) {
+        let prefix = match source {
+            LogSource::Host => LOGGING_PREFIX,
+            _ => "WIDGET",
+        };
+        log::log!(to_log_level(level), "{}: {}: {}", prefix, widget_name, message);
+
+        let entry = WidgetLogEntry {
+            timestamp_ms: SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map_or(0, |now| now.as_millis() as u64),
+            level,
+            source,
+            message: truncate(message),
+        };
+        let entry = CAPTURE.with_borrow_mut(|capture| match capture {
+            Some((captured_widget, entries)) if captured_widget == widget_name => {
+                entries.push(entry);
+                None
+            }
+            _ => Some(entry),
+        });
+        let Some(entry) = entry else {
+            return;
+        };
+        let mut logs = LOGS.lock().expect("Widget logs poisoned");
+        let entries = logs.entry(widget_name.to_owned()).or_default();
+        if entries.len() == MAX_ENTRIES {
+            entries.pop_front();
+        }
+        entries.push_back(entry);
+    }
+
+    /// Get the log lines of a widget
+    /// # Arguments
+    /// * `widget_name` - Name of the widget
+    /// * `min_level` - Only lines of this or a higher severity are returned
+    /// # Returns
+    /// The log lines, oldest first
+    pub fn get(widget_name: &str, min_level: LogLevel) -> Vec<WidgetLogEntry> {
+        let logs = LOGS.lock().expect("Widget logs poisoned");
+        logs.get(widget_name)
+            .map(|entries| {
+                entries
+                    .iter()
+                    .filter(|entry| entry.level >= min_level)
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Capture the log lines of a widget while running a function on the current thread
+    /// They are returned instead of being kept, e.g. so a test run does not show up on the widget's card
+    /// # Arguments
+    /// * `widget_name` - Name of the widget
+    /// * `f` - The function to run
+    /// # Returns
+    /// The result of the function and the captured log lines, oldest first
+    pub fn capture<T>(widget_name: &str, f: impl FnOnce() -> T) -> (T, Vec<WidgetLogEntry>) {
+        /// Stops capturing even if the function panics, threads are reused (e.g. by `spawn_blocking`)
+        struct StopCapture;
+        impl Drop for StopCapture {
+            fn drop(&mut self) {
+                CAPTURE.set(None);
+            }
+        }
+
+        CAPTURE.set(Some((widget_name.to_owned(), Vec::new())));
+        let _stop = StopCapture;
+        let result = f();
+        let entries = CAPTURE
+            .take()
+            .map(|(_, entries)| entries)
+            .unwrap_or_default();
+        (result, entries)
+    }
+
+    /// Remove all log lines of a widget
+    /// # Arguments
+    /// * `widget_name` - Name of the widget
+    pub fn clear(widget_name: &str) {
+        LOGS.lock().expect("Widget logs poisoned").remove(widget_name);
+    }
+}
+
+fn to_log_level(level: LogLevel) -> log::Level {
+    match level {
+        LogLevel::Debug => log::Level::Debug,
+        LogLevel::Info => log::Level::Info,
+        LogLevel::Warn => log::Level::Warn,
+        LogLevel::Error => log::Level::Error,
+    }
+}
+
+fn truncate(message: &str) -> String {
+    if message.len() <= MAX_MESSAGE_LENGTH {
+        return message.to_owned();
+    }
+    let mut end = MAX_MESSAGE_LENGTH;
+    while !message.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}…", &message[..end])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ring_buffer() {
+        let widget = "test_ring_buffer";
+        WidgetLogs::clear(widget);
+        for i in 0..MAX_ENTRIES + 5 {
+            WidgetLogs::record(widget, LogLevel::Info, LogSource::Stdout, &i.to_string());
+        }
+        let entries = WidgetLogs::get(widget, LogLevel::Debug);
+        assert_eq!(entries.len(), MAX_ENTRIES);
+        assert_eq!(entries[0].message, "5");
+        assert!(WidgetLogs::get("other_widget", LogLevel::Debug).is_empty());
+        WidgetLogs::clear(widget);
+        assert!(WidgetLogs::get(widget, LogLevel::Debug).is_empty());
+    }
+
+    #[test]
+    fn test_level_filter() {
+        let widget = "test_level_filter";
+        WidgetLogs::record(widget, LogLevel::Debug, LogSource::Widget, "debug");
+        WidgetLogs::record(widget, LogLevel::Warn, LogSource::Stderr, "warn");
+        WidgetLogs::record(widget, LogLevel::Error, LogSource::Host, "error");
+        let messages: Vec<String> = WidgetLogs::get(widget, LogLevel::Warn)
+            .into_iter()
+            .map(|entry| entry.message)
+            .collect();
+        assert_eq!(messages, vec!["warn", "error"]);
+    }
+
+    #[test]
+    fn test_capture() {
+        let widget = "test_capture";
+        WidgetLogs::record(widget, LogLevel::Info, LogSource::Widget, "kept");
+        let (result, captured) = WidgetLogs::capture(widget, || {
+            WidgetLogs::record(widget, LogLevel::Info, LogSource::Widget, "captured");
+            WidgetLogs::record(
+                "test_capture_other",
+                LogLevel::Info,
+                LogSource::Widget,
+                "other",
+            );
+            42
+        });
+        assert_eq!(result, 42);
+        assert_eq!(captured.len(), 1);
+        assert_eq!(captured[0].message, "captured");
+        let kept: Vec<String> = WidgetLogs::get(widget, LogLevel::Debug)
+            .into_iter()
+            .map(|entry| entry.message)
+            .collect();
+        assert_eq!(kept, vec!["kept"]);
+        assert_eq!(
+            WidgetLogs::get("test_capture_other", LogLevel::Debug).len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_truncate() {
+        assert_eq!(truncate("short"), "short");
+        let long = "ä".repeat(MAX_MESSAGE_LENGTH);
+        assert!(truncate(&long).len() <= MAX_MESSAGE_LENGTH + "…".len());
+    }
+}
@@ -0,0 +1,486 @@
+//! Validation of widget configurations against the JSON Schema returned by the widget's `get-config-schema`.
+//! Supports the subset of JSON Schema widget configurations use: types, enums, required and nested properties,
+//! arrays and the length and range limits. Other keywords (e.g. `pattern` or `$ref`) are ignored,
+//! so a configuration is never rejected for something that can not be checked here.
+//! The configuration form of the dashboard is generated from the same schema and starts with its defaults.
+
+use serde_json::{Map, Value};
+
+use crate::models::ConfigFieldError;
+
+/// Keywords of a JSON Schema, used to tell a schema from the jsonform shorthand
+const SCHEMA_KEYWORDS: [&str; 5] = ["type", "properties", "enum", "items", "$schema"];
+
+/// Returns the schema in its JSON Schema form
+/// jsonform also accepts a bare map of properties as schema, which is wrapped into an object schema.
+/// # Arguments
+/// * `schema` - The schema returned by the widget
+pub fn normalize(schema: Value) -> Value {
+    let Value::Object(properties) = schema else {
+        return schema;
+    };
+    let is_shorthand = !properties.is_empty()
+        && !SCHEMA_KEYWORDS
+            .iter()
+            .any(|keyword| properties.contains_key(*keyword))
+        && properties.values().all(Value::is_object);
+    if !is_shorthand {
+        return Value::Object(properties);
+    }
+    let mut schema = Map::new();
+    schema.insert("type".to_string(), Value::from("object"));
+    schema.insert("properties".to_string(), Value::Object(properties));
+    Value::Object(schema)
+}
+
+/// Parses a configuration and validates it against a schema
+/// # Arguments
+/// * `schema` - The schema returned by the widget
+/// * `config` - The configuration as entered by the user
+/// # Returns
+/// The parsed configuration or why it does not match the schema
+pub fn validate_str(schema: &str, config: &str) -> Result<Value, Vec<ConfigFieldError>> {
+    let config: Value = serde_json::from_str(config).map_err(|err| {
+        vec![ConfigFieldError {
+            path: String::new(),
+            message: format!("Not valid JSON: {}", err),
+        }]
+    })?;
+    let schema: Value = serde_json::from_str(schema).map_err(|err| {
+        vec![ConfigFieldError {
+            path: String::new(),
+            message: format!("The widget returned an invalid schema: {}", err),
+        }]
+    })?;
+    let errors = validate(&normalize(schema), &config);
+    if errors.is_empty() {
+        Ok(config)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Returns the value a new field starts with
+/// The schema's `default` if it has one, otherwise an empty value of its type.
+/// Objects start with their properties that have a default or are required.
+/// # Arguments
+/// * `schema` - The schema of the field in its JSON Schema form
+pub fn default_value(schema: &Value) -> Value {
+    if let Some(default) = schema.get("default") {
+        return default.clone();
+    }
+    if let Some(first) = schema
+        .get("enum")
+        .and_then(Value::as_array)
+        .and_then(|allowed| allowed.first())
+    {
+        return first.clone();
+    }
+    match type_of(schema) {
+        "boolean" => Value::Bool(false),
+        "integer" | "number" => Value::from(0),
+        "array" => Value::Array(vec![]),
+        "object" => fill_defaults(schema, &Value::Object(Map::new())),
+        _ => Value::String(String::new()),
+    }
+}
+
+/// Adds the defaults of the schema to the properties missing in a configuration
+/// Configurations stored before the user saved the form, e.g. `{}` after installing, start with the defaults.
+/// # Arguments
+/// * `schema` - The schema in its JSON Schema form
+/// * `value` - The stored configuration
+pub fn fill_defaults(schema: &Value, value: &Value) -> Value {
+    let (Some(properties), Value::Object(object)) =
+        (schema.get("properties").and_then(Value::as_object), value)
+    else {
+        return value.clone();
+    };
+    let required = required_properties(schema);
+    let mut filled = object.clone();
+    for (name, property) in properties {
+        match object.get(name) {
+            Some(value) => {
+                filled.insert(name.clone(), fill_defaults(property, value));
+            }
+            None if property.get("default").is_some() || required.contains(&name.as_str()) => {
+                filled.insert(name.clone(), default_value(property));
+            }
+            None => {}
+        }
+    }
+    Value::Object(filled)
+}
+
+/// Returns the JSON Schema type of a field
+/// The first type other than `null` if there are several, inferred from the other keywords if there is none
+pub fn type_of(schema: &Value) -> &str {
+    match schema.get("type") {
+        Some(Value::String(name)) => name,
+        Some(Value::Array(names)) => names
+            .iter()
+            .filter_map(Value::as_str)
+            .find(|name| *name != "null")
+            .unwrap_or("null"),
+        _ if schema.get("properties").is_some() => "object",
+        _ if schema.get("items").is_some() => "array",
+        _ => "string",
+    }
+}
+
+/// Returns the names of the required properties of an object schema
+/// Draft 3 and jsonform mark required properties on the property itself, later drafts list them on the object.
+pub fn required_properties(schema: &Value) -> Vec<&str> {
+    schema.as_object().map(required_in).unwrap_or_default()
+}
+
+fn required_in(schema: &Map<String, Value>) -> Vec<&str> {
+    let mut required: Vec<&str> = match schema.get("required") {
+        Some(Value::Array(names)) => names.iter().filter_map(Value::as_str).collect(),
+        _ => vec![],
+    };
+    if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+        required.extend(
+            properties
+                .iter()
+                .filter(|(_, property)| property.get("required") == Some(&Value::Bool(true)))
+                .map(|(name, _)| name.as_str()),
+        );
+    }
+    required
+}
+
+/// Validates a value against a schema
+/// # Arguments
+/// * `schema` - The schema in its JSON Schema form, see [normalize]
+/// * `value` - The value to validate
+/// # Returns
+/// All values not matching the schema, empty if the value is valid
+pub fn validate(schema: &Value, value: &Value) -> Vec<ConfigFieldError> {
+    let mut errors = Vec::new();
+    validate_at(schema, value, "", &mut errors);
+    errors
+}
+
+fn validate_at(schema: &Value, value: &Value, path: &str, errors: &mut Vec<ConfigFieldError>) {
+    let Value::Object(schema) = schema else {
+        // `true`, `false` and malformed schemas: `false` rejects everything
+        if schema == &Value::Bool(false) {
+            push(errors, path, "No value is allowed here".to_string());
+        }
+        return;
+    };
+    let mut error = |message: String| push(errors, path, message);
+
+    if let Some(expected) = schema.get("type") {
+        let types: Vec<&str> = match expected {
+            Value::String(name) => vec![name.as_str()],
+            Value::Array(names) => names.iter().filter_map(Value::as_str).collect(),
+            _ => vec![],
+        };
+        if !types.is_empty() && !types.iter().any(|name| has_type(value, name)) {
+            error(format!("Must be {}", types.join(" or ")));
+            // The other keywords describe values of the expected type
+            return;
+        }
+    }
+    let allowed = schema.get("enum").and_then(Value::as_array);
+    if let Some(allowed) = allowed.filter(|allowed| !allowed.contains(value)) {
+        let allowed: Vec<String> = allowed.iter().map(Value::to_string).collect();
+        error(format!("Must be one of {}", allowed.join(", ")));
+    }
+    if let Some(expected) = schema.get("const").filter(|expected| *expected != value) {
+        error(format!("Must be {}", expected));
+    }
+
+    match value {
+        Value::String(text) => {
+            let length = text.chars().count() as u64;
+            if let Some(min) = limit(schema, "minLength").filter(|min| length < *min as u64) {
+                error(format!("Must be at least {} characters long", min));
+            }
+            if let Some(max) = limit(schema, "maxLength").filter(|max| length > *max as u64) {
+                error(format!("Must be at most {} characters long", max));
+            }
+        }
+        Value::Number(number) => {
+            let number = number.as_f64().unwrap_or_default();
+            validate_range(schema, number, &mut error);
+        }
+        Value::Array(items) => {
+            let count = items.len() as u64;
+            if let Some(min) = limit(schema, "minItems").filter(|min| count < *min as u64) {
+                error(format!("Must have at least {} entries", min));
+            }
+            if let Some(max) = limit(schema, "maxItems").filter(|max| count > *max as u64) {
+                error(format!("Must have at most {} entries", max));
+            }
+            let unique = schema.get("uniqueItems") == Some(&Value::Bool(true));
+            if unique
+                && items
+                    .iter()
+                    .enumerate()
+                    .any(|(i, item)| items[..i].contains(item))
+            {
+                error("Entries must be unique".to_string());
+            }
+            match schema.get("items") {
+                Some(Value::Array(schemas)) => {
+                    for (index, (item, schema)) in items.iter().zip(schemas).enumerate() {
+                        validate_at(schema, item, &join_path(path, &index.to_string()), errors);
+                    }
+                }
+                Some(schema) => {
+                    for (index, item) in items.iter().enumerate() {
+                        validate_at(schema, item, &join_path(path, &index.to_string()), errors);
+                    }
+                }
+                None => {}
+            }
+        }
+        Value::Object(object) => validate_object(schema, object, path, errors),
+        Value::Bool(_) | Value::Null => {}
+    }
+}
+
+fn validate_object(
+    schema: &Map<String, Value>,
+    object: &Map<String, Value>,
+    path: &str,
+    errors: &mut Vec<ConfigFieldError>,
+) {
+    let empty = Map::new();
+    let properties = match schema.get("properties") {
+        Some(Value::Object(properties)) => properties,
+        _ => &empty,
+    };
+
+    for name in required_in(schema) {
+        if !object.contains_key(name) {
+            push(errors, &join_path(path, name), "Is required".to_string());
+        }
+    }
+
+    for (name, value) in object {
+        let path = join_path(path, name);
+        match (properties.get(name), schema.get("additionalProperties")) {
+            (Some(property), _) => validate_at(property, value, &path, errors),
+            (None, Some(Value::Bool(false))) => {
+                push(errors, &path, "Is not a known setting".to_string())
+            }
+            (None, Some(additional)) => validate_at(additional, value, &path, errors),
+            (None, None) => {}
+        }
+    }
+}
+
+fn validate_range(schema: &Map<String, Value>, number: f64, error: &mut impl FnMut(String)) {
+    let bound = |keyword: &str| schema.get(keyword).and_then(Value::as_f64);
+    // Draft 4 turns `minimum` and `maximum` exclusive with a boolean, later drafts use a number
+    let exclusive = |keyword: &str| schema.get(keyword) == Some(&Value::Bool(true));
+
+    if let Some(min) = bound("minimum") {
+        if exclusive("exclusiveMinimum") && number <= min {
+            error(format!("Must be greater than {}", min));
+        } else if number < min {
+            error(format!("Must be at least {}", min));
+        }
+    }
+    if let Some(min) = bound("exclusiveMinimum").filter(|min| number <= *min) {
+        error(format!("Must be greater than {}", min));
+    }
+    if let Some(max) = bound("maximum") {
+        if exclusive("exclusiveMaximum") && number >= max {
+            error(format!("Must be less than {}", max));
+        } else if number > max {
+            error(format!("Must be at most {}", max));
+        }
+    }
+    if let Some(max) = bound("exclusiveMaximum").filter(|max| number >= *max) {
+        error(format!("Must be less than {}", max));
+    }
+}
+
+/// Whether the value is of the JSON Schema type, unknown types match everything
+fn has_type(value: &Value, name: &str) -> bool {
+    match name {
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.as_f64().is_some_and(|number| number.fract() == 0.0),
+        "boolean" => value.is_boolean(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+fn limit(schema: &Map<String, Value>, keyword: &str) -> Option<f64> {
+    schema.get(keyword).and_then(Value::as_f64)
+}
+
+/// Appends a property name or array index to a dotted path, see [ConfigFieldError::path]
+pub fn join_path(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+fn push(errors: &mut Vec<ConfigFieldError>, path: &str, message: String) {
+    errors.push(ConfigFieldError {
+        path: path.to_string(),
+        message,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn paths(errors: &[ConfigFieldError]) -> Vec<&str> {
+        errors.iter().map(|error| error.path.as_str()).collect()
+    }
+
+    #[test]
+    fn test_validate() {
+        let schema = json!({
+            "type": "object",
+            "required": ["from"],
+            "additionalProperties": false,
+            "properties": {
+                "from": {"type": "string", "minLength": 1},
+                "limit": {"type": "integer", "minimum": 1, "maximum": 10},
+                "unit": {"type": "string", "enum": ["metric", "imperial"]},
+                "lines": {
+                    "type": "array",
+                    "maxItems": 2,
+                    "items": {
+                        "type": "object",
+                        "properties": {"number": {"type": "string", "required": true}}
+                    }
+                }
+            }
+        });
+        let valid =
+            json!({"from": "Bern", "limit": 3, "unit": "metric", "lines": [{"number": "10"}]});
+        assert_eq!(validate(&schema, &valid), vec![]);
+
+        let invalid = json!({
+            "from": "",
+            "limit": 2.5,
+            "unit": "kelvin",
+            "lines": [{"number": 10}, {}, {"number": "12"}],
+            "color": "red"
+        });
+        let errors = validate(&schema, &invalid);
+        assert_eq!(
+            paths(&errors),
+            vec![
+                "color",
+                "from",
+                "limit",
+                "lines",
+                "lines.0.number",
+                "lines.1.number",
+                "unit"
+            ]
+        );
+        assert_eq!(errors[2].message, "Must be integer");
+        assert_eq!(errors[5].message, "Is required");
+
+        let errors = validate(&schema, &json!({}));
+        assert_eq!(paths(&errors), vec!["from"]);
+        let errors = validate(&schema, &json!([]));
+        assert_eq!(errors[0].message, "Must be object");
+    }
+
+    #[test]
+    fn test_validate_range() {
+        let draft4 = json!({"type": "number", "minimum": 0, "exclusiveMinimum": true});
+        assert_eq!(
+            validate(&draft4, &json!(0))[0].message,
+            "Must be greater than 0"
+        );
+        assert_eq!(validate(&draft4, &json!(0.5)), vec![]);
+
+        let draft6 = json!({"type": "number", "exclusiveMaximum": 1});
+        assert_eq!(
+            validate(&draft6, &json!(1))[0].message,
+            "Must be less than 1"
+        );
+        assert_eq!(validate(&draft6, &json!(0.5)), vec![]);
+    }
+
+    #[test]
+    fn test_normalize_jsonform_shorthand() {
+        let shorthand = json!({
+            "from": {"type": "string", "required": true},
+            "to": {"type": "string"}
+        });
+        let schema = normalize(shorthand);
+        assert_eq!(schema["type"], "object");
+        assert_eq!(
+            paths(&validate(&schema, &json!({"to": "Bern"}))),
+            vec!["from"]
+        );
+
+        let full = json!({"type": "object", "properties": {}});
+        assert_eq!(normalize(full.clone()), full);
+    }
+
+    #[test]
+    fn test_fill_defaults() {
+        let schema = json!({
+            "type": "object",
+            "required": ["from"],
+            "properties": {
+                "from": {"type": "string"},
+                "limit": {"type": "integer", "default": 3},
+                "unit": {"type": "string", "enum": ["metric", "imperial"]},
+                "display": {
+                    "type": "object",
+                    "properties": {"compact": {"type": "boolean", "default": true}}
+                }
+            }
+        });
+        assert_eq!(
+            fill_defaults(&schema, &json!({})),
+            json!({"from": "", "limit": 3})
+        );
+        assert_eq!(
+            fill_defaults(&schema, &json!({"limit": 5, "display": {}})),
+            json!({"from": "", "limit": 5, "display": {"compact": true}})
+        );
+        assert_eq!(
+            default_value(&schema["properties"]["unit"]),
+            json!("metric")
+        );
+        assert_eq!(
+            default_value(&schema["properties"]["display"]),
+            json!({"compact": true})
+        );
+        assert_eq!(type_of(&json!({"type": ["null", "number"]})), "number");
+        assert_eq!(type_of(&json!({"items": {}})), "array");
+    }
+
+    #[test]
+    fn test_validate_str() {
+        let schema = r#"{"type": "object", "properties": {"from": {"type": "string"}}}"#;
+        assert_eq!(
+            validate_str(schema, r#"{"from": "Bern"}"#).unwrap()["from"],
+            "Bern"
+        );
+        assert!(
+            validate_str(schema, "{\"from\":").unwrap_err()[0]
+                .message
+                .starts_with("Not valid JSON")
+        );
+        assert_eq!(
+            paths(&validate_str(schema, r#"{"from": 1}"#).unwrap_err()),
+            vec!["from"]
+        );
+    }
+}
@@ -1,4 +1,5 @@
 //! Common parts used by both the front- and backend.
 #[forbid(unsafe_code)]
 pub mod models;
+pub mod config_schema;
 pub mod widget_store_item;
@@ -12,6 +12,81 @@ pub struct WidgetInstallationData {
     pub description: String,
     pub version: String,
     pub json_config: String,
+    /// Permissions the user granted to the widget at install time
+    #[serde(default)]
+    pub permissions: WidgetPermissions,
+    /// The widget was installed before widgets had to request permissions.
+    /// It runs without any until the user approved the permissions it requests.
+    #[serde(default)]
+    pub approval_required: bool,
+    /// The widget was compiled by a previous version of WG Display and could not be recompiled,
+    /// as neither its component nor its source provides it anymore. It is not displayed until it is installed again.
+    #[serde(default)]
+    pub reinstall_required: bool,
+    /// Optional parts of WASI the user granted to the widget
+    #[serde(default)]
+    pub wasi_grants: WasiGrants,
+    /// Limits the HTTP requests of the widget, e.g. to stay within the quota of an API.
+    /// Responses served from the cache do not count against the limit.
+    #[serde(default)]
+    pub http_rate_limit: Option<RateLimit>,
+    /// Where the widget was installed from, used to install it again on another display.
+    /// `None` for widgets installed before the source was recorded.
+    #[serde(default)]
+    pub source: Option<InstallAction>,
+}
+
+/// Allows `requests` requests per `per_seconds` seconds
+#[derive(Deserialize, Serialize, Clone, PartialEq, Debug)]
+pub struct RateLimit {
+    pub requests: u32,
+    pub per_seconds: u32,
+}
+
+/// Optional parts of WASI the user can grant to a widget.
+/// Without any grants, widgets have no filesystem, socket or environment access.
+#[derive(Deserialize, Serialize, Clone, PartialEq, Default, Debug)]
+pub struct WasiGrants {
+    /// Give the widget a private directory that persists between runs, mounted at `/scratch`
+    #[serde(default)]
+    pub scratch_directory: bool,
+}
+
+/// HTTP methods a widget can be granted
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum HttpMethod {
+    Get,
+    Head,
+    Post,
+    Put,
+    Delete,
+}
+
+/// HTTP access granted to a widget
+#[derive(Deserialize, Serialize, Clone, PartialEq, Default, Debug)]
+pub struct HttpPermissions {
+    /// Hosts the widget may send requests to.
+    /// An entry of the form `*.example.com` matches all subdomains of `example.com`.
+    pub hosts: Vec<String>,
+    pub methods: Vec<HttpMethod>,
+    /// Maximum size of request and response bodies in bytes
+    pub max_body_size: u64,
+}
+
+/// Capabilities of the host a widget is allowed to use.
+/// Widgets declare the capabilities they need in their manifest, the user approves them at install time.
+#[derive(Deserialize, Serialize, Clone, PartialEq, Default, Debug)]
+pub struct WidgetPermissions {
+    /// HTTP access. `None` if the widget may not send any requests
+    #[serde(default)]
+    pub http: Option<HttpPermissions>,
+}
+
+impl WidgetPermissions {
+    /// Whether no capabilities are granted at all
+    pub fn is_empty(&self) -> bool {
+        self.http.is_none()
+    }
 }
 
 /// The system configuration.
@@ -22,21 +97,328 @@ pub struct SystemConfiguration {
     pub background_color: String,
     #[serde(default)]
     pub widgets: Vec<WidgetInstallationData>,
+    /// Disables the cache for HTTP responses shared by all widgets
+    #[serde(default)]
+    pub disable_http_cache: bool,
+    /// Location of the display, used by widgets such as weather or transit
+    #[serde(default)]
+    pub location: Option<Location>,
+    /// IANA name of the timezone of the display (e.g. `Europe/Zurich`).
+    /// The timezone of the host system is used if not set.
+    #[serde(default)]
+    pub timezone: Option<String>,
+}
+
+/// Geographic location of the display
+#[derive(Deserialize, Serialize, Clone, PartialEq, Default, Debug)]
+pub struct Location {
+    pub latitude: f64,
+    pub longitude: f64,
+    /// Human readable name of the place (e.g. `Bern`)
+    #[serde(default)]
+    pub locality: String,
+}
+
+impl SystemConfiguration {
+    /// Set the system settings of a configuration edited on the dashboard
+    /// The widgets are kept: their settings are saved through their own endpoints,
+    /// so a dashboard that still shows an outdated configuration can not revert them.
+    /// # Arguments
+    /// * `edited` - The edited configuration
+    pub fn set_system_settings(&mut self, edited: SystemConfiguration) {
+        let SystemConfiguration {
+            background_color,
+            widgets: _,
+            disable_http_cache,
+            location,
+            timezone,
+        } = edited;
+        self.background_color = background_color;
+        self.disable_http_cache = disable_http_cache;
+        self.location = location;
+        self.timezone = timezone;
+    }
+}
+
+impl Location {
+    /// Check whether the coordinates are within their valid ranges
+    pub fn is_valid(&self) -> bool {
+        (-90.0..=90.0).contains(&self.latitude) && (-180.0..=180.0).contains(&self.longitude)
+    }
 }
 
 /// Stores the data needed for a widget installation
-#[derive(Deserialize, Serialize, Clone)]
+#[derive(Deserialize, Serialize, Clone, PartialEq, Debug)]
 pub enum InstallAction {
     FromUrl(String),
     FromStoreItemName(String),
 }
 
+/// Request to install a widget
+#[derive(Deserialize, Serialize, Clone, PartialEq, Debug)]
+pub struct InstallRequest {
+    pub action: InstallAction,
+    /// Permissions the user approved.
+    /// The installation only completes if they match the permissions requested by the widget.
+    #[serde(default)]
+    pub granted_permissions: Option<WidgetPermissions>,
+}
+
+/// Outcome of an install request
+#[derive(Deserialize, Serialize, Clone, PartialEq, Debug)]
+pub enum InstallResponse {
+    Installed,
+    /// The widget requests permissions that were not approved yet.
+    /// Repeat the request with these permissions granted to complete the installation.
+    ApprovalRequired {
+        widget_name: String,
+        permissions: WidgetPermissions,
+    },
+}
+
+/// A saved state of the system configuration
+#[derive(Deserialize, Serialize, Clone, PartialEq, Debug)]
+pub struct ConfigRevision {
+    pub id: u64,
+    /// Milliseconds since the UNIX epoch
+    pub timestamp_ms: u64,
+}
+
+/// A value that differs between two configurations
+#[derive(Deserialize, Serialize, Clone, PartialEq, Debug)]
+pub struct ConfigChange {
+    /// Dotted path of the value, e.g. `widgets.weather.json_config.city`
+    pub path: String,
+    /// The old value as JSON, `None` if it was added
+    pub old: Option<String>,
+    /// The new value as JSON, `None` if it was removed
+    pub new: Option<String>,
+}
+
+/// A widget of an imported bundle that was not installed
+#[derive(Deserialize, Serialize, Clone, PartialEq, Debug)]
+pub enum ImportConflict {
+    /// A widget with the same name is installed already, it was kept as it is
+    AlreadyInstalled {
+        widget_name: String,
+        installed_version: String,
+    },
+    /// The widget was granted permissions on the exporting display.
+    /// It is only installed once the user approves them on this display.
+    ApprovalRequired {
+        widget_name: String,
+        permissions: WidgetPermissions,
+    },
+    /// The widget requests other permissions than granted on the exporting display.
+    /// Install it manually to approve them.
+    PermissionsChanged {
+        widget_name: String,
+        permissions: WidgetPermissions,
+    },
+    /// The bundle neither embeds the widget nor knows where it was installed from
+    NoSource { widget_name: String },
+    /// Downloading or installing the widget failed
+    Failed { widget_name: String, error: String },
+}
+
+/// Outcome of importing a bundle
+#[derive(Deserialize, Serialize, Clone, PartialEq, Default, Debug)]
+pub struct ImportReport {
+    /// Widgets that were installed and configured
+    pub installed: Vec<String>,
+    pub conflicts: Vec<ImportConflict>,
+    /// Why the configuration of an installed widget was not restored,
+    /// e.g. as it does not match the version installed from the store
+    pub rejected_configs: Vec<String>,
+}
+
+/// Severity of a widget log entry, ordered from least to most severe
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl LogLevel {
+    pub const ALL: [LogLevel; 4] = [
+        LogLevel::Debug,
+        LogLevel::Info,
+        LogLevel::Warn,
+        LogLevel::Error,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LogLevel::Debug => "debug",
+            LogLevel::Info => "info",
+            LogLevel::Warn => "warn",
+            LogLevel::Error => "error",
+        }
+    }
+}
+
+impl std::str::FromStr for LogLevel {
+    type Err = String;
+
+    fn from_str(level: &str) -> Result<Self, Self::Err> {
+        LogLevel::ALL
+            .into_iter()
+            .find(|candidate| candidate.as_str().eq_ignore_ascii_case(level))
+            .ok_or_else(|| format!("Unknown log level {}", level))
+    }
+}
+
+/// Origin of a widget log entry
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum LogSource {
+    /// Logged by the widget through the logging interface
+    Widget,
+    /// Written by the widget to stdout
+    Stdout,
+    /// Written by the widget to stderr
+    Stderr,
+    /// Logged by the host about the widget (e.g. failed runs or denied requests)
+    Host,
+}
+
+/// A log line of a widget
+#[derive(Deserialize, Serialize, Clone, PartialEq, Debug)]
+pub struct WidgetLogEntry {
+    /// Milliseconds since the UNIX epoch
+    pub timestamp_ms: u64,
+    pub level: LogLevel,
+    pub source: LogSource,
+    pub message: String,
+}
+
+/// Health of a widget, tracked over its runs since the display was started
+#[derive(Deserialize, Serialize, Clone, PartialEq, Default, Debug)]
+pub struct WidgetHealth {
+    /// Milliseconds since the UNIX epoch of the last successful run
+    pub last_success_ms: Option<u64>,
+    /// Milliseconds since the UNIX epoch of the last failed run
+    pub last_failure_ms: Option<u64>,
+    /// Number of failed runs since the last successful one
+    pub consecutive_failures: u32,
+    pub last_error: Option<String>,
+    /// Number of runs, successful or not
+    pub runs: u64,
+    /// Number of runs the widget was called in, runs that failed before (e.g. while loading it) are not timed
+    #[serde(default)]
+    pub timed_runs: u64,
+    /// Average duration of the timed runs
+    pub average_run_ms: f64,
+    /// Milliseconds the next run is delayed by because of failures
+    pub backoff_ms: u64,
+}
+
+impl WidgetHealth {
+    /// A widget is healthy if its last run succeeded
+    pub fn is_healthy(&self) -> bool {
+        self.consecutive_failures == 0
+    }
+}
+
+/// A stored value that could not be decoded and was moved out of the way
+#[derive(Deserialize, Serialize, Clone, PartialEq, Debug)]
+pub struct QuarantinedEntry {
+    /// Key the value was stored under
+    pub key: String,
+    /// Why the value could not be decoded
+    pub reason: String,
+    /// Milliseconds since the UNIX epoch
+    pub quarantined_at_ms: u64,
+    /// Size of the value in bytes
+    pub size: u64,
+}
+
+/// State of the configuration file the display is provisioned with
+#[derive(Deserialize, Serialize, Clone, PartialEq, Default, Debug)]
+pub struct ConfigFileStatus {
+    pub path: String,
+    /// Dotted paths of the values set by the file, e.g. `widgets.weather.json_config`.
+    /// Changing them on the dashboard has no effect, the file takes precedence.
+    pub managed: Vec<String>,
+    /// Why the file could not be applied. The last valid version of the file stays applied.
+    pub errors: Vec<String>,
+    /// Widget configurations of the file that do not match the widget's configuration schema.
+    /// The widgets keep the configuration edited on the dashboard.
+    pub rejected_configs: Vec<String>,
+    /// Widgets configured in the file that are not installed
+    pub not_installed: Vec<String>,
+    /// Milliseconds since the UNIX epoch when the file was last read
+    pub loaded_at_ms: u64,
+}
+
+/// A value of a widget configuration that does not match the widget's configuration schema
+#[derive(Deserialize, Serialize, Clone, PartialEq, Debug)]
+pub struct ConfigFieldError {
+    /// Dotted path of the value, e.g. `stations.0.name`. Empty for the configuration itself.
+    pub path: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for ConfigFieldError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+/// Outcome of running a widget once with a proposed configuration
+#[derive(Deserialize, Serialize, Clone, PartialEq, Debug)]
+pub struct TestRunReport {
+    /// What the widget would display, `None` if the run failed
+    pub output: Option<String>,
+    /// Why the run failed
+    pub error: Option<String>,
+    /// Log lines written during the run, they are not added to the logs of the widget
+    pub logs: Vec<WidgetLogEntry>,
+    pub duration_ms: u64,
+}
+
 /// Represents the information associated with a widget in the store.
-#[derive(Debug, serde::Deserialize, serde::Serialize)]
+#[derive(Debug, Clone, PartialEq, Default, serde::Deserialize, serde::Serialize)]
 pub struct WidgetStoreItem {
     pub name: String,
     pub description: String,
     pub repository: String,
+    /// Categories the widget belongs to (e.g. "transport", "weather")
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub author: Option<String>,
+    /// SPDX license identifier
+    #[serde(default)]
+    pub license: Option<String>,
+    #[serde(default)]
+    pub homepage: Option<String>,
+    #[serde(default)]
+    pub icon_url: Option<String>,
+    #[serde(default)]
+    pub screenshot_url: Option<String>,
+    /// Minimum version of WG Display the widget runs on
+    #[serde(default)]
+    pub min_host_version: Option<String>,
+    /// Capabilities the widget declares to use.
+    /// `None` if the store entry does not declare any.
+    #[serde(default)]
+    pub permissions: Option<DeclaredPermissions>,
+}
+
+/// Capabilities a widget declares to use
+#[derive(Debug, Clone, PartialEq, Default, serde::Deserialize, serde::Serialize)]
+pub struct DeclaredPermissions {
+    /// Hosts the widget sends HTTP requests to
+    #[serde(default)]
+    pub http_hosts: Vec<String>,
 }
 
 fn persist_system_config(config: SystemConfiguration) {
@@ -52,24 +434,64 @@ fn persist_system_config(config: SystemConfiguration) {
 }
 
 /// Actions that can be dispatched to the system configuration reducer.
-/// The reducer is responsible for updating and persisting the system configuration state.
+/// The reducer is responsible for updating the system configuration state and persisting the system settings.
+/// Actions on widgets only update the state, the widgets are changed through their own endpoints.
 #[derive(PartialEq)]
 pub enum SystemConfigurationAction {
     SetInitialConfig(SystemConfiguration),
     SetBackgroundColor(String),
+    SetLocation(Option<Location>),
+    SetTimezone(Option<String>),
+    SetDisableHttpCache(bool),
     AddWidget(WidgetInstallationData),
+    /// Grant or revoke the scratch directory of a widget
+    SetScratchDirectory {
+        widget_name: String,
+        enabled: bool,
+    },
+    /// Set or remove the limit of the HTTP requests of a widget
+    SetHttpRateLimit {
+        widget_name: String,
+        rate_limit: Option<RateLimit>,
+    },
+    /// The user approved the permissions a widget requests
+    ApprovePermissions {
+        widget_name: String,
+        permissions: WidgetPermissions,
+    },
 }
 
 impl Reducible for SystemConfiguration {
     type Action = SystemConfigurationAction;
 
     fn reduce(self: Rc<Self>, action: Self::Action) -> Rc<Self> {
+        // Widgets are installed and their settings saved through their own endpoints,
+        // only changed system settings are persisted here
+        let persist = matches!(
+            action,
+            SystemConfigurationAction::SetBackgroundColor(_)
+                | SystemConfigurationAction::SetLocation(_)
+                | SystemConfigurationAction::SetTimezone(_)
+                | SystemConfigurationAction::SetDisableHttpCache(_)
+        );
         let config = match action {
             SystemConfigurationAction::SetInitialConfig(new_config) => new_config,
             SystemConfigurationAction::SetBackgroundColor(background_color) => Self {
                 background_color,
                 ..(*self).clone()
             },
+            SystemConfigurationAction::SetLocation(location) => Self {
+                location,
+                ..(*self).clone()
+            },
+            SystemConfigurationAction::SetTimezone(timezone) => Self {
+                timezone,
+                ..(*self).clone()
+            },
+            SystemConfigurationAction::SetDisableHttpCache(disable_http_cache) => Self {
+                disable_http_cache,
+                ..(*self).clone()
+            },
             SystemConfigurationAction::AddWidget(new_config) => Self {
                 widgets: {
                     let mut widget_config = self.widgets.clone();
@@ -78,8 +500,105 @@ impl Reducible for SystemConfiguration {
                 },
                 ..(*self).clone()
             },
+            SystemConfigurationAction::SetScratchDirectory {
+                widget_name,
+                enabled,
+            } => Self {
+                widgets: self
+                    .widgets
+                    .iter()
+                    .cloned()
+                    .map(|mut widget| {
+                        if widget.name == widget_name {
+                            widget.wasi_grants.scratch_directory = enabled;
+                        }
+                        widget
+                    })
+                    .collect(),
+                ..(*self).clone()
+            },
+            SystemConfigurationAction::SetHttpRateLimit {
+                widget_name,
+                rate_limit,
+            } => Self {
+                widgets: self
+                    .widgets
+                    .iter()
+                    .cloned()
+                    .map(|mut widget| {
+                        if widget.name == widget_name {
+                            widget.http_rate_limit = rate_limit.clone();
+                        }
+                        widget
+                    })
+                    .collect(),
+                ..(*self).clone()
+            },
+            SystemConfigurationAction::ApprovePermissions {
+                widget_name,
+                permissions,
+            } => Self {
+                widgets: self
+                    .widgets
+                    .iter()
+                    .cloned()
+                    .map(|mut widget| {
+                        if widget.name == widget_name {
+                            widget.permissions = permissions.clone();
+                            widget.approval_required = false;
+                        }
+                        widget
+                    })
+                    .collect(),
+                ..(*self).clone()
+            },
         };
-        persist_system_config(config.clone());
+        if persist {
+            persist_system_config(config.clone());
+        }
         config.into()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stale_dashboard_does_not_revert_widgets() {
+        let saved_widget = WidgetInstallationData {
+            name: "weather".to_string(),
+            json_config: "{\"city\":\"Thun\"}".to_string(),
+            wasi_grants: WasiGrants {
+                scratch_directory: true,
+            },
+            ..Default::default()
+        };
+        let mut current = SystemConfiguration {
+            background_color: "#000000".to_string(),
+            widgets: vec![saved_widget.clone()],
+            ..Default::default()
+        };
+        // A dashboard loaded before the widget was configured and before the clock was installed
+        let stale = SystemConfiguration {
+            background_color: "#FFFFFF".to_string(),
+            widgets: vec![WidgetInstallationData {
+                name: "weather".to_string(),
+                json_config: "{\"city\":\"Bern\"}".to_string(),
+                ..Default::default()
+            }],
+            timezone: Some("Europe/Zurich".to_string()),
+            ..Default::default()
+        };
+        current.widgets.push(WidgetInstallationData {
+            name: "clock".to_string(),
+            ..Default::default()
+        });
+
+        current.set_system_settings(stale);
+        assert_eq!(current.background_color, "#FFFFFF");
+        assert_eq!(current.timezone.as_deref(), Some("Europe/Zurich"));
+        assert_eq!(current.widgets.len(), 2);
+        assert_eq!(current.widgets[0], saved_widget);
+    }
+}
@@ -6,6 +6,7 @@ impl WidgetStoreItem {
             name,
             description,
             repository,
+            ..Default::default()
         }
     }
 
@@ -20,6 +21,53 @@ impl WidgetStoreItem {
         }
         format!("{}/{}", self.repository, SUFFIX)
     }
+
+    /// Check whether the item matches a search query
+    /// The query is matched case insensitively against name, description, author and tags
+    /// # Arguments
+    /// * `query` - The search query. An empty query matches every item
+    /// # Returns
+    /// True if the item matches the query
+    pub fn matches_search(&self, query: &str) -> bool {
+        let query = query.trim().to_lowercase();
+        if query.is_empty() {
+            return true;
+        }
+        let contains = |text: &str| text.to_lowercase().contains(&query);
+        contains(&self.name)
+            || contains(&self.description)
+            || self.author.as_deref().is_some_and(contains)
+            || self.tags.iter().any(|tag| contains(tag))
+    }
+
+    /// Check whether the item is tagged with the given tag (case insensitive)
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+    }
+
+    /// Check whether the widget can run on the given host version
+    /// # Arguments
+    /// * `host_version` - Version of the WG Display application (e.g. "2.0.2")
+    /// # Returns
+    /// True if the widget declares no minimum host version or the host is at least that version
+    pub fn supports_host_version(&self, host_version: &str) -> bool {
+        let Some(min_host_version) = &self.min_host_version else {
+            return true;
+        };
+        parse_version(host_version) >= parse_version(min_host_version)
+    }
+}
+
+/// Parses a dotted version string into its numeric components
+/// A leading `v` and pre-release or build suffixes are ignored, missing components count as 0
+fn parse_version(version: &str) -> [u64; 3] {
+    let version = version.trim().trim_start_matches('v');
+    let version = version.split(['-', '+']).next().unwrap_or_default();
+    let mut parsed = [0; 3];
+    for (component, part) in parsed.iter_mut().zip(version.split('.')) {
+        *component = part.parse().unwrap_or(0);
+    }
+    parsed
 }
 
 #[cfg(test)]
@@ -38,4 +86,37 @@ mod tests {
                 .to_string();
         assert_eq!(item.get_download_url(), expected_url);
     }
+
+    #[test]
+    fn test_matches_search() {
+        let item = WidgetStoreItem {
+            author: Some("Elia".to_string()),
+            tags: vec!["transport".to_string()],
+            ..WidgetStoreItem::new(
+                "Public Transport".to_string(),
+                "Displays the next departures".to_string(),
+                "https://github.com/myusername/mywidget".to_string(),
+            )
+        };
+        assert!(item.matches_search(""));
+        assert!(item.matches_search("public"));
+        assert!(item.matches_search("DEPARTURES"));
+        assert!(item.matches_search("elia"));
+        assert!(item.matches_search("transp"));
+        assert!(!item.matches_search("weather"));
+        assert!(item.has_tag("Transport"));
+        assert!(!item.has_tag("weather"));
+    }
+
+    #[test]
+    fn test_supports_host_version() {
+        let mut item = WidgetStoreItem::default();
+        assert!(item.supports_host_version("2.0.2"));
+
+        item.min_host_version = Some("2.1".to_string());
+        assert!(!item.supports_host_version("2.0.2"));
+        assert!(item.supports_host_version("2.1.0"));
+        assert!(item.supports_host_version("v2.10.0"));
+        assert!(item.supports_host_version("3.0.0-beta"));
+    }
 }
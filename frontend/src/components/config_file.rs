@@ -0,0 +1,78 @@
+//! Shows that the display is configured by a file and which values the file controls.
+//! Values set by the file cannot be changed on the dashboard, the file takes precedence.
+
+use common::models::ConfigFileStatus;
+use gloo_net::http::Request;
+use yew::prelude::*;
+use yew_feather::{AlertTriangle, FileText};
+
+use crate::components::widget_logs::format_time;
+
+#[function_component(ConfigFileComponent)]
+pub fn config_file_component() -> Html {
+    let status = use_state(|| None as Option<ConfigFileStatus>);
+
+    {
+        let status = status.clone();
+        use_effect_with_deps(
+            move |_| {
+                wasm_bindgen_futures::spawn_local(async move {
+                    let response = Request::get("/config/file").send().await;
+                    if let Ok(response) = response {
+                        if let Ok(received) = response.json::<Option<ConfigFileStatus>>().await {
+                            status.set(received);
+                        }
+                    }
+                });
+                || {}
+            },
+            (),
+        );
+    }
+
+    let Some(status) = (*status).clone() else {
+        return html! {};
+    };
+
+    html! {
+        <div class="text-zinc-700 border border-black m-2 p-2 rounded-md">
+            <div class="flex flex-row">
+                <FileText></FileText>
+                <span class="pl-2 break-all">{format!("Configured by {} (read {} UTC)", status.path, format_time(status.loaded_at_ms))}</span>
+            </div>
+            if !status.managed.is_empty() {
+                <div class="text-sm pl-8">{"These values are set by the file and cannot be changed here:"}</div>
+                <ul class="text-sm pl-12 list-disc">
+                    { for status.managed.iter().map(|path| html! { <li class="break-words">{path}</li> }) }
+                </ul>
+            }
+            if !status.errors.is_empty() {
+                <div class="text-red-800">
+                    <div class="flex flex-row">
+                        <AlertTriangle></AlertTriangle>
+                        <span class="pl-2">{"The file could not be applied, its last valid version is still in use"}</span>
+                    </div>
+                    <ul class="text-sm pl-8 list-disc">
+                        { for status.errors.iter().map(|error| html! { <li class="break-words">{error}</li> }) }
+                    </ul>
+                </div>
+            }
+            if !status.rejected_configs.is_empty() {
+                <div class="text-red-800">
+                    <div class="flex flex-row">
+                        <AlertTriangle></AlertTriangle>
+                        <span class="pl-2">{"These widget configurations do not match the widgets and are not applied"}</span>
+                    </div>
+                    <ul class="text-sm pl-8 list-disc">
+                        { for status.rejected_configs.iter().map(|rejected| html! { <li class="break-words">{rejected}</li> }) }
+                    </ul>
+                </div>
+            }
+            if !status.not_installed.is_empty() {
+                <div class="text-sm pl-8">
+                    {format!("Configured but not installed: {}", status.not_installed.join(", "))}
+                </div>
+            }
+        </div>
+    }
+}
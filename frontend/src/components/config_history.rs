@@ -0,0 +1,194 @@
+//! Lists the saved revisions of the configuration, shows what changed since and restores them.
+
+use common::models::{
+    ConfigChange, ConfigRevision, SystemConfiguration, SystemConfigurationAction,
+};
+use gloo_net::http::Request;
+use yew::prelude::*;
+
+use crate::components::config_card::ConfigCardComponent;
+use crate::components::widget_logs::format_time;
+
+#[derive(Properties, PartialEq)]
+pub struct ConfigHistoryProps {
+    /// Reloaded after a revision was restored
+    pub config: UseReducerHandle<SystemConfiguration>,
+}
+
+/// Formats a timestamp as `YYYY-MM-DD HH:MM:SS` (UTC)
+fn format_date_time(timestamp_ms: u64) -> String {
+    // Civil date from days since the UNIX epoch, see http://howardhinnant.github.io/date_algorithms.html
+    let days = (timestamp_ms / 86_400_000) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02} {}",
+        year,
+        month,
+        day,
+        format_time(timestamp_ms)
+    )
+}
+
+#[function_component(ConfigHistoryComponent)]
+pub fn config_history_component(props: &ConfigHistoryProps) -> Html {
+    let is_open = use_state(|| false);
+    let revisions = use_state(Vec::<ConfigRevision>::new);
+    // Revision whose changes are shown
+    let changes = use_state(|| None as Option<(u64, Vec<ConfigChange>)>);
+    let error = use_state(|| None as Option<String>);
+
+    {
+        let revisions = revisions.clone();
+        let is_open = *is_open;
+        use_effect_with_deps(
+            move |_| {
+                if is_open {
+                    wasm_bindgen_futures::spawn_local(async move {
+                        let response = Request::get("/config/revisions").send().await;
+                        if let Ok(response) = response {
+                            if let Ok(received) = response.json::<Vec<ConfigRevision>>().await {
+                                revisions.set(received);
+                            }
+                        }
+                    });
+                }
+                || {}
+            },
+            (is_open, (*props.config).clone()),
+        );
+    }
+
+    let on_toggle = {
+        let is_open = is_open.clone();
+        let changes = changes.clone();
+        Callback::from(move |_: MouseEvent| {
+            changes.set(None);
+            is_open.set(!*is_open);
+        })
+    };
+
+    let on_show_changes = {
+        let changes = changes.clone();
+        let error = error.clone();
+        Callback::from(move |id: u64| {
+            let changes = changes.clone();
+            let error = error.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                let response = Request::get(&format!("/config/revisions/{}/diff", id))
+                    .send()
+                    .await;
+                match response {
+                    Ok(response) => match response.json::<Vec<ConfigChange>>().await {
+                        Ok(received) => changes.set(Some((id, received))),
+                        Err(_) => error.set(Some("Could not load changes".to_string())),
+                    },
+                    Err(_) => error.set(Some("Could not load changes".to_string())),
+                }
+            });
+        })
+    };
+
+    let on_restore = {
+        let config = props.config.clone();
+        let changes = changes.clone();
+        let error = error.clone();
+        Callback::from(move |id: u64| {
+            let config = config.clone();
+            let changes = changes.clone();
+            let error = error.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                let response = Request::post(&format!("/config/revisions/{}/restore", id))
+                    .send()
+                    .await;
+                match response {
+                    Ok(response) if response.ok() => {}
+                    Ok(response) => {
+                        // Explains e.g. which widget config does not match the installed version
+                        let reason = response.text().await.unwrap_or_default();
+                        error.set(Some(format!("Could not restore revision: {}", reason)));
+                        return;
+                    }
+                    Err(_) => {
+                        error.set(Some("Could not restore revision".to_string()));
+                        return;
+                    }
+                }
+                error.set(None);
+                changes.set(None);
+                if let Ok(response) = Request::get("/system_config").send().await {
+                    if let Ok(received_config) = response.json::<SystemConfiguration>().await {
+                        config
+                            .dispatch(SystemConfigurationAction::SetInitialConfig(received_config));
+                    }
+                }
+            });
+        })
+    };
+
+    html! {
+        <ConfigCardComponent>
+            <div class="text-white text-md font-medium">{"History"}</div>
+            <div class="text-slate-300 text-sm">{"Every saved configuration is kept, restore one to undo changes"}</div>
+            <button class="text-gray-300 text-sm font-semibold" onclick={on_toggle}>
+                {if *is_open { "Hide history" } else { "Show history" }}
+            </button>
+            if *is_open {
+                <div class="text-slate-300 text-sm max-h-64 overflow-y-auto">
+                    { for revisions.iter().enumerate().map(|(index, revision)| {
+                        let id = revision.id;
+                        html! {
+                            <div class="pt-1">
+                                <div class="flex flex-row gap-2">
+                                    <span class="font-mono">{format!("{} UTC", format_date_time(revision.timestamp_ms))}</span>
+                                    if index == 0 {
+                                        <span class="text-slate-400">{"current"}</span>
+                                    } else {
+                                        <button class="text-gray-300 font-semibold" onclick={on_show_changes.reform(move |_: MouseEvent| id)}>{"Changes"}</button>
+                                        <button class="text-gray-300 font-semibold" onclick={on_restore.reform(move |_: MouseEvent| id)}>{"Restore"}</button>
+                                    }
+                                </div>
+                                {
+                                    match &*changes {
+                                        Some((changes_id, changes)) if *changes_id == id => html! {
+                                            <div class="font-mono text-xs pl-2">
+                                                if changes.is_empty() {
+                                                    <div class="text-slate-400">{"Same as the current configuration"}</div>
+                                                }
+                                                { for changes.iter().map(|change| html! {
+                                                    <div class="break-words">
+                                                        {format!(
+                                                            "{}: {} → {}",
+                                                            change.path,
+                                                            change.old.as_deref().unwrap_or("(none)"),
+                                                            change.new.as_deref().unwrap_or("(none)")
+                                                        )}
+                                                    </div>
+                                                }) }
+                                            </div>
+                                        },
+                                        _ => html! {},
+                                    }
+                                }
+                            </div>
+                        }
+                    }) }
+                </div>
+            }
+            if let Some(error) = &*error {
+                <div class="text-red-400 text-sm">{error.clone()}</div>
+            }
+        </ConfigCardComponent>
+    }
+}
@@ -0,0 +1,223 @@
+//! Lets the user export the configuration of the display to a file and import it on another display.
+
+use common::models::{
+    ImportConflict, ImportReport, SystemConfiguration, SystemConfigurationAction,
+};
+use gloo_net::http::Request;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+use crate::components::config_card::ConfigCardComponent;
+use crate::components::permission_approval::PermissionApprovalComponent;
+
+#[derive(Properties, PartialEq)]
+pub struct ConfigTransferProps {
+    /// Reloaded after an import
+    pub config: UseReducerHandle<SystemConfiguration>,
+}
+
+fn describe_conflict(conflict: &ImportConflict) -> String {
+    match conflict {
+        ImportConflict::AlreadyInstalled { widget_name, .. } => {
+            format!("{}: installed already, kept as it is", widget_name)
+        }
+        ImportConflict::ApprovalRequired { widget_name, .. } => {
+            format!("{}: not installed, its permissions were not approved", widget_name)
+        }
+        ImportConflict::PermissionsChanged { widget_name, .. } => format!(
+            "{}: requests other permissions, install it manually to approve them",
+            widget_name
+        ),
+        ImportConflict::NoSource { widget_name } => {
+            format!("{}: not contained in the file", widget_name)
+        }
+        ImportConflict::Failed { widget_name, error } => format!("{}: {}", widget_name, error),
+    }
+}
+
+/// Read the selected bundle file
+async fn read_bundle(input: HtmlInputElement) -> Result<String, String> {
+    let file = input
+        .files()
+        .and_then(|files| files.get(0))
+        .ok_or("No file selected")?;
+    JsFuture::from(file.text())
+        .await
+        .ok()
+        .and_then(|content| content.as_string())
+        .ok_or_else(|| "Could not read file".to_string())
+}
+
+/// Upload a bundle and reload the configuration
+/// # Arguments
+/// * `content` - The bundle
+/// * `approve` - Widgets whose permissions the user approved
+async fn import_bundle(
+    content: String,
+    approve: Vec<String>,
+    config: UseReducerHandle<SystemConfiguration>,
+) -> Result<ImportReport, String> {
+    let response = Request::post("/config/import")
+        .query(approve.iter().map(|widget_name| ("approve", widget_name)))
+        .body(content)
+        .map_err(|_| "Could not read file".to_string())?
+        .send()
+        .await
+        .map_err(|_| "Could not upload file".to_string())?;
+    if !response.ok() {
+        return Err(response.text().await.unwrap_or_default());
+    }
+    let report = response
+        .json::<ImportReport>()
+        .await
+        .map_err(|_| "Invalid response".to_string())?;
+
+    if let Ok(response) = Request::get("/system_config").send().await {
+        if let Ok(received_config) = response.json::<SystemConfiguration>().await {
+            config.dispatch(SystemConfigurationAction::SetInitialConfig(received_config));
+        }
+    }
+    Ok(report)
+}
+
+/// Combine the report of importing a bundle again after approving a widget with the previous one
+/// Widgets installed by the previous import are reported as installed already by the new one.
+fn merge_reports(previous: &ImportReport, report: ImportReport) -> ImportReport {
+    let conflicts = report
+        .conflicts
+        .into_iter()
+        .filter(|conflict| {
+            !matches!(conflict, ImportConflict::AlreadyInstalled { widget_name, .. }
+                if previous.installed.contains(widget_name))
+        })
+        .collect();
+    ImportReport {
+        installed: [previous.installed.clone(), report.installed].concat(),
+        conflicts,
+        rejected_configs: [previous.rejected_configs.clone(), report.rejected_configs].concat(),
+    }
+}
+
+#[function_component(ConfigTransferComponent)]
+pub fn config_transfer_component(props: &ConfigTransferProps) -> Html {
+    let result = use_state(|| None as Option<Result<ImportReport, String>>);
+    // Kept to import the bundle again once the user approved the permissions of a widget
+    let bundle = use_state(|| None as Option<String>);
+    let declined = use_state(Vec::<String>::new);
+    let is_busy = use_state(|| false);
+
+    let on_file_selected = {
+        let config = props.config.clone();
+        let result = result.clone();
+        let bundle = bundle.clone();
+        let declined = declined.clone();
+        Callback::from(move |event: Event| {
+            let Some(input) = event
+                .target()
+                .and_then(|t| t.dyn_into::<HtmlInputElement>().ok())
+            else {
+                return;
+            };
+            let config = config.clone();
+            let result = result.clone();
+            let bundle = bundle.clone();
+            let declined = declined.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                declined.set(Vec::new());
+                let content = match read_bundle(input).await {
+                    Ok(content) => content,
+                    Err(err) => {
+                        result.set(Some(Err(err)));
+                        return;
+                    }
+                };
+                bundle.set(Some(content.clone()));
+                result.set(Some(import_bundle(content, Vec::new(), config).await));
+            });
+        })
+    };
+
+    let on_approve = {
+        let config = props.config.clone();
+        let result = result.clone();
+        let bundle = bundle.clone();
+        let is_busy = is_busy.clone();
+        Callback::from(move |widget_name: String| {
+            let (Some(content), Some(Ok(previous))) = ((*bundle).clone(), (*result).clone())
+            else {
+                return;
+            };
+            let config = config.clone();
+            let result = result.clone();
+            let is_busy = is_busy.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                is_busy.set(true);
+                let report = import_bundle(content, vec![widget_name], config).await;
+                is_busy.set(false);
+                result.set(Some(report.map(|report| merge_reports(&previous, report))));
+            });
+        })
+    };
+
+    let on_decline = {
+        let declined = declined.clone();
+        Callback::from(move |widget_name: String| {
+            let mut names = (*declined).clone();
+            names.push(widget_name);
+            declined.set(names);
+        })
+    };
+
+    html! {
+        <ConfigCardComponent>
+            <div class="text-white text-md font-medium">{"Export and import"}</div>
+            <div class="text-slate-300 text-sm">{"Copies the settings and widgets to another display. Secrets are not exported."}</div>
+            <div class="flex flex-row gap-4 pt-2">
+                <a href="/config/export?embed_components=true" download="wg_display_config.json" class="text-gray-300 text-sm font-semibold">{"Export"}</a>
+                <label class="text-gray-300 text-sm font-semibold cursor-pointer">
+                    {"Import"}
+                    <input type="file" accept="application/json" class="hidden" onchange={on_file_selected}/>
+                </label>
+            </div>
+            {
+                match &*result {
+                    None => html! {},
+                    Some(Err(err)) => html! {
+                        <div class="text-red-400 text-sm break-words">{format!("Import failed: {}", err)}</div>
+                    },
+                    Some(Ok(report)) => html! {
+                        <div class="text-slate-300 text-sm">
+                            <div>{format!("Installed {} widgets", report.installed.len())}</div>
+                            { for report.conflicts.iter().map(|conflict| match conflict {
+                                ImportConflict::ApprovalRequired { widget_name, permissions }
+                                    if !declined.contains(widget_name) => html! {
+                                    <PermissionApprovalComponent
+                                        widget_name={widget_name.clone()}
+                                        permissions={permissions.clone()}
+                                        on_approve={on_approve.reform({
+                                            let widget_name = widget_name.clone();
+                                            move |_: MouseEvent| widget_name.clone()
+                                        })}
+                                        on_cancel={on_decline.reform({
+                                            let widget_name = widget_name.clone();
+                                            move |_: MouseEvent| widget_name.clone()
+                                        })}
+                                        disabled={*is_busy}
+                                    />
+                                },
+                                _ => html! {
+                                    <div class="text-red-400 break-words">{describe_conflict(conflict)}</div>
+                                },
+                            }) }
+                            { for report.rejected_configs.iter().map(|rejected| html! {
+                                <div class="text-yellow-400 break-words">{format!("Configuration not restored: {}", rejected)}</div>
+                            }) }
+                        </div>
+                    },
+                }
+            }
+        </ConfigCardComponent>
+    }
+}
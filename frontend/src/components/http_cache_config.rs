@@ -0,0 +1,41 @@
+//! Component allowing the user to disable the cache for the HTTP responses of widgets.
+
+use common::models::{SystemConfiguration, SystemConfigurationAction};
+use wasm_bindgen::JsCast;
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+use crate::components::config_card::ConfigCardComponent;
+
+#[derive(Properties, PartialEq)]
+pub struct HttpCacheConfigProps {
+    pub config: UseReducerHandle<SystemConfiguration>,
+}
+
+#[function_component(HttpCacheConfigComponent)]
+pub fn http_cache_config_component(props: &HttpCacheConfigProps) -> Html {
+    let on_changed = {
+        let system_config = props.config.clone();
+        Callback::from(move |event: Event| {
+            let input = event
+                .target()
+                .and_then(|t| t.dyn_into::<HtmlInputElement>().ok());
+            if let Some(input) = input {
+                system_config.dispatch(SystemConfigurationAction::SetDisableHttpCache(
+                    !input.checked(),
+                ));
+            }
+        })
+    };
+
+    html! {
+        <ConfigCardComponent>
+            <div class="text-white text-md font-medium">{"HTTP cache"}</div>
+            <div class="text-slate-300 text-sm">{"Answers repeated requests of widgets from cached responses, as long as the server allows it. Cached responses don't count against rate limits."}</div>
+            <label class="text-slate-300 text-sm flex flex-row items-center gap-2 pt-2">
+                <input type="checkbox" checked={!props.config.disable_http_cache} onchange={on_changed}/>
+                {"Cache HTTP responses"}
+            </label>
+        </ConfigCardComponent>
+    }
+}
@@ -0,0 +1,100 @@
+//! Component allowing the user to configure the location and timezone of the display.
+//! Widgets read them instead of asking for coordinates in their own configuration.
+
+use common::models::{SystemConfiguration, SystemConfigurationAction};
+use wasm_bindgen::JsCast;
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+use crate::components::config_card::ConfigCardComponent;
+
+#[derive(Properties, PartialEq)]
+pub struct LocationConfigProps {
+    pub config: UseReducerHandle<SystemConfiguration>,
+}
+
+/// The part of the location an input edits
+#[derive(Clone, Copy)]
+enum LocationField {
+    Latitude,
+    Longitude,
+    Locality,
+}
+
+fn input_value(event: Event) -> Option<String> {
+    event
+        .target()
+        .and_then(|t| t.dyn_into::<HtmlInputElement>().ok())
+        .map(|input| input.value())
+}
+
+#[function_component(LocationConfigComponent)]
+pub fn location_config_component(props: &LocationConfigProps) -> Html {
+    let on_location_changed = {
+        let system_config = props.config.clone();
+        Callback::from(move |(field, event): (LocationField, Event)| {
+            let Some(value) = input_value(event) else {
+                return;
+            };
+            let mut location = system_config.location.clone().unwrap_or_default();
+            match field {
+                LocationField::Latitude => match value.parse() {
+                    Ok(latitude) => location.latitude = latitude,
+                    Err(_) => return,
+                },
+                LocationField::Longitude => match value.parse() {
+                    Ok(longitude) => location.longitude = longitude,
+                    Err(_) => return,
+                },
+                LocationField::Locality => location.locality = value,
+            }
+            if location.is_valid() {
+                system_config.dispatch(SystemConfigurationAction::SetLocation(Some(location)));
+            }
+        })
+    };
+
+    let on_clear_location = {
+        let system_config = props.config.clone();
+        Callback::from(move |_: MouseEvent| {
+            system_config.dispatch(SystemConfigurationAction::SetLocation(None));
+        })
+    };
+
+    let on_timezone_changed = {
+        let system_config = props.config.clone();
+        Callback::from(move |event: Event| {
+            if let Some(timezone) = input_value(event) {
+                let timezone = timezone.trim().to_string();
+                let timezone = (!timezone.is_empty()).then_some(timezone);
+                system_config.dispatch(SystemConfigurationAction::SetTimezone(timezone));
+            }
+        })
+    };
+
+    let location = props.config.location.clone().unwrap_or_default();
+    let has_location = props.config.location.is_some();
+    let field = |field: LocationField| {
+        on_location_changed.reform(move |event: Event| (field, event))
+    };
+
+    html! {
+        <ConfigCardComponent>
+            <div class="text-white text-md font-medium">{"Location"}</div>
+            <div class="text-slate-300 text-sm">{"Used by widgets such as weather or public transport"}</div>
+            <div class="flex flex-row gap-2 pt-2">
+                <input name="latitude" type="number" step="any" min="-90" max="90" placeholder="Latitude" class="bg-transparent text-slate-300 text-sm w-24"
+                    value={has_location.then(|| location.latitude.to_string())} onchange={field(LocationField::Latitude)}/>
+                <input name="longitude" type="number" step="any" min="-180" max="180" placeholder="Longitude" class="bg-transparent text-slate-300 text-sm w-24"
+                    value={has_location.then(|| location.longitude.to_string())} onchange={field(LocationField::Longitude)}/>
+                <input name="locality" type="text" placeholder="Place" class="bg-transparent text-slate-300 text-sm"
+                    value={location.locality.clone()} onchange={field(LocationField::Locality)}/>
+            </div>
+            <button class="text-gray-300 text-sm font-semibold" onclick={on_clear_location} disabled={!has_location}>{"Clear location"}</button>
+            <div class="text-white text-md font-medium pt-2">{"Timezone"}</div>
+            <div class="text-slate-300 text-sm">{"IANA name such as Europe/Zurich. Leave empty to use the timezone of the display"}</div>
+            <input name="timezone" type="text" placeholder="System timezone" class="bg-transparent text-slate-300 text-sm"
+                value={props.config.timezone.clone().unwrap_or_default()} onchange={on_timezone_changed}/>
+        </ConfigCardComponent>
+    }
+}
@@ -2,5 +2,21 @@
 
 pub mod background_color_config;
 pub mod config_card;
+pub mod config_file;
+pub mod config_history;
+pub mod config_transfer;
 pub mod divider;
 pub mod error_display;
+pub mod http_cache_config;
+pub mod location_config;
+pub mod permission_approval;
+pub mod permission_review;
+pub mod persistence_problems;
+pub mod random_seed_notice;
+pub mod schema_form;
+pub mod store_item;
+pub mod test_run_report;
+pub mod widget_health;
+pub mod widget_logs;
+pub mod widget_rate_limit;
+pub mod widget_secrets;
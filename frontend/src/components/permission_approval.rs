@@ -0,0 +1,69 @@
+//! Asks the user to approve the permissions a widget requests before it is installed or runs.
+
+use common::models::{HttpMethod, WidgetPermissions};
+use yew::prelude::*;
+
+use crate::components::config_card::ConfigCardComponent;
+
+/// Props for the PermissionApprovalComponent
+#[derive(Properties, PartialEq)]
+pub struct PermissionApprovalProps {
+    pub widget_name: AttrValue,
+    /// The permissions the widget requests
+    pub permissions: WidgetPermissions,
+    pub on_approve: Callback<MouseEvent>,
+    pub on_cancel: Callback<MouseEvent>,
+    /// Disables the buttons (e.g. while the installation is running)
+    pub disabled: bool,
+    #[prop_or(AttrValue::from("Approve and install"))]
+    pub approve_label: AttrValue,
+}
+
+fn method_name(method: &HttpMethod) -> &'static str {
+    match method {
+        HttpMethod::Get => "GET",
+        HttpMethod::Head => "HEAD",
+        HttpMethod::Post => "POST",
+        HttpMethod::Put => "PUT",
+        HttpMethod::Delete => "DELETE",
+    }
+}
+
+/// Describes the permissions as list of human readable lines
+fn describe_permissions(permissions: &WidgetPermissions) -> Vec<String> {
+    let mut lines = Vec::new();
+    if let Some(http) = &permissions.http {
+        lines.push(format!("Send HTTP requests to {}", http.hosts.join(", ")));
+        let methods: Vec<&str> = http.methods.iter().map(method_name).collect();
+        lines.push(format!("Using the methods {}", methods.join(", ")));
+        lines.push(format!(
+            "With request and response bodies of up to {} bytes",
+            http.max_body_size
+        ));
+    }
+    lines
+}
+
+#[function_component(PermissionApprovalComponent)]
+pub fn permission_approval_component(props: &PermissionApprovalProps) -> Html {
+    html! {
+        <ConfigCardComponent>
+            <div class="text-white text-md font-medium">
+                {format!("{} requests the following permissions", props.widget_name)}
+            </div>
+            <ul class="list-disc pl-5 text-slate-300 text-sm">
+                { for describe_permissions(&props.permissions).into_iter().map(|line| html! {
+                    <li>{line}</li>
+                })}
+            </ul>
+            <div class="flex flex-row justify-end gap-2 pt-2">
+                <button class="text-gray-300 text-sm font-semibold" onclick={props.on_cancel.clone()} disabled={props.disabled}>
+                    {"Cancel"}
+                </button>
+                <button class="text-gray-300 text-sm font-semibold" onclick={props.on_approve.clone()} disabled={props.disabled}>
+                    {props.approve_label.clone()}
+                </button>
+            </div>
+        </ConfigCardComponent>
+    }
+}
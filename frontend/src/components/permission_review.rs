@@ -0,0 +1,121 @@
+//! Lets the user approve the permissions of a widget that was installed before widgets had to request them.
+
+use common::models::WidgetPermissions;
+use gloo_net::http::Request;
+use yew::prelude::*;
+
+use crate::components::permission_approval::PermissionApprovalComponent;
+
+/// Props for the PermissionReviewComponent
+#[derive(Properties, PartialEq)]
+pub struct PermissionReviewProps {
+    pub widget_name: AttrValue,
+    /// Called with the granted permissions once they were approved
+    pub on_approved: Callback<WidgetPermissions>,
+}
+
+#[function_component(PermissionReviewComponent)]
+pub fn permission_review_component(props: &PermissionReviewProps) -> Html {
+    let requested = use_state(|| None as Option<WidgetPermissions>);
+    let is_busy = use_state(|| false);
+    let error = use_state(|| None as Option<String>);
+
+    let on_review = {
+        let widget_name = props.widget_name.clone();
+        let requested = requested.clone();
+        let is_busy = is_busy.clone();
+        let error = error.clone();
+        Callback::from(move |_: MouseEvent| {
+            let widget_name = widget_name.clone();
+            let requested = requested.clone();
+            let is_busy = is_busy.clone();
+            let error = error.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                is_busy.set(true);
+                let url = format!("/widgets/{}/requested_permissions", widget_name);
+                let response = Request::get(&url).send().await;
+                is_busy.set(false);
+                match response {
+                    Ok(response) if response.ok() => {
+                        match response.json::<WidgetPermissions>().await {
+                            Ok(permissions) => {
+                                error.set(None);
+                                requested.set(Some(permissions));
+                            }
+                            Err(err) => error.set(Some(format!("Failed to parse permissions: {}", err))),
+                        }
+                    }
+                    Ok(response) => {
+                        error.set(Some(response.text().await.unwrap_or("No error message".to_string())))
+                    }
+                    Err(err) => error.set(Some(format!("Failed to load permissions: {}", err))),
+                }
+            });
+        })
+    };
+
+    let on_approve = {
+        let widget_name = props.widget_name.clone();
+        let on_approved = props.on_approved.clone();
+        let requested = requested.clone();
+        let is_busy = is_busy.clone();
+        let error = error.clone();
+        Callback::from(move |_: MouseEvent| {
+            let Some(permissions) = (*requested).clone() else {
+                return;
+            };
+            let widget_name = widget_name.clone();
+            let on_approved = on_approved.clone();
+            let requested = requested.clone();
+            let is_busy = is_busy.clone();
+            let error = error.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                let url = format!("/widgets/{}/approve_permissions", widget_name);
+                let Ok(request) = Request::post(&url).json(&permissions) else {
+                    return;
+                };
+                is_busy.set(true);
+                let response = request.send().await;
+                is_busy.set(false);
+                match response {
+                    Ok(response) if response.ok() => {
+                        requested.set(None);
+                        on_approved.emit(permissions);
+                    }
+                    Ok(response) => {
+                        error.set(Some(response.text().await.unwrap_or("No error message".to_string())))
+                    }
+                    Err(err) => error.set(Some(format!("Failed to approve permissions: {}", err))),
+                }
+            });
+        })
+    };
+
+    let on_cancel = {
+        let requested = requested.clone();
+        Callback::from(move |_: MouseEvent| requested.set(None))
+    };
+
+    html! {
+        <div class="pt-2">
+            <div class="text-yellow-400 text-sm">
+                {"This widget was installed before widgets had to request permissions. It can't send HTTP requests until you approve them."}
+            </div>
+            if let Some(permissions) = &*requested {
+                <PermissionApprovalComponent
+                    widget_name={props.widget_name.clone()}
+                    permissions={permissions.clone()}
+                    on_approve={on_approve}
+                    on_cancel={on_cancel}
+                    disabled={*is_busy}
+                    approve_label="Approve"
+                />
+            } else {
+                <button class="text-gray-300 text-sm font-semibold" onclick={on_review} disabled={*is_busy}>{"Review permissions"}</button>
+            }
+            if let Some(error) = &*error {
+                <div class="text-red-300 text-xs">{error.clone()}</div>
+            }
+        </div>
+    }
+}
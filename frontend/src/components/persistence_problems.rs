@@ -0,0 +1,66 @@
+//! Warns about stored data that could not be read and was quarantined by the display.
+//! The display keeps running with defaults, so the user may have to reconfigure or reinstall widgets.
+
+use common::models::QuarantinedEntry;
+use gloo_net::http::Request;
+use yew::prelude::*;
+use yew_feather::AlertTriangle;
+
+use crate::components::widget_logs::format_time;
+
+#[function_component(PersistenceProblemsComponent)]
+pub fn persistence_problems_component() -> Html {
+    let problems = use_state(Vec::<QuarantinedEntry>::new);
+
+    {
+        let problems = problems.clone();
+        use_effect_with_deps(
+            move |_| {
+                wasm_bindgen_futures::spawn_local(async move {
+                    let response = Request::get("/persistence/problems").send().await;
+                    if let Ok(response) = response {
+                        if let Ok(entries) = response.json::<Vec<QuarantinedEntry>>().await {
+                            problems.set(entries);
+                        }
+                    }
+                });
+                || {}
+            },
+            (),
+        );
+    }
+
+    let on_dismiss = {
+        let problems = problems.clone();
+        Callback::from(move |_: MouseEvent| {
+            let problems = problems.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                let response = Request::delete("/persistence/problems").send().await;
+                if matches!(response, Ok(response) if response.ok()) {
+                    problems.set(vec![]);
+                }
+            });
+        })
+    };
+
+    if problems.is_empty() {
+        return html! {};
+    }
+
+    html! {
+        <div class="text-red-800 border border-black m-2 p-2 rounded-md">
+            <div class="flex flex-row">
+                <AlertTriangle></AlertTriangle>
+                <span class="pl-2">{"Some stored data could not be read and was replaced by defaults. Check your settings and reinstall affected widgets."}</span>
+            </div>
+            <ul class="text-sm pl-8 list-disc">
+                { for problems.iter().map(|problem| html! {
+                    <li class="break-words">
+                        {format!("{} UTC: {} ({} bytes): {}", format_time(problem.quarantined_at_ms), problem.key, problem.size, problem.reason)}
+                    </li>
+                }) }
+            </ul>
+            <button onclick={on_dismiss} class="text-sm font-semibold pl-8">{"Dismiss"}</button>
+        </div>
+    }
+}
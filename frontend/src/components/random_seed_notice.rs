@@ -0,0 +1,40 @@
+//! Warns that the display hands out predictable random values, as it was started with a seed for tests.
+
+use gloo_net::http::Request;
+use yew::prelude::*;
+use yew_feather::AlertTriangle;
+
+#[function_component(RandomSeedNoticeComponent)]
+pub fn random_seed_notice_component() -> Html {
+    let seed = use_state(|| None as Option<u64>);
+
+    {
+        let seed = seed.clone();
+        use_effect_with_deps(
+            move |_| {
+                wasm_bindgen_futures::spawn_local(async move {
+                    let received_seed = match Request::get("/random_seed").send().await {
+                        Ok(response) => response.json::<Option<u64>>().await.ok().flatten(),
+                        Err(_) => None,
+                    };
+                    seed.set(received_seed);
+                });
+                || {}
+            },
+            (),
+        );
+    }
+
+    let Some(seed) = *seed else {
+        return html! {};
+    };
+
+    html! {
+        <div class="text-red-800 border border-black m-2 p-2 rounded-md">
+            <div class="flex flex-row">
+                <AlertTriangle></AlertTriangle>
+                <span class="pl-2">{format!("The display was started with the random seed {}. Widgets get predictable random values, so don't use it outside of tests.", seed)}</span>
+            </div>
+        </div>
+    }
+}
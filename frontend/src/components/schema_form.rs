@@ -0,0 +1,269 @@
+//! Form field generated from the JSON Schema of a widget configuration.
+//! Renders strings, numbers, enums, booleans, arrays and nested objects and shows the errors of its value inline.
+
+use std::rc::Rc;
+
+use common::config_schema::{default_value, join_path, required_properties, type_of};
+use common::models::ConfigFieldError;
+use serde_json::{Map, Number, Value};
+use wasm_bindgen::JsCast;
+use web_sys::{HtmlInputElement, HtmlSelectElement};
+use yew::prelude::*;
+
+/// Props for the SchemaFieldComponent
+#[derive(Properties, PartialEq)]
+pub struct SchemaFieldProps {
+    /// Schema of the field in its JSON Schema form, see [common::config_schema::normalize]
+    pub schema: Value,
+    /// Current value, `null` if it is not set
+    pub value: Value,
+    /// Dotted path of the value, errors with this path are shown below the field
+    #[prop_or_default]
+    pub path: String,
+    /// Property name, used as label if the schema has no title
+    #[prop_or_default]
+    pub name: Option<String>,
+    #[prop_or_default]
+    pub required: bool,
+    pub errors: Rc<Vec<ConfigFieldError>>,
+    /// Called with the new value, `None` if an optional value was cleared
+    pub on_change: Callback<Option<Value>>,
+}
+
+fn input_value(event: Event) -> Option<String> {
+    event
+        .target()
+        .and_then(|t| t.dyn_into::<HtmlInputElement>().ok())
+        .map(|input| input.value())
+}
+
+/// Parses the text of a number input
+/// Text that is not a number is kept as string, so the validation reports it instead of dropping it.
+fn parse_number(text: &str, integer: bool) -> Value {
+    let parsed = if integer {
+        text.parse::<i64>().ok().map(Value::from)
+    } else {
+        text.parse::<f64>()
+            .ok()
+            .and_then(Number::from_f64)
+            .map(Value::Number)
+    };
+    parsed.unwrap_or_else(|| Value::String(text.to_string()))
+}
+
+fn enum_label(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+#[function_component(SchemaFieldComponent)]
+pub fn schema_field_component(props: &SchemaFieldProps) -> Html {
+    let schema = &props.schema;
+    let label = schema
+        .get("title")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .or_else(|| props.name.clone());
+    let description = schema.get("description").and_then(Value::as_str);
+    let errors: Vec<&ConfigFieldError> = props
+        .errors
+        .iter()
+        .filter(|error| error.path == props.path)
+        .collect();
+
+    let input = if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+        let on_change = {
+            let allowed = allowed.clone();
+            props.on_change.reform(move |event: Event| {
+                let index = event
+                    .target()
+                    .and_then(|t| t.dyn_into::<HtmlSelectElement>().ok())
+                    .and_then(|select| select.value().parse::<usize>().ok());
+                index.and_then(|index| allowed.get(index).cloned())
+            })
+        };
+        html! {
+            <select class="bg-transparent text-slate-300 text-sm" onchange={on_change}>
+                if !props.required {
+                    <option value="" selected={props.value.is_null()}>{"-"}</option>
+                }
+                { for allowed.iter().enumerate().map(|(index, option)| html! {
+                    <option value={index.to_string()} selected={*option == props.value}>{enum_label(option)}</option>
+                }) }
+            </select>
+        }
+    } else {
+        match type_of(schema) {
+            "boolean" => {
+                let on_change = props.on_change.reform(|event: Event| {
+                    event
+                        .target()
+                        .and_then(|t| t.dyn_into::<HtmlInputElement>().ok())
+                        .map(|input| Value::Bool(input.checked()))
+                });
+                html! {
+                    <input type="checkbox" checked={props.value.as_bool().unwrap_or(false)} onchange={on_change}/>
+                }
+            }
+            kind @ ("integer" | "number") => {
+                let integer = kind == "integer";
+                let on_change = props.on_change.reform(move |event: Event| {
+                    input_value(event)
+                        .filter(|text| !text.trim().is_empty())
+                        .map(|text| parse_number(text.trim(), integer))
+                });
+                let value = match &props.value {
+                    Value::Null => String::new(),
+                    Value::String(text) => text.clone(),
+                    other => other.to_string(),
+                };
+                html! {
+                    <input type="text" inputmode={if integer { "numeric" } else { "decimal" }}
+                        class="bg-transparent text-slate-300 text-sm" {value} onchange={on_change}/>
+                }
+            }
+            "array" => render_array(props),
+            "object" => render_object(props),
+            _ => {
+                let on_change = props.on_change.reform(|event: Event| {
+                    input_value(event)
+                        .filter(|text| !text.is_empty())
+                        .map(Value::String)
+                });
+                html! {
+                    <input type="text" class="bg-transparent text-slate-300 text-sm"
+                        value={props.value.as_str().unwrap_or_default().to_string()} onchange={on_change}/>
+                }
+            }
+        }
+    };
+
+    html! {
+        <div class="pt-2">
+            if let Some(label) = label {
+                <div class="text-white text-sm font-medium">
+                    {label}
+                    if props.required {
+                        <span class="text-red-300">{" *"}</span>
+                    }
+                </div>
+            }
+            if let Some(description) = description {
+                <div class="text-slate-400 text-xs">{description}</div>
+            }
+            {input}
+            { for errors.iter().map(|error| html! {
+                <div class="text-red-300 text-xs">{error.message.clone()}</div>
+            }) }
+        </div>
+    }
+}
+
+fn render_array(props: &SchemaFieldProps) -> Html {
+    let items_schema = props.schema.get("items").cloned().unwrap_or(Value::Bool(true));
+    let items = props.value.as_array().cloned().unwrap_or_default();
+
+    let on_add = {
+        let items = items.clone();
+        let default = default_value(&items_schema);
+        props.on_change.reform(move |_: MouseEvent| {
+            let mut items = items.clone();
+            items.push(default.clone());
+            Some(Value::Array(items))
+        })
+    };
+
+    html! {
+        <div class="border-l border-zinc-500 pl-2">
+            { for items.iter().enumerate().map(|(index, item)| {
+                let on_item_change = {
+                    let items = items.clone();
+                    props.on_change.reform(move |value: Option<Value>| {
+                        let mut items = items.clone();
+                        items[index] = value.unwrap_or(Value::Null);
+                        Some(Value::Array(items))
+                    })
+                };
+                let on_remove = {
+                    let items = items.clone();
+                    props.on_change.reform(move |_: MouseEvent| {
+                        let mut items = items.clone();
+                        items.remove(index);
+                        Some(Value::Array(items))
+                    })
+                };
+                html! {
+                    <div class="flex flex-row items-end gap-2">
+                        <SchemaFieldComponent
+                            schema={items_schema.clone()}
+                            value={item.clone()}
+                            path={join_path(&props.path, &index.to_string())}
+                            errors={props.errors.clone()}
+                            on_change={on_item_change}
+                            required=true
+                        />
+                        <button class="text-gray-300 text-sm font-semibold" onclick={on_remove}>{"Remove"}</button>
+                    </div>
+                }
+            }) }
+            <button class="text-gray-300 text-sm font-semibold pt-1" onclick={on_add}>{"Add"}</button>
+        </div>
+    }
+}
+
+fn render_object(props: &SchemaFieldProps) -> Html {
+    let empty = Map::new();
+    let properties = props
+        .schema
+        .get("properties")
+        .and_then(Value::as_object)
+        .unwrap_or(&empty);
+    let required = required_properties(&props.schema);
+    let object = props.value.as_object().cloned().unwrap_or_default();
+    // Errors about values the schema does not describe, e.g. settings of an older version
+    let unknown: Vec<&ConfigFieldError> = props
+        .errors
+        .iter()
+        .filter(|error| {
+            object
+                .keys()
+                .filter(|key| !properties.contains_key(*key))
+                .any(|key| error.path == join_path(&props.path, key))
+        })
+        .collect();
+
+    html! {
+        <div class={classes!((!props.path.is_empty()).then_some("border-l border-zinc-500 pl-2"))}>
+            { for properties.iter().map(|(name, property)| {
+                let on_property_change = {
+                    let object = object.clone();
+                    let name = name.clone();
+                    props.on_change.reform(move |value: Option<Value>| {
+                        let mut object = object.clone();
+                        match value {
+                            Some(value) => object.insert(name.clone(), value),
+                            None => object.remove(&name),
+                        };
+                        Some(Value::Object(object))
+                    })
+                };
+                html! {
+                    <SchemaFieldComponent
+                        schema={property.clone()}
+                        value={object.get(name).cloned().unwrap_or(Value::Null)}
+                        path={join_path(&props.path, name)}
+                        name={name.clone()}
+                        required={required.contains(&name.as_str())}
+                        errors={props.errors.clone()}
+                        on_change={on_property_change}
+                    />
+                }
+            }) }
+            { for unknown.iter().map(|error| html! {
+                <div class="text-red-300 text-xs pt-2">{error.to_string()}</div>
+            }) }
+        </div>
+    }
+}
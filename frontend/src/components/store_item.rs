@@ -0,0 +1,85 @@
+//! Card showing a widget of the store together with its metadata.
+
+use common::models::{DeclaredPermissions, WidgetStoreItem};
+use yew::prelude::*;
+
+use crate::components::config_card::ConfigCardComponent;
+
+/// Props for the StoreItemComponent
+#[derive(Properties, PartialEq)]
+pub struct StoreItemProps {
+    pub item: WidgetStoreItem,
+    /// Called when the install button is clicked. The button value holds the item name.
+    pub on_install: Callback<MouseEvent>,
+    /// Called when the deinstall button is clicked. The button value holds the item name.
+    pub on_deinstall: Callback<MouseEvent>,
+    /// Disables the buttons (e.g. while an installation is running)
+    pub disabled: bool,
+}
+
+/// Describes what the widget does with the network in a human readable way
+fn describe_permissions(permissions: &Option<DeclaredPermissions>) -> String {
+    match permissions {
+        None => "Network access not declared".to_string(),
+        Some(permissions) if permissions.http_hosts.is_empty() => "No network access".to_string(),
+        Some(permissions) => format!("Network access to {}", permissions.http_hosts.join(", ")),
+    }
+}
+
+#[function_component(StoreItemComponent)]
+pub fn store_item_component(props: &StoreItemProps) -> Html {
+    let item = &props.item;
+
+    html! {
+        <ConfigCardComponent>
+            <div class="flex flex-col">
+                <div class="flex flex-row justify-between">
+                    <div class="flex flex-row pr-4">
+                        if let Some(icon_url) = &item.icon_url {
+                            <img src={icon_url.clone()} alt="" class="h-8 w-8 object-contain mr-3"/>
+                        }
+                        <div class="flex flex-col">
+                            <span class="text-slate-300 text-sm font-semibold"> {&item.name} </span>
+                            <span class="text-slate-300 text-xs"> {&item.description} </span>
+                            if let Some(author) = &item.author {
+                                <span class="text-slate-400 text-xs"> {format!("by {}", author)} </span>
+                            }
+                        </div>
+                    </div>
+                    <div class="flex flex-row gap-2">
+                        <button class="pt-2 text-gray-300 text-sm font-semibold" value={item.name.clone()} onclick={props.on_deinstall.clone()} disabled={props.disabled}>
+                            {"Deinstall"}
+                        </button>
+                        <button class="pt-2 text-gray-300 text-sm font-semibold" value={item.name.clone()} onclick={props.on_install.clone()} disabled={props.disabled}>
+                            {"Install"}
+                        </button>
+                    </div>
+                </div>
+                if !item.tags.is_empty() {
+                    <div class="flex flex-row flex-wrap gap-1 pt-2">
+                        { for item.tags.iter().map(|tag| html! {
+                            <span class="bg-zinc-500 text-white text-xs rounded px-1">{tag}</span>
+                        })}
+                    </div>
+                }
+                <div class="flex flex-col pt-2 text-slate-400 text-xs">
+                    <span>{describe_permissions(&item.permissions)}</span>
+                    if let Some(license) = &item.license {
+                        <span>{format!("License: {}", license)}</span>
+                    }
+                    if let Some(min_host_version) = &item.min_host_version {
+                        <span>{format!("Requires WG Display {} or newer", min_host_version)}</span>
+                    }
+                    <div class="flex flex-row gap-2">
+                        if let Some(homepage) = &item.homepage {
+                            <a href={homepage.clone()} target="_blank" class="underline">{"Homepage"}</a>
+                        }
+                        if let Some(screenshot_url) = &item.screenshot_url {
+                            <a href={screenshot_url.clone()} target="_blank" class="underline">{"Screenshot"}</a>
+                        }
+                    </div>
+                </div>
+            </div>
+        </ConfigCardComponent>
+    }
+}
@@ -0,0 +1,36 @@
+//! Shows the outcome of a test run of a widget: what it would display, its logs and how long it took.
+
+use common::models::TestRunReport;
+use yew::prelude::*;
+
+use crate::components::config_card::ConfigCardComponent;
+use crate::components::widget_logs::render_log_entry;
+
+/// Props for the TestRunReportComponent
+#[derive(Properties, PartialEq)]
+pub struct TestRunReportProps {
+    pub report: TestRunReport,
+}
+
+#[function_component(TestRunReportComponent)]
+pub fn test_run_report_component(props: &TestRunReportProps) -> Html {
+    let report = &props.report;
+    html! {
+        <ConfigCardComponent>
+            <div class="text-white text-md font-medium">{format!("Test run ({} ms)", report.duration_ms)}</div>
+            if let Some(output) = &report.output {
+                <pre class="text-slate-300 text-sm whitespace-pre-wrap break-words">{output}</pre>
+            }
+            if let Some(error) = &report.error {
+                <div class="text-red-400 text-sm break-words">{format!("Run failed: {}", error)}</div>
+            }
+            <div class="font-mono text-xs max-h-48 overflow-y-auto pt-2">
+                if report.logs.is_empty() {
+                    <p class="text-gray-400">{"No log lines"}</p>
+                } else {
+                    { for report.logs.iter().map(render_log_entry) }
+                }
+            </div>
+        </ConfigCardComponent>
+    }
+}
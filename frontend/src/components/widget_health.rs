@@ -0,0 +1,58 @@
+//! Shows how a widget is doing: whether its last run succeeded, its last error and how long runs take.
+
+use common::models::WidgetHealth;
+use yew::prelude::*;
+
+use crate::components::widget_logs::format_time;
+
+/// Props for the WidgetHealthComponent
+#[derive(Properties, PartialEq)]
+pub struct WidgetHealthProps {
+    /// None if the widget did not run yet
+    pub health: Option<WidgetHealth>,
+}
+
+#[function_component(WidgetHealthComponent)]
+pub fn widget_health_component(props: &WidgetHealthProps) -> Html {
+    let Some(health) = &props.health else {
+        return html! {
+            <div class="text-slate-400 text-sm">{"Not run yet"}</div>
+        };
+    };
+
+    let status = if health.is_healthy() {
+        html! { <span class="text-green-400">{"Healthy"}</span> }
+    } else {
+        html! {
+            <span class="text-red-400">
+                {format!(
+                    "Failing ({} times in a row, retrying in {} s)",
+                    health.consecutive_failures,
+                    health.backoff_ms / 1000
+                )}
+            </span>
+        }
+    };
+
+    html! {
+        <div class="text-slate-300 text-sm">
+            <div>{status}</div>
+            <div class="text-slate-400">
+                {format!(
+                    "Last success: {} · Average run: {:.0} ms · {} runs",
+                    health.last_success_ms.map_or("never".to_string(), |ms| format!("{} UTC", format_time(ms))),
+                    health.average_run_ms,
+                    health.runs
+                )}
+            </div>
+            {
+                match (&health.last_error, health.last_failure_ms) {
+                    (Some(error), Some(failed_at)) if !health.is_healthy() => html! {
+                        <div class="text-red-400 break-words">{format!("{} UTC: {}", format_time(failed_at), error)}</div>
+                    },
+                    _ => html! {},
+                }
+            }
+        </div>
+    }
+}
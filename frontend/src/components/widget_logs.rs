@@ -0,0 +1,148 @@
+//! Shows the recent log lines of a widget, including its stdout/stderr and errors reported by the host.
+
+use common::models::{LogLevel, LogSource, WidgetLogEntry};
+use gloo_net::http::Request;
+use wasm_bindgen::JsCast;
+use web_sys::HtmlSelectElement;
+use yew::prelude::*;
+
+/// Props for the WidgetLogsComponent
+#[derive(Properties, PartialEq)]
+pub struct WidgetLogsProps {
+    pub widget_name: AttrValue,
+}
+
+/// Formats the time of a log line as `HH:MM:SS` (UTC)
+pub fn format_time(timestamp_ms: u64) -> String {
+    let seconds_of_day = (timestamp_ms / 1000) % 86_400;
+    format!(
+        "{:02}:{:02}:{:02}",
+        seconds_of_day / 3600,
+        (seconds_of_day / 60) % 60,
+        seconds_of_day % 60
+    )
+}
+
+fn level_class(level: LogLevel) -> &'static str {
+    match level {
+        LogLevel::Debug => "text-slate-500",
+        LogLevel::Info => "text-slate-300",
+        LogLevel::Warn => "text-yellow-400",
+        LogLevel::Error => "text-red-400",
+    }
+}
+
+fn source_name(source: LogSource) -> &'static str {
+    match source {
+        LogSource::Widget => "widget",
+        LogSource::Stdout => "stdout",
+        LogSource::Stderr => "stderr",
+        LogSource::Host => "host",
+    }
+}
+
+/// Renders a log line, colored by its level
+pub fn render_log_entry(entry: &WidgetLogEntry) -> Html {
+    html! {
+        <div class={level_class(entry.level)}>
+            {format!(
+                "{} {} [{}] {}",
+                format_time(entry.timestamp_ms),
+                entry.level.as_str(),
+                source_name(entry.source),
+                entry.message
+            )}
+        </div>
+    }
+}
+
+#[function_component(WidgetLogsComponent)]
+pub fn widget_logs_component(props: &WidgetLogsProps) -> Html {
+    let is_open = use_state(|| false);
+    let min_level = use_state(|| LogLevel::Info);
+    let entries = use_state(Vec::<WidgetLogEntry>::new);
+    // Incremented to reload the log lines
+    let revision = use_state(|| 0u32);
+
+    {
+        let entries = entries.clone();
+        let widget_name = props.widget_name.clone();
+        let is_open = *is_open;
+        let min_level = *min_level;
+        use_effect_with_deps(
+            move |_| {
+                if is_open {
+                    wasm_bindgen_futures::spawn_local(async move {
+                        let url = format!(
+                            "/widgets/{}/logs?min_level={}",
+                            widget_name,
+                            min_level.as_str()
+                        );
+                        let response = Request::get(&url).send().await;
+                        if let Ok(response) = response {
+                            if let Ok(received) = response.json::<Vec<WidgetLogEntry>>().await {
+                                entries.set(received);
+                            }
+                        }
+                    });
+                }
+                || {}
+            },
+            (props.widget_name.clone(), is_open, min_level, *revision),
+        );
+    }
+
+    let on_toggle = {
+        let is_open = is_open.clone();
+        Callback::from(move |_: MouseEvent| is_open.set(!*is_open))
+    };
+
+    let on_refresh = {
+        let revision = revision.clone();
+        Callback::from(move |_: MouseEvent| revision.set(*revision + 1))
+    };
+
+    let on_level_changed = {
+        let min_level = min_level.clone();
+        Callback::from(move |event: Event| {
+            let level = event
+                .target()
+                .and_then(|t| t.dyn_into::<HtmlSelectElement>().ok())
+                .and_then(|select| select.value().parse().ok());
+            if let Some(level) = level {
+                min_level.set(level);
+            }
+        })
+    };
+
+    if !*is_open {
+        return html! {
+            <button class="text-gray-300 text-sm font-semibold" onclick={on_toggle}>{"Show logs"}</button>
+        };
+    }
+
+    html! {
+        <div class="pt-2">
+            <div class="flex flex-row items-center gap-2">
+                <button class="text-gray-300 text-sm font-semibold" onclick={on_toggle}>{"Hide logs"}</button>
+                <select class="bg-transparent text-slate-300 text-sm" onchange={on_level_changed}>
+                    { for LogLevel::ALL.iter().map(|level| html! {
+                        <option value={level.as_str()} selected={*level == *min_level}>{level.as_str()}</option>
+                    })}
+                </select>
+                <button class="text-gray-300 text-sm font-semibold" onclick={on_refresh}>{"Refresh"}</button>
+            </div>
+            <div class="font-mono text-xs max-h-48 overflow-y-auto">
+                {
+                    if entries.is_empty() {
+                        html! { <p class="text-gray-400">{"No log lines"}</p> }
+                    } else {
+                        html! {
+                            { for entries.iter().map(render_log_entry) }
+                        }
+                    }
+                }
+            </div>
+        </div>
+    }
+}
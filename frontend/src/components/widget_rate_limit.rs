@@ -0,0 +1,127 @@
+//! Lets the user limit the HTTP requests of a widget, e.g. to stay within the quota of an API.
+
+use common::models::RateLimit;
+use gloo_net::http::Request;
+use wasm_bindgen::JsCast;
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+/// Limit suggested when the user enables the rate limit
+const DEFAULT_RATE_LIMIT: RateLimit = RateLimit {
+    requests: 60,
+    per_seconds: 3600,
+};
+
+/// Props for the WidgetRateLimitComponent
+#[derive(Properties, PartialEq)]
+pub struct WidgetRateLimitProps {
+    pub widget_name: AttrValue,
+    /// The saved rate limit
+    pub rate_limit: Option<RateLimit>,
+    /// Called with the rate limit once it is saved
+    pub on_saved: Callback<Option<RateLimit>>,
+}
+
+fn input_number(event: Event) -> Option<u32> {
+    event
+        .target()
+        .and_then(|t| t.dyn_into::<HtmlInputElement>().ok())
+        .and_then(|input| input.value().parse().ok())
+}
+
+#[function_component(WidgetRateLimitComponent)]
+pub fn widget_rate_limit_component(props: &WidgetRateLimitProps) -> Html {
+    // The edited rate limit, `None` while it matches the saved one
+    let edited = use_state(|| None as Option<Option<RateLimit>>);
+    let error = use_state(|| None as Option<String>);
+    let rate_limit = (*edited)
+        .clone()
+        .unwrap_or_else(|| props.rate_limit.clone());
+
+    let on_toggle = {
+        let edited = edited.clone();
+        let rate_limit = rate_limit.clone();
+        Callback::from(move |_: Event| {
+            let toggled = match rate_limit {
+                Some(_) => None,
+                None => Some(DEFAULT_RATE_LIMIT),
+            };
+            edited.set(Some(toggled));
+        })
+    };
+
+    let on_requests_changed = {
+        let edited = edited.clone();
+        let rate_limit = rate_limit.clone();
+        Callback::from(move |event: Event| {
+            if let (Some(limit), Some(requests)) = (rate_limit.clone(), input_number(event)) {
+                edited.set(Some(Some(RateLimit { requests, ..limit })));
+            }
+        })
+    };
+
+    let on_per_seconds_changed = {
+        let edited = edited.clone();
+        let rate_limit = rate_limit.clone();
+        Callback::from(move |event: Event| {
+            if let (Some(limit), Some(per_seconds)) = (rate_limit.clone(), input_number(event)) {
+                edited.set(Some(Some(RateLimit {
+                    per_seconds,
+                    ..limit
+                })));
+            }
+        })
+    };
+
+    let on_save = {
+        let widget_name = props.widget_name.clone();
+        let on_saved = props.on_saved.clone();
+        let edited = edited.clone();
+        let error = error.clone();
+        let rate_limit = rate_limit.clone();
+        Callback::from(move |_: MouseEvent| {
+            let url = format!("/widgets/{}/http_rate_limit", widget_name);
+            let on_saved = on_saved.clone();
+            let edited = edited.clone();
+            let error = error.clone();
+            let rate_limit = rate_limit.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                let Ok(request) = Request::post(&url).json(&rate_limit) else {
+                    return;
+                };
+                match request.send().await {
+                    Ok(response) if response.ok() => {
+                        error.set(None);
+                        edited.set(None);
+                        on_saved.emit(rate_limit);
+                    }
+                    Ok(response) => error.set(Some(response.text().await.unwrap_or_default())),
+                    Err(_) => error.set(Some("Failed to save the rate limit".to_string())),
+                }
+            });
+        })
+    };
+
+    html! {
+        <div class="pt-2">
+            <label class="text-slate-300 text-sm flex flex-row items-center gap-2">
+                <input type="checkbox" checked={rate_limit.is_some()} onchange={on_toggle}/>
+                {"Limit HTTP requests"}
+            </label>
+            if let Some(limit) = &rate_limit {
+                <div class="flex flex-row items-center gap-2 text-slate-300 text-sm pt-1">
+                    <input type="number" min="1" class="bg-transparent text-slate-300 text-sm w-16" value={limit.requests.to_string()} onchange={on_requests_changed}/>
+                    {"requests per"}
+                    <input type="number" min="1" class="bg-transparent text-slate-300 text-sm w-16" value={limit.per_seconds.to_string()} onchange={on_per_seconds_changed}/>
+                    {"seconds"}
+                </div>
+            }
+            if edited.is_some() {
+                <button class="text-gray-300 text-sm font-semibold" onclick={on_save}>{"Save"}</button>
+            }
+            if let Some(error) = &*error {
+                <div class="text-red-400 text-sm break-words">{error.clone()}</div>
+            }
+        </div>
+    }
+}
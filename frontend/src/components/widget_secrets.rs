@@ -0,0 +1,132 @@
+//! Lets the user manage the secrets of a widget (e.g. API tokens).
+//! Secrets are write-only, only their names are ever shown.
+
+use gloo_net::http::Request;
+use wasm_bindgen::JsCast;
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+/// Props for the WidgetSecretsComponent
+#[derive(Properties, PartialEq)]
+pub struct WidgetSecretsProps {
+    pub widget_name: AttrValue,
+}
+
+fn input_value(event: Event) -> Option<String> {
+    event
+        .target()
+        .and_then(|t| t.dyn_into::<HtmlInputElement>().ok())
+        .map(|input| input.value())
+}
+
+#[function_component(WidgetSecretsComponent)]
+pub fn widget_secrets_component(props: &WidgetSecretsProps) -> Html {
+    let secret_names = use_state(Vec::<String>::new);
+    let new_name = use_state(String::new);
+    let new_value = use_state(String::new);
+    // Incremented after every change to reload the secret names
+    let revision = use_state(|| 0u32);
+
+    {
+        let secret_names = secret_names.clone();
+        let widget_name = props.widget_name.clone();
+        use_effect_with_deps(
+            move |_| {
+                wasm_bindgen_futures::spawn_local(async move {
+                    let response = Request::get(&format!("/secrets/{}", widget_name))
+                        .send()
+                        .await;
+                    if let Ok(response) = response {
+                        if let Ok(names) = response.json::<Vec<String>>().await {
+                            secret_names.set(names);
+                        }
+                    }
+                });
+                || {}
+            },
+            (props.widget_name.clone(), *revision),
+        );
+    }
+
+    let on_name_changed = {
+        let new_name = new_name.clone();
+        Callback::from(move |event: Event| {
+            if let Some(value) = input_value(event) {
+                new_name.set(value);
+            }
+        })
+    };
+
+    let on_value_changed = {
+        let new_value = new_value.clone();
+        Callback::from(move |event: Event| {
+            if let Some(value) = input_value(event) {
+                new_value.set(value);
+            }
+        })
+    };
+
+    let on_save = {
+        let widget_name = props.widget_name.clone();
+        let new_name = new_name.clone();
+        let new_value = new_value.clone();
+        let revision = revision.clone();
+        Callback::from(move |_: MouseEvent| {
+            if new_name.is_empty() {
+                return;
+            }
+            let url = format!("/secrets/{}/{}", widget_name, *new_name);
+            let value = (*new_value).clone();
+            let new_name = new_name.clone();
+            let new_value = new_value.clone();
+            let revision = revision.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                let request = Request::post(&url).body(value);
+                let Ok(request) = request else {
+                    return;
+                };
+                if request.send().await.is_ok() {
+                    new_name.set(String::new());
+                    new_value.set(String::new());
+                    revision.set(*revision + 1);
+                }
+            });
+        })
+    };
+
+    let on_delete = {
+        let widget_name = props.widget_name.clone();
+        let revision = revision.clone();
+        Callback::from(move |secret_name: String| {
+            let url = format!("/secrets/{}/{}", widget_name, secret_name);
+            let revision = revision.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                if Request::delete(&url).send().await.is_ok() {
+                    revision.set(*revision + 1);
+                }
+            });
+        })
+    };
+
+    html! {
+        <div class="pt-2">
+            <div class="text-white text-sm font-medium">{"Secrets"}</div>
+            <ul class="text-slate-300 text-sm">
+                { for secret_names.iter().map(|name| html! {
+                    <li class="flex flex-row justify-between gap-2">
+                        <span>{name.clone()}</span>
+                        <button class="text-gray-300 text-sm font-semibold" onclick={on_delete.reform({
+                            let name = name.clone();
+                            move |_: MouseEvent| name.clone()
+                        })}>{"Delete"}</button>
+                    </li>
+                })}
+            </ul>
+            <div class="flex flex-row gap-2 pt-1">
+                <input type="text" placeholder="Name" class="bg-transparent text-slate-300 text-sm" value={(*new_name).clone()} onchange={on_name_changed}/>
+                <input type="password" placeholder="Value" class="bg-transparent text-slate-300 text-sm" value={(*new_value).clone()} onchange={on_value_changed}/>
+                <button class="text-gray-300 text-sm font-semibold" onclick={on_save}>{"Save"}</button>
+            </div>
+        </div>
+    }
+}
@@ -1,22 +1,76 @@
-use common::models::{SystemConfiguration, SystemConfigurationAction};
+use std::collections::HashMap;
+
+use common::models::{
+    RateLimit, SystemConfiguration, SystemConfigurationAction, WidgetHealth, WidgetPermissions,
+};
 use gloo_console::log;
 use gloo_net::http::Request;
 use wasm_bindgen::JsCast;
-use web_sys::{HtmlButtonElement, MouseEvent};
-use yew::{function_component, html, use_effect_with_deps, use_reducer, use_state, Callback, Html};
+use web_sys::{Event, HtmlButtonElement, HtmlInputElement, MouseEvent};
+use yew::{
+    function_component, html, use_effect_with_deps, use_reducer, use_state, Callback, Html,
+    UseStateHandle,
+};
 use yew_feather::PlusCircle;
+use yew_hooks::use_interval;
 use yew_router::prelude::Link;
 
 use crate::components::background_color_config::BackgroundColorConfigComponent;
 use crate::components::config_card::ConfigCardComponent;
+use crate::components::config_file::ConfigFileComponent;
+use crate::components::config_history::ConfigHistoryComponent;
+use crate::components::config_transfer::ConfigTransferComponent;
 use crate::components::divider::DividerComponent;
 use crate::components::error_display::ErrorDisplay;
+use crate::components::http_cache_config::HttpCacheConfigComponent;
+use crate::components::location_config::LocationConfigComponent;
+use crate::components::permission_review::PermissionReviewComponent;
+use crate::components::persistence_problems::PersistenceProblemsComponent;
+use crate::components::random_seed_notice::RandomSeedNoticeComponent;
+use crate::components::widget_health::WidgetHealthComponent;
+use crate::components::widget_logs::WidgetLogsComponent;
+use crate::components::widget_rate_limit::WidgetRateLimitComponent;
+use crate::components::widget_secrets::WidgetSecretsComponent;
 use crate::routing::router::Route;
 
+/// Interval in which the health of the widgets is refreshed
+const STATUS_REFRESH_INTERVAL_MS: u32 = 10_000;
+
+/// Fetches the health of all widgets
+fn fetch_widgets_status(widgets_status: UseStateHandle<HashMap<String, WidgetHealth>>) {
+    wasm_bindgen_futures::spawn_local(async move {
+        let response = Request::get("/widgets/status").send().await;
+        let Ok(response) = response else {
+            return;
+        };
+        if let Ok(status) = response.json::<HashMap<String, WidgetHealth>>().await {
+            widgets_status.set(status);
+        }
+    });
+}
+
 #[function_component(Home)]
 pub fn home() -> Html {
     let system_config = use_reducer(SystemConfiguration::default);
     let error = use_state(|| None as Option<String>);
+    let widgets_status = use_state(HashMap::<String, WidgetHealth>::new);
+
+    {
+        // Fetch the health right away and then periodically
+        let widgets_status_clone = widgets_status.clone();
+        use_effect_with_deps(
+            move |_| {
+                fetch_widgets_status(widgets_status_clone);
+                || {}
+            },
+            (),
+        );
+        let widgets_status = widgets_status.clone();
+        use_interval(
+            move || fetch_widgets_status(widgets_status.clone()),
+            STATUS_REFRESH_INTERVAL_MS,
+        );
+    }
 
     {
         // Only runs on first render
@@ -74,6 +128,61 @@ pub fn home() -> Html {
         })
     };
 
+    let on_toggle_scratch_directory = {
+        let system_config = system_config.clone();
+        let error = error.clone();
+        Callback::from(move |(widget_name, event): (String, Event)| {
+            let input = event
+                .target()
+                .and_then(|t| t.dyn_into::<HtmlInputElement>().ok());
+            let Some(input) = input else {
+                return;
+            };
+            let enabled = input.checked();
+            let system_config = system_config.clone();
+            let error = error.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                let url = format!("/widgets/{}/scratch_directory", widget_name);
+                let Ok(request) = Request::post(&url).json(&enabled) else {
+                    return;
+                };
+                match request.send().await {
+                    Ok(response) if response.ok() => {
+                        system_config.dispatch(SystemConfigurationAction::SetScratchDirectory {
+                            widget_name,
+                            enabled,
+                        });
+                    }
+                    _ => {
+                        // Show the stored state again
+                        input.set_checked(!enabled);
+                        error.set(Some("Failed to change the scratch directory".to_string()));
+                    }
+                }
+            });
+        })
+    };
+
+    let on_rate_limit_saved = {
+        let system_config = system_config.clone();
+        Callback::from(move |(widget_name, rate_limit): (String, Option<RateLimit>)| {
+            system_config.dispatch(SystemConfigurationAction::SetHttpRateLimit {
+                widget_name,
+                rate_limit,
+            });
+        })
+    };
+
+    let on_permissions_approved = {
+        let system_config = system_config.clone();
+        Callback::from(move |(widget_name, permissions): (String, WidgetPermissions)| {
+            system_config.dispatch(SystemConfigurationAction::ApprovePermissions {
+                widget_name,
+                permissions,
+            });
+        })
+    };
+
     html! {
         <div class="h-full">
             <meta name="viewport" content="width=device-width initial-scale=1.0"/>
@@ -84,6 +193,9 @@ pub fn home() -> Html {
                     <div>
                         // Image
                         <img src="assets/logo.png" alt="" class="h-24 object-contain py-4"/>
+                        <PersistenceProblemsComponent/>
+                        <RandomSeedNoticeComponent/>
+                        <ConfigFileComponent/>
                         // Content
                         <div>
                             <DividerComponent text="General"/>
@@ -92,6 +204,22 @@ pub fn home() -> Html {
                                 config={system_config.clone()}
                             />
 
+                            <LocationConfigComponent
+                                config={system_config.clone()}
+                            />
+
+                            <HttpCacheConfigComponent
+                                config={system_config.clone()}
+                            />
+
+                            <ConfigTransferComponent
+                                config={system_config.clone()}
+                            />
+
+                            <ConfigHistoryComponent
+                                config={system_config.clone()}
+                            />
+
 
                             <DividerComponent text="Widgets"/>
 
@@ -102,9 +230,32 @@ pub fn home() -> Html {
                                             <div class="text-white text-lg font-bold pb-1">{widget.name.clone()}</div>
                                             <div class="text-slate-400 text-sm pb-1">{widget.version.clone()}</div>
                                             <div class="text-slate-300 text-sm">{widget.description.clone()}</div>
+                                            <WidgetHealthComponent health={widgets_status.get(&widget.name).cloned()}/>
+                                            if widget.reinstall_required {
+                                                <div class="text-red-400 text-sm">{"Compiled by a previous version of WG Display and could not be recompiled. Install the widget again to display it."}</div>
+                                            }
+                                            if widget.approval_required {
+                                                <PermissionReviewComponent widget_name={widget.name.clone()} on_approved={on_permissions_approved.reform({
+                                                    let widget_name = widget.name.clone();
+                                                    move |permissions: WidgetPermissions| (widget_name.clone(), permissions)
+                                                })}/>
+                                            }
+                                            <label class="text-slate-300 text-sm flex flex-row items-center gap-2">
+                                                <input type="checkbox" checked={widget.wasi_grants.scratch_directory} onchange={on_toggle_scratch_directory.reform({
+                                                    let widget_name = widget.name.clone();
+                                                    move |event: Event| (widget_name.clone(), event)
+                                                })}/>
+                                                {"Scratch directory"}
+                                            </label>
+                                            <WidgetRateLimitComponent widget_name={widget.name.clone()} rate_limit={widget.http_rate_limit.clone()} on_saved={on_rate_limit_saved.reform({
+                                                let widget_name = widget.name.clone();
+                                                move |rate_limit: Option<RateLimit>| (widget_name.clone(), rate_limit)
+                                            })}/>
+                                            <WidgetSecretsComponent widget_name={widget.name.clone()}/>
+                                            <WidgetLogsComponent widget_name={widget.name.clone()}/>
                                             <button value={widget.name.clone()} onclick={on_deinstall_widget.clone()} class="text-gray-300 text-sm font-semibold">{"Deinstall"}</button>
                                             <br/>
-                                            <a href={format!("/widget_configuration/{}", widget.name.clone())} class="text-gray-300 text-sm font-semibold">{{"Configure widget"}}</a>
+                                            <Link<Route> to={Route::WidgetConfig { widget_name: widget.name.clone() }} classes="text-gray-300 text-sm font-semibold">{"Configure widget"}</Link<Route>>
                                         </ConfigCardComponent>
                                     }
                                 }) }
@@ -1,7 +1,10 @@
 use crate::components::{
     config_card::ConfigCardComponent, divider::DividerComponent, error_display::ErrorDisplay,
+    permission_approval::PermissionApprovalComponent, store_item::StoreItemComponent,
+};
+use common::models::{
+    InstallAction, InstallRequest, InstallResponse, WidgetPermissions, WidgetStoreItem,
 };
-use common::models::{InstallAction, WidgetStoreItem};
 use gloo_console::log;
 use gloo_net::http::Request;
 use wasm_bindgen::JsCast;
@@ -35,17 +38,26 @@ async fn load_store_items(
     }
 }
 
-async fn install_widget(
+/// An installation waiting for the user to approve the permissions the widget requests
+#[derive(Clone, PartialEq)]
+struct PendingApproval {
     action: InstallAction,
+    widget_name: String,
+    permissions: WidgetPermissions,
+}
+
+async fn install_widget(
+    request: InstallRequest,
     error: UseStateHandle<Option<String>>,
     is_installing: UseStateHandle<bool>,
     navigator: Navigator,
     widget_store_items: UseStateHandle<Vec<WidgetStoreItem>>,
+    pending_approval: UseStateHandle<Option<PendingApproval>>,
 ) {
     is_installing.set(true);
     let response = Request::post("/install_widget")
-        .json(&action)
-        .expect("Failed to serialize install action")
+        .json(&request)
+        .expect("Failed to serialize install request")
         .send()
         .await;
     is_installing.set(false);
@@ -56,11 +68,28 @@ async fn install_widget(
             log!("Failed to install widget");
         }
         Ok(response) => match response.status() {
-            200 => {
-                log!("Successfully installed widget");
-                load_store_items(widget_store_items, error).await;
-                navigator.push(&crate::Route::Home);
-            }
+            200 => match response.json::<InstallResponse>().await {
+                Ok(InstallResponse::Installed) => {
+                    log!("Successfully installed widget");
+                    pending_approval.set(None);
+                    load_store_items(widget_store_items, error).await;
+                    navigator.push(&crate::Route::Home);
+                }
+                Ok(InstallResponse::ApprovalRequired {
+                    widget_name,
+                    permissions,
+                }) => {
+                    log!("Widget requests permissions");
+                    pending_approval.set(Some(PendingApproval {
+                        action: request.action,
+                        widget_name,
+                        permissions,
+                    }));
+                }
+                Err(e) => {
+                    error.set(Some(format!("Failed to deserialize install response: {}", e)));
+                }
+            },
             _ => {
                 let response_text = response.text().await;
                 let error_text = response_text.unwrap_or("No error message".to_string());
@@ -77,6 +106,9 @@ pub fn install() -> Html {
     let widget_store_items = use_state(Vec::<WidgetStoreItem>::default);
     let error = use_state(|| None as Option<String>);
     let is_installing = use_state(|| false);
+    let search = use_state(String::new);
+    let selected_tag = use_state(|| None as Option<String>);
+    let pending_approval = use_state(|| None as Option<PendingApproval>);
     let navigator = use_navigator().unwrap();
 
     {
@@ -109,15 +141,28 @@ pub fn install() -> Html {
         let is_installing = is_installing.clone();
         let navigator = navigator.clone();
         let widget_store_items = widget_store_items.clone();
+        let pending_approval = pending_approval.clone();
         Callback::from(move |_| {
             if let Some(action) = &*installation_data {
                 let error = error.clone();
                 let is_installing = is_installing.clone();
                 let navigator = navigator.clone();
                 let widget_store_items = widget_store_items.clone();
-                let action = action.clone();
+                let pending_approval = pending_approval.clone();
+                let request = InstallRequest {
+                    action: action.clone(),
+                    granted_permissions: None,
+                };
                 wasm_bindgen_futures::spawn_local(async move {
-                    install_widget(action, error, is_installing, navigator, widget_store_items).await;
+                    install_widget(
+                        request,
+                        error,
+                        is_installing,
+                        navigator,
+                        widget_store_items,
+                        pending_approval,
+                    )
+                    .await;
                 });
             }
         })
@@ -128,6 +173,7 @@ pub fn install() -> Html {
         let is_installing = is_installing.clone();
         let navigator = navigator.clone();
         let widget_store_items = widget_store_items.clone();
+        let pending_approval = pending_approval.clone();
         Callback::from(move |event: MouseEvent| {
             let value = event
                 .target()
@@ -139,13 +185,49 @@ pub fn install() -> Html {
                 let is_installing = is_installing.clone();
                 let navigator = navigator.clone();
                 let widget_store_items = widget_store_items.clone();
+                let pending_approval = pending_approval.clone();
+                wasm_bindgen_futures::spawn_local(async move {
+                    install_widget(
+                        InstallRequest {
+                            action: InstallAction::FromStoreItemName(value),
+                            granted_permissions: None,
+                        },
+                        error,
+                        is_installing,
+                        navigator,
+                        widget_store_items,
+                        pending_approval,
+                    )
+                    .await;
+                });
+            }
+        })
+    };
+
+    let on_approve_permissions = {
+        let error = error.clone();
+        let is_installing = is_installing.clone();
+        let navigator = navigator.clone();
+        let widget_store_items = widget_store_items.clone();
+        let pending_approval = pending_approval.clone();
+        Callback::from(move |_: MouseEvent| {
+            if let Some(pending) = (*pending_approval).clone() {
+                let error = error.clone();
+                let is_installing = is_installing.clone();
+                let navigator = navigator.clone();
+                let widget_store_items = widget_store_items.clone();
+                let pending_approval = pending_approval.clone();
                 wasm_bindgen_futures::spawn_local(async move {
                     install_widget(
-                        InstallAction::FromStoreItemName(value),
+                        InstallRequest {
+                            action: pending.action,
+                            granted_permissions: Some(pending.permissions),
+                        },
                         error,
                         is_installing,
                         navigator,
                         widget_store_items,
+                        pending_approval,
                     )
                     .await;
                 });
@@ -153,6 +235,11 @@ pub fn install() -> Html {
         })
     };
 
+    let on_cancel_approval = {
+        let pending_approval = pending_approval.clone();
+        Callback::from(move |_: MouseEvent| pending_approval.set(None))
+    };
+
     let on_deinstall_widget = {
         let error = error.clone();
         let is_installing = is_installing.clone();
@@ -197,6 +284,45 @@ pub fn install() -> Html {
         })
     };
 
+    let on_search = {
+        let search = search.clone();
+        move |event: InputEvent| {
+            let input = event
+                .target()
+                .and_then(|t| t.dyn_into::<HtmlInputElement>().ok());
+            if let Some(input) = input {
+                search.set(input.value());
+            }
+        }
+    };
+
+    let on_select_tag = {
+        let selected_tag = selected_tag.clone();
+        Callback::from(move |tag: String| {
+            if selected_tag.as_deref() == Some(tag.as_str()) {
+                selected_tag.set(None);
+            } else {
+                selected_tag.set(Some(tag));
+            }
+        })
+    };
+
+    let tags = {
+        let mut tags: Vec<String> = widget_store_items
+            .iter()
+            .flat_map(|item| item.tags.iter().map(|tag| tag.to_lowercase()))
+            .collect();
+        tags.sort();
+        tags.dedup();
+        tags
+    };
+
+    let visible_items: Vec<&WidgetStoreItem> = widget_store_items
+        .iter()
+        .filter(|item| item.matches_search(&search))
+        .filter(|item| selected_tag.as_ref().is_none_or(|tag| item.has_tag(tag)))
+        .collect();
+
     html! {
         <div class="h-full">
             <meta name="viewport" content="width=device-width initial-scale=1.0"/>
@@ -213,6 +339,15 @@ pub fn install() -> Html {
                                 <span class="text-black text-sm">{"Installing widget..."}</span>
                             </div>
                         }
+                        if let Some(pending) = &*pending_approval {
+                            <PermissionApprovalComponent
+                                widget_name={pending.widget_name.clone()}
+                                permissions={pending.permissions.clone()}
+                                on_approve={on_approve_permissions}
+                                on_cancel={on_cancel_approval}
+                                disabled={*is_installing}
+                            />
+                        }
                         // Content
                         <div>
                             <DividerComponent text="Install from URL"/>
@@ -229,28 +364,34 @@ pub fn install() -> Html {
 
                                 if widget_store_items.is_empty() {
                                     <p class="text-center text-sm">{"Store items could not be loaded"}</p>
+                                } else {
+                                    <input type="search" oninput={on_search} value={(*search).clone()} class="w-full rounded-sm pl-2 mb-2" placeholder="Search widgets"/>
+                                    <div class="flex flex-row flex-wrap gap-1">
+                                        { for tags.into_iter().map(|tag| {
+                                            let class = if selected_tag.as_deref() == Some(tag.as_str()) {
+                                                "bg-zinc-700 text-white text-xs rounded px-2 py-1"
+                                            } else {
+                                                "bg-zinc-400 text-black text-xs rounded px-2 py-1"
+                                            };
+                                            let label = tag.clone();
+                                            html! {
+                                                <button {class} onclick={on_select_tag.reform(move |_| tag.clone())}>{label}</button>
+                                            }
+                                        })}
+                                    </div>
+                                    if visible_items.is_empty() {
+                                        <p class="text-center text-sm pt-2">{"No widgets match the filter"}</p>
+                                    }
                                 }
 
-                                { for widget_store_items.iter().map(|item| {
+                                { for visible_items.into_iter().map(|item| {
                                     html! {
-                                        <ConfigCardComponent>
-                                            <div class="flex flex-col">
-                                                <div class="flex flex-row justify-between">
-                                                    <div class="flex flex-col pr-4">
-                                                        <span class="text-slate-300 text-sm font-semibold"> {&item.name} </span>
-                                                        <span class="text-slate-300 text-xs"> {&item.description} </span>
-                                                    </div>
-                                                    <div class="flex flex-row gap-2">
-                                                        <button class="pt-2 text-gray-300 text-sm font-semibold" value={item.name.clone()} onclick={on_deinstall_widget.clone()} disabled={*is_installing}>
-                                                            {"Deinstall"}
-                                                        </button>
-                                                        <button class="pt-2 text-gray-300 text-sm font-semibold" value={item.name.clone()} onclick={on_install_widget.clone()} disabled={*is_installing}>
-                                                            {"Install"}
-                                                        </button>
-                                                    </div>
-                                                </div>
-                                            </div>
-                                        </ConfigCardComponent>
+                                        <StoreItemComponent
+                                            item={item.clone()}
+                                            on_install={on_install_widget.clone()}
+                                            on_deinstall={on_deinstall_widget.clone()}
+                                            disabled={*is_installing}
+                                        />
                                     }
                                 })}
                         </div>
@@ -1,3 +1,4 @@
 pub mod config_schema;
 pub mod home;
 pub mod install;
+pub mod widget_config;
@@ -0,0 +1,269 @@
+use std::rc::Rc;
+
+use common::config_schema::{fill_defaults, normalize, validate};
+use common::models::{ConfigFieldError, ConfigFileStatus, SystemConfiguration, TestRunReport};
+use gloo_net::http::Request;
+use serde_json::{Map, Value};
+use yew::prelude::*;
+use yew_feather::Loader;
+use yew_router::prelude::Link;
+
+use crate::components::config_card::ConfigCardComponent;
+use crate::components::divider::DividerComponent;
+use crate::components::error_display::ErrorDisplay;
+use crate::components::schema_form::SchemaFieldComponent;
+use crate::components::test_run_report::TestRunReportComponent;
+use crate::routing::router::Route;
+
+#[derive(Properties, PartialEq)]
+pub struct Props {
+    pub widget_name: AttrValue,
+}
+
+/// What the configuration is sent to the server for
+#[derive(Clone, Copy, PartialEq)]
+enum FormAction {
+    Save,
+    /// Run the widget once with the configuration without saving it
+    Test,
+}
+
+/// What the form is loaded from
+#[derive(Clone, PartialEq)]
+struct LoadedForm {
+    schema: Value,
+    /// Whether the configuration file sets the configuration, saving it here has no effect then
+    managed_by_file: bool,
+}
+
+/// Loads the schema of the widget and its current configuration, prefilled with the defaults of the schema
+async fn load_form(widget_name: &str) -> Result<(LoadedForm, Value), String> {
+    let response = Request::get(&format!("/config_schema/{}", widget_name))
+        .send()
+        .await
+        .map_err(|err| format!("Failed to load config schema: {}", err))?;
+    if !response.ok() {
+        return Err(format!(
+            "Could not load the config schema of {}, is it installed?",
+            widget_name
+        ));
+    }
+    let schema = response
+        .text()
+        .await
+        .map_err(|err| format!("Failed to load config schema: {}", err))?;
+    let schema: Value = serde_json::from_str(&schema)
+        .map_err(|err| format!("The widget returned an invalid config schema: {}", err))?;
+    let schema = normalize(schema);
+
+    let system_config = Request::get("/system_config")
+        .send()
+        .await
+        .map_err(|err| format!("Failed to load system config: {}", err))?
+        .json::<SystemConfiguration>()
+        .await
+        .map_err(|err| format!("Failed to parse system config: {}", err))?;
+    let config = system_config
+        .widgets
+        .iter()
+        .find(|widget| widget.name == widget_name)
+        .and_then(|widget| serde_json::from_str(&widget.json_config).ok())
+        .unwrap_or_else(|| Value::Object(Map::new()));
+
+    let managed_field = format!("widgets.{}.json_config", widget_name);
+    let managed_by_file = match Request::get("/config/file").send().await {
+        Ok(response) => response
+            .json::<Option<ConfigFileStatus>>()
+            .await
+            .ok()
+            .flatten()
+            .is_some_and(|status| status.managed.contains(&managed_field)),
+        Err(_) => false,
+    };
+
+    let config = fill_defaults(&schema, &config);
+    Ok((
+        LoadedForm {
+            schema,
+            managed_by_file,
+        },
+        config,
+    ))
+}
+
+#[function_component(WidgetConfig)]
+pub fn widget_config(props: &Props) -> Html {
+    let form = use_state(|| None as Option<LoadedForm>);
+    let value = use_state(|| Value::Null);
+    let error = use_state(|| None as Option<String>);
+    // Errors reported by the server for the last saved value
+    let server_errors = use_state(Vec::<ConfigFieldError>::new);
+    let busy = use_state(|| None as Option<FormAction>);
+    let saved = use_state(|| false);
+    let test_report = use_state(|| None as Option<TestRunReport>);
+
+    {
+        let widget_name = props.widget_name.clone();
+        let form = form.clone();
+        let value = value.clone();
+        let error = error.clone();
+        use_effect_with_deps(
+            move |_| {
+                wasm_bindgen_futures::spawn_local(async move {
+                    match load_form(&widget_name).await {
+                        Ok((loaded, config)) => {
+                            value.set(config);
+                            form.set(Some(loaded));
+                        }
+                        Err(err) => error.set(Some(err)),
+                    }
+                });
+                || {}
+            },
+            props.widget_name.clone(),
+        );
+    }
+
+    let errors = match &*form {
+        Some(form) => validate(&form.schema, &value),
+        None => vec![],
+    };
+    // Shown until the value is changed again
+    let errors = if errors.is_empty() {
+        (*server_errors).clone()
+    } else {
+        errors
+    };
+
+    let on_change = {
+        let value = value.clone();
+        let error = error.clone();
+        let server_errors = server_errors.clone();
+        let saved = saved.clone();
+        Callback::from(move |new_value: Option<Value>| {
+            value.set(new_value.unwrap_or_else(|| Value::Object(Map::new())));
+            error.set(None);
+            server_errors.set(vec![]);
+            saved.set(false);
+        })
+    };
+
+    let on_submit = {
+        let widget_name = props.widget_name.clone();
+        let value = value.clone();
+        let error = error.clone();
+        let server_errors = server_errors.clone();
+        let busy = busy.clone();
+        let saved = saved.clone();
+        let test_report = test_report.clone();
+        let has_errors = !errors.is_empty();
+        Callback::from(move |action: FormAction| {
+            if has_errors {
+                error.set(Some("Fix the highlighted fields first".to_string()));
+                return;
+            }
+            let url = match action {
+                FormAction::Save => format!("/widget_config/{}", widget_name),
+                FormAction::Test => format!("/widgets/{}/test_run", widget_name),
+            };
+            let body = value.to_string();
+            let error = error.clone();
+            let server_errors = server_errors.clone();
+            let busy = busy.clone();
+            let saved = saved.clone();
+            let test_report = test_report.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                let request = Request::post(&url)
+                    .header("Content-Type", "application/json")
+                    .body(body);
+                let Ok(request) = request else {
+                    return;
+                };
+                busy.set(Some(action));
+                error.set(None);
+                let response = request.send().await;
+                busy.set(None);
+                match response {
+                    Err(err) => error.set(Some(format!("Failed to send config: {}", err))),
+                    Ok(response) => match (response.status(), action) {
+                        (200, FormAction::Save) => saved.set(true),
+                        (200, FormAction::Test) => match response.json::<TestRunReport>().await {
+                            Ok(report) => test_report.set(Some(report)),
+                            Err(err) => {
+                                error.set(Some(format!("Failed to parse test run: {}", err)))
+                            }
+                        },
+                        (422, _) => match response.json::<Vec<ConfigFieldError>>().await {
+                            Ok(errors) => {
+                                error.set(Some("The widget rejected the configuration".to_string()));
+                                server_errors.set(errors);
+                            }
+                            Err(err) => error.set(Some(format!("Failed to parse errors: {}", err))),
+                        },
+                        _ => {
+                            let response_text = response.text().await;
+                            error.set(Some(
+                                response_text.unwrap_or("No error message".to_string()),
+                            ));
+                        }
+                    },
+                }
+            });
+        })
+    };
+
+    let title = format!("Configure {}", props.widget_name);
+
+    html! {
+        <div class="h-full">
+            <meta name="viewport" content="width=device-width initial-scale=1.0"/>
+            <div class="flex flex-col items-center justify-center">
+                // Card
+                <div class="bg-zinc-200 rounded-2xl p-5 m-10 shadow-2xl">
+                    <div class="flex flex-col w-full">
+                        <DividerComponent text={title}/>
+                        if let Some(form) = &*form {
+                            if form.managed_by_file {
+                                <p class="text-sm text-center pb-2">{"This configuration is set by the configuration file, changes made here have no effect."}</p>
+                            }
+                            <ConfigCardComponent>
+                                <SchemaFieldComponent
+                                    schema={form.schema.clone()}
+                                    value={(*value).clone()}
+                                    errors={Rc::new(errors)}
+                                    on_change={on_change}
+                                />
+                            </ConfigCardComponent>
+                            <div class="flex flex-row justify-between items-center">
+                                <Link<Route> to={Route::Home} classes="text-zinc-700 text-sm font-semibold">{"Back"}</Link<Route>>
+                                if *saved {
+                                    <span class="text-green-800 text-sm">{"Configuration saved"}</span>
+                                }
+                                <div class="flex flex-row gap-4">
+                                    { for [(FormAction::Test, "Test"), (FormAction::Save, "Save")].into_iter().map(|(action, label)| html! {
+                                        <button class="text-zinc-700 text-sm font-semibold" onclick={on_submit.reform(move |_: MouseEvent| action)} disabled={busy.is_some()}>
+                                            if *busy == Some(action) {
+                                                <Loader class="animate-spin"/>
+                                            } else {
+                                                {label}
+                                            }
+                                        </button>
+                                    }) }
+                                </div>
+                            </div>
+                            if let Some(report) = &*test_report {
+                                <TestRunReportComponent report={report.clone()}/>
+                            }
+                        } else if error.is_none() {
+                            <div class="flex justify-center items-center">
+                                <Loader class="animate-spin mr-2"/>
+                                <span class="text-black text-sm">{"Loading configuration..."}</span>
+                            </div>
+                        }
+                    </div>
+                </div>
+                <ErrorDisplay error={error.clone()}></ErrorDisplay>
+            </div>
+        </div>
+    }
+}
@@ -1,7 +1,9 @@
 use yew::prelude::*;
 use yew_router::prelude::*;
 
-use crate::pages::{config_schema::ConfigSchema, home::Home, install::Install};
+use crate::pages::{
+    config_schema::ConfigSchema, home::Home, install::Install, widget_config::WidgetConfig,
+};
 
 #[derive(Clone, Routable, PartialEq)]
 pub enum Route {
@@ -11,6 +13,8 @@ pub enum Route {
     Install,
     #[at("/config_schema/:widget_name")]
     ConfigSchema { widget_name: String },
+    #[at("/widget_configuration/:widget_name")]
+    WidgetConfig { widget_name: String },
     #[not_found]
     #[at("/404")]
     NotFound,
@@ -31,6 +35,11 @@ pub fn switch(routes: Route) -> Html {
                 <ConfigSchema widget_name={widget_name} />
             }
         }
+        Route::WidgetConfig { widget_name } => {
+            html! {
+                <WidgetConfig widget_name={widget_name} />
+            }
+        }
         Route::NotFound => html! { <h1>{ "404" }</h1> },
     }
 }
@@ -0,0 +1,27 @@
+;; A minimal widget exporting the functions the store page queries
+(component
+  (core module $widget
+    (memory (export "memory") 1)
+    (data (i32.const 64) "Clock")
+    (data (i32.const 80) "1.2.3")
+    (data (i32.const 96) "{\"type\":\"object\"}")
+    ;; Strings are returned as pointer and length in the return area at 0
+    (func $return_string (param $ptr i32) (param $len i32) (result i32)
+      (i32.store (i32.const 0) (local.get $ptr))
+      (i32.store (i32.const 4) (local.get $len))
+      (i32.const 0))
+    (func (export "get-name") (result i32)
+      (call $return_string (i32.const 64) (i32.const 5)))
+    (func (export "get-version") (result i32)
+      (call $return_string (i32.const 80) (i32.const 5)))
+    (func (export "get-config-schema") (result i32)
+      (call $return_string (i32.const 96) (i32.const 17)))
+  )
+  (core instance $instance (instantiate $widget))
+  (func (export "get-name") (result string)
+    (canon lift (core func $instance "get-name") (memory $instance "memory")))
+  (func (export "get-version") (result string)
+    (canon lift (core func $instance "get-version") (memory $instance "memory")))
+  (func (export "get-config-schema") (result string)
+    (canon lift (core func $instance "get-config-schema") (memory $instance "memory")))
+)
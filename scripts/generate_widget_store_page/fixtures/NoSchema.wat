@@ -0,0 +1,16 @@
+;; A widget that does not export get-config-schema
+(component
+  (core module $widget
+    (memory (export "memory") 1)
+    (data (i32.const 64) "NoSchema")
+    (func (export "get-string") (result i32)
+      (i32.store (i32.const 0) (i32.const 64))
+      (i32.store (i32.const 4) (i32.const 8))
+      (i32.const 0))
+  )
+  (core instance $instance (instantiate $widget))
+  (func (export "get-name") (result string)
+    (canon lift (core func $instance "get-string") (memory $instance "memory")))
+  (func (export "get-version") (result string)
+    (canon lift (core func $instance "get-string") (memory $instance "memory")))
+)
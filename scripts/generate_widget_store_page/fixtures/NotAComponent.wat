@@ -0,0 +1,4 @@
+;; A core module instead of a component
+(module
+  (func (export "get-name"))
+)
@@ -0,0 +1,20 @@
+;; A widget whose get-version traps
+(component
+  (core module $widget
+    (memory (export "memory") 1)
+    (data (i32.const 64) "Trapping")
+    (func (export "get-name") (result i32)
+      (i32.store (i32.const 0) (i32.const 64))
+      (i32.store (i32.const 4) (i32.const 8))
+      (i32.const 0))
+    (func (export "trap") (result i32)
+      unreachable)
+  )
+  (core instance $instance (instantiate $widget))
+  (func (export "get-name") (result string)
+    (canon lift (core func $instance "get-name") (memory $instance "memory")))
+  (func (export "get-version") (result string)
+    (canon lift (core func $instance "trap") (memory $instance "memory")))
+  (func (export "get-config-schema") (result string)
+    (canon lift (core func $instance "trap") (memory $instance "memory")))
+)
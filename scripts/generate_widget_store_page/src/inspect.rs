@@ -0,0 +1,137 @@
+//! Fetches widgets of the store and checks that they implement the widget world
+use std::{fs, path::PathBuf};
+
+use anyhow::{bail, Context, Result};
+use common::models::WidgetStoreItem;
+use wasmtime::component::{types::ComponentItem, Component, Instance, Linker};
+use wasmtime::{Config, Engine, Store};
+
+/// Exports every widget has to provide to be installable
+const REQUIRED_EXPORTS: [&str; 3] = ["get-name", "get-version", "get-config-schema"];
+
+/// Information reported by the widget itself
+pub struct WidgetInfo {
+    pub name: String,
+    pub version: String,
+    pub config_schema: String,
+}
+
+pub struct Inspector {
+    engine: Engine,
+    fixtures: Option<PathBuf>,
+}
+
+impl Inspector {
+    /// # Arguments
+    /// * `fixtures` - Directory containing a `<store item name>.wasm` or `.wat` file per widget.
+    ///   If given, widgets are loaded from there instead of being downloaded.
+    pub fn new(fixtures: Option<PathBuf>) -> Result<Self> {
+        let mut config = Config::new();
+        config.wasm_component_model(true);
+        Ok(Self {
+            engine: Engine::new(&config)?,
+            fixtures,
+        })
+    }
+
+    /// Load a widget, compile it and query its name, version and configuration schema
+    /// # Arguments
+    /// * `item` - The store item of the widget
+    /// # Returns
+    /// The information reported by the widget or an error if it is not a valid widget
+    pub fn inspect(&self, item: &WidgetStoreItem) -> Result<WidgetInfo> {
+        let bytes = self.load(item)?;
+        let component =
+            Component::new(&self.engine, &bytes).context("Widget is not a valid component")?;
+
+        let exports: Vec<String> = component
+            .component_type()
+            .exports(&self.engine)
+            .filter(|(_, item)| matches!(item, ComponentItem::ComponentFunc(_)))
+            .map(|(name, _)| name.to_string())
+            .collect();
+        for export in REQUIRED_EXPORTS {
+            if !exports.iter().any(|name| name == export) {
+                bail!("Widget does not export {}", export);
+            }
+        }
+
+        // The queried exports must not depend on the host API, so all imports can trap
+        let mut linker = Linker::new(&self.engine);
+        linker.define_unknown_imports_as_traps(&component)?;
+        let mut store = Store::new(&self.engine, ());
+        let instance = linker.instantiate(&mut store, &component)?;
+
+        Ok(WidgetInfo {
+            name: call_string_export(&mut store, &instance, "get-name")?,
+            version: call_string_export(&mut store, &instance, "get-version")?,
+            config_schema: call_string_export(&mut store, &instance, "get-config-schema")?,
+        })
+    }
+
+    fn load(&self, item: &WidgetStoreItem) -> Result<Vec<u8>> {
+        match &self.fixtures {
+            Some(fixtures) => {
+                let mut path = fixtures.join(format!("{}.wasm", item.name));
+                // Components in the text format are compiled as well, e.g. the fixtures of the tests
+                if !path.exists() {
+                    path.set_extension("wat");
+                }
+                fs::read(&path).with_context(|| format!("Could not read {}", path.display()))
+            }
+            None => {
+                let url = item.get_download_url();
+                let response = reqwest::blocking::get(&url)
+                    .and_then(|response| response.error_for_status())
+                    .with_context(|| format!("Could not download {}", url))?;
+                Ok(response.bytes()?.to_vec())
+            }
+        }
+    }
+}
+
+fn call_string_export(store: &mut Store<()>, instance: &Instance, name: &str) -> Result<String> {
+    let func = instance
+        .get_typed_func::<(), (String,)>(&mut *store, name)
+        .with_context(|| format!("Export {} has an unexpected signature", name))?;
+    let (result,) = func
+        .call(&mut *store, ())
+        .with_context(|| format!("Calling {} failed", name))?;
+    func.post_return(&mut *store)?;
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inspect(name: &str) -> Result<WidgetInfo> {
+        let fixtures = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("fixtures");
+        let inspector = Inspector::new(Some(fixtures)).unwrap();
+        inspector.inspect(&WidgetStoreItem::new(
+            name.to_string(),
+            "A widget".to_string(),
+            format!("https://github.com/eliabieri/{}", name),
+        ))
+    }
+
+    fn error(name: &str) -> String {
+        format!("{:#}", inspect(name).err().unwrap())
+    }
+
+    #[test]
+    fn test_inspect() {
+        let info = inspect("Clock").unwrap();
+        assert_eq!(info.name, "Clock");
+        assert_eq!(info.version, "1.2.3");
+        assert_eq!(info.config_schema, "{\"type\":\"object\"}");
+    }
+
+    #[test]
+    fn test_inspect_invalid_widgets() {
+        assert!(error("NoSchema").contains("does not export get-config-schema"));
+        assert!(error("Trapping").contains("Calling get-version failed"));
+        assert!(error("NotAComponent").contains("not a valid component"));
+        assert!(error("Missing").contains("Could not read"));
+    }
+}
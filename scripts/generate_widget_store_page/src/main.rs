@@ -1,35 +1,123 @@
+//! Generates the widget store page from `widget_store.json`.
+//!
+//! Usage: `cargo run -- [--validate] [--fetch] [--fixtures <dir>] [--store <path>]`
+//! - `--validate` checks the listing and exits with an error if it is broken, without generating the page
+//! - `--fetch` downloads every widget, checks that it implements the widget world
+//!   and shows its version and configuration schema on the page
+//! - `--fixtures <dir>` loads the widgets from `<dir>/<store item name>.wasm` (or `.wat`) instead of downloading them.
+//!   Implies `--fetch`
+//! - `--store <path>` reads the listing from the given path
 use std::{
     fs::{self, File},
     io::Write,
+    path::PathBuf,
+    process::ExitCode,
 };
 
-use build_html::*;
-use serde::Deserialize;
+use common::models::WidgetStoreItem;
 
-#[derive(Deserialize)]
-struct WidgetStoreItem {
-    name: String,
-    description: String,
-    repository: String,
+use crate::inspect::Inspector;
+
+mod inspect;
+mod page;
+mod validate;
+
+struct Args {
+    validate: bool,
+    fetch: bool,
+    fixtures: Option<PathBuf>,
+    store: PathBuf,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = Args {
+        validate: false,
+        fetch: false,
+        fixtures: None,
+        store: PathBuf::from("../../widget_store.json"),
+    };
+    let mut raw_args = std::env::args().skip(1);
+    while let Some(arg) = raw_args.next() {
+        match arg.as_str() {
+            "--validate" => args.validate = true,
+            "--fetch" => args.fetch = true,
+            "--fixtures" => {
+                let dir = raw_args.next().ok_or("--fixtures needs a directory")?;
+                args.fixtures = Some(PathBuf::from(dir));
+                args.fetch = true;
+            }
+            "--store" => {
+                let path = raw_args.next().ok_or("--store needs a path")?;
+                args.store = PathBuf::from(path);
+            }
+            other => return Err(format!("Unknown argument {}", other)),
+        }
+    }
+    Ok(args)
 }
 
-fn main() {
-    let data = fs::read_to_string("../../widget_store.json").expect("Unable to read file");
-    let widget_store_items: Vec<WidgetStoreItem> =
-        serde_json::from_str(&data).expect("Could not parse widget_store JSON file");
+fn main() -> ExitCode {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("{}", err);
+            return ExitCode::FAILURE;
+        }
+    };
 
-    let mut table = Table::new();
-    table.add_header_row(&["Name", "Description", "Repository"]);
+    let data = fs::read_to_string(&args.store).expect("Unable to read file");
+    let widget_store_items = match serde_json::from_str::<Vec<WidgetStoreItem>>(&data) {
+        Ok(items) => items,
+        Err(err) => {
+            eprintln!("Could not parse widget_store JSON file: {}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut errors = validate::validate_items(&widget_store_items);
+
+    let inspector = if args.fetch {
+        Some(Inspector::new(args.fixtures).expect("Could not create WebAssembly engine"))
+    } else {
+        None
+    };
+    let mut items = Vec::new();
     for item in widget_store_items {
-        table.add_body_row(&[item.name, item.description, item.repository]);
+        let info = inspector.as_ref().and_then(|inspector| {
+            println!("Checking widget {}", item.name);
+            match inspector.inspect(&item) {
+                Ok(info) => {
+                    if info.name != item.name {
+                        println!("Warning: {} reports its name as {}", item.name, info.name);
+                    }
+                    Some(info)
+                }
+                Err(err) => {
+                    errors.push(format!("{}: {:#}", item.name, err));
+                    None
+                }
+            }
+        });
+        items.push((item, info));
     }
 
-    let html: String = HtmlPage::new()
-        .with_title("WG Display Widget Store")
-        .with_table(table)
-        .to_html_string();
+    if !errors.is_empty() {
+        eprintln!("The widget store listing has {} problem(s):", errors.len());
+        for error in &errors {
+            eprintln!("- {}", error);
+        }
+        if args.validate {
+            return ExitCode::FAILURE;
+        }
+    }
+    if args.validate {
+        println!("The widget store listing is valid");
+        return ExitCode::SUCCESS;
+    }
 
+    let html = page::render(&items);
     let file = File::create("widget_store_page.html").unwrap();
     let mut writer = std::io::BufWriter::new(file);
     writer.write_all(html.as_bytes()).unwrap();
+    ExitCode::SUCCESS
 }
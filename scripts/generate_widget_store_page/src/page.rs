@@ -0,0 +1,72 @@
+//! Renders the store listing as HTML page
+use build_html::*;
+use common::models::WidgetStoreItem;
+
+use crate::inspect::WidgetInfo;
+
+/// Render the store page
+/// # Arguments
+/// * `items` - The store items together with the information reported by the widget, if it was fetched
+/// # Returns
+/// The HTML page
+pub fn render(items: &[(WidgetStoreItem, Option<WidgetInfo>)]) -> String {
+    let mut table = Table::new();
+    table.add_header_row([
+        "Name",
+        "Description",
+        "Version",
+        "Tags",
+        "Author",
+        "Network access",
+        "Repository",
+        "Configuration schema",
+    ]);
+    for (item, info) in items {
+        let network_access = match &item.permissions {
+            None => "Not declared".to_string(),
+            Some(permissions) if permissions.http_hosts.is_empty() => "None".to_string(),
+            Some(permissions) => permissions.http_hosts.join(", "),
+        };
+        let repository = format!("<a href=\"{0}\">{0}</a>", escape(&item.repository));
+        let (version, schema) = match info {
+            Some(info) => (
+                escape(&info.version),
+                format!(
+                    "<details><summary>Show</summary><pre>{}</pre></details>",
+                    escape(&pretty_json(&info.config_schema))
+                ),
+            ),
+            None => ("-".to_string(), "-".to_string()),
+        };
+        table.add_body_row([
+            escape(&item.name),
+            escape(&item.description),
+            version,
+            escape(&item.tags.join(", ")),
+            escape(item.author.as_deref().unwrap_or("-")),
+            escape(&network_access),
+            repository,
+            schema,
+        ]);
+    }
+
+    HtmlPage::new()
+        .with_title("WG Display Widget Store")
+        .with_header(1, "WG Display Widget Store")
+        .with_table(table)
+        .to_html_string()
+}
+
+/// Pretty prints a JSON document, falls back to the raw text if it is not valid JSON
+fn pretty_json(json: &str) -> String {
+    serde_json::from_str::<serde_json::Value>(json)
+        .and_then(|value| serde_json::to_string_pretty(&value))
+        .unwrap_or_else(|_| json.to_string())
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,183 @@
+//! Static checks of the store listing that do not need network access
+use std::collections::HashSet;
+
+use common::models::WidgetStoreItem;
+
+/// Check all items of the store listing
+/// # Arguments
+/// * `items` - The parsed store listing
+/// # Returns
+/// A human readable description of every problem found
+pub fn validate_items(items: &[WidgetStoreItem]) -> Vec<String> {
+    let mut errors = Vec::new();
+    let mut names = HashSet::new();
+
+    for item in items {
+        let context = if item.name.is_empty() {
+            "<unnamed>".to_string()
+        } else {
+            item.name.clone()
+        };
+        let mut report = |message: String| errors.push(format!("{}: {}", context, message));
+
+        if item.name.trim().is_empty() {
+            report("name must not be empty".to_string());
+        }
+        if !names.insert(item.name.to_lowercase()) {
+            report("name is not unique".to_string());
+        }
+        if item.description.trim().is_empty() {
+            report("description must not be empty".to_string());
+        }
+        if let Err(err) = check_repository_url(&item.repository) {
+            report(format!("repository {}", err));
+        }
+
+        let optional_urls = [
+            ("homepage", &item.homepage),
+            ("icon_url", &item.icon_url),
+            ("screenshot_url", &item.screenshot_url),
+        ];
+        for (field, url) in optional_urls {
+            if let Some(url) = url {
+                if !is_http_url(url) {
+                    report(format!("{} '{}' is not an http(s) URL", field, url));
+                }
+            }
+        }
+
+        if item.tags.iter().any(|tag| tag.trim().is_empty()) {
+            report("tags must not be empty".to_string());
+        }
+        if let Some(min_host_version) = &item.min_host_version {
+            if !is_version(min_host_version) {
+                report(format!(
+                    "min_host_version '{}' is not a version",
+                    min_host_version
+                ));
+            }
+        }
+        if let Some(permissions) = &item.permissions {
+            for host in &permissions.http_hosts {
+                if host.is_empty() || host.contains('/') || host.contains(':') {
+                    report(format!("permitted host '{}' must be a bare hostname", host));
+                }
+            }
+        }
+    }
+    errors
+}
+
+/// Widgets are downloaded from the GitHub releases of their repository,
+/// so the repository has to be a GitHub repository URL
+fn check_repository_url(url: &str) -> Result<(), String> {
+    let Some(path) = url.strip_prefix("https://github.com/") else {
+        return Err(format!("'{}' must start with https://github.com/", url));
+    };
+    let segments: Vec<&str> = path.trim_end_matches('/').split('/').collect();
+    if segments.len() != 2 || segments.iter().any(|segment| segment.is_empty()) {
+        return Err(format!(
+            "'{}' must have the form https://github.com/<owner>/<repository>",
+            url
+        ));
+    }
+    Ok(())
+}
+
+fn is_http_url(url: &str) -> bool {
+    let rest = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"));
+    rest.is_some_and(|rest| !rest.is_empty() && !rest.starts_with('/'))
+}
+
+fn is_version(version: &str) -> bool {
+    let version = version.trim_start_matches('v');
+    let parts: Vec<&str> = version.split('.').collect();
+    parts.len() <= 3 && parts.iter().all(|part| part.parse::<u64>().is_ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::models::DeclaredPermissions;
+
+    fn item(name: &str, repository: &str) -> WidgetStoreItem {
+        WidgetStoreItem::new(
+            name.to_string(),
+            "A widget".to_string(),
+            repository.to_string(),
+        )
+    }
+
+    #[test]
+    fn test_valid_listing() {
+        let items = vec![
+            item(
+                "Time",
+                "https://github.com/eliabieri/wg_display_widget_time",
+            ),
+            item(
+                "Aareguru",
+                "https://github.com/eliabieri/wg_display_widget_aareguru/",
+            ),
+        ];
+        assert!(validate_items(&items).is_empty());
+    }
+
+    #[test]
+    fn test_duplicate_names() {
+        let items = vec![
+            item(
+                "Time",
+                "https://github.com/eliabieri/wg_display_widget_time",
+            ),
+            item("time", "https://github.com/someone/time"),
+        ];
+        let errors = validate_items(&items);
+        assert_eq!(errors, vec!["time: name is not unique"]);
+    }
+
+    #[test]
+    fn test_repository_url_shape() {
+        assert!(check_repository_url("https://github.com/owner/repo").is_ok());
+        assert!(check_repository_url("http://github.com/owner/repo").is_err());
+        assert!(check_repository_url("https://gitlab.com/owner/repo").is_err());
+        assert!(check_repository_url("https://github.com/owner").is_err());
+        assert!(check_repository_url("https://github.com/owner/repo/tree/main").is_err());
+    }
+
+    #[test]
+    fn test_optional_fields() {
+        let mut invalid = item(
+            "Time",
+            "https://github.com/eliabieri/wg_display_widget_time",
+        );
+        invalid.homepage = Some("example.com".to_string());
+        invalid.min_host_version = Some("latest".to_string());
+        invalid.permissions = Some(DeclaredPermissions {
+            http_hosts: vec!["https://example.com/api".to_string()],
+        });
+        assert_eq!(validate_items(&[invalid]).len(), 3);
+    }
+
+    #[test]
+    fn test_fixture_listing() {
+        let path =
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/widget_store.json");
+        let data = std::fs::read_to_string(path).unwrap();
+        let items: Vec<WidgetStoreItem> = serde_json::from_str(&data).unwrap();
+        assert_eq!(
+            validate_items(&items),
+            vec![
+                "clock: name is not unique",
+                "clock: description must not be empty",
+                "clock: repository 'https://gitlab.com/someone/clock' must start with https://github.com/",
+                "clock: icon_url 'clock.png' is not an http(s) URL",
+                "clock: tags must not be empty",
+                "clock: min_host_version 'latest' is not a version",
+                "clock: permitted host 'https://worldtimeapi.org/api' must be a bare hostname",
+            ]
+        );
+    }
+}